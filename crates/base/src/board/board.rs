@@ -1,7 +1,11 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
 
 use crate::{
     bitboard::Bitboard,
+    nnue::{accumulator::Accumulator, network::Network},
     r#move::Move,
     square::{constants::*, Square},
     zobrist::{ZobristHash, ZobristHasher},
@@ -12,6 +16,7 @@ use super::{
     error::{
         BoardError, InvalidEnPassant, NotEnoughParts, WrongActiveColor, WrongCastlingAvailibility,
     },
+    outcome::Outcome,
     piece::{Piece, Tile},
 };
 
@@ -26,6 +31,8 @@ pub struct Board {
     midgame: [i32; Color::COUNT],
     endgame: [i32; Color::COUNT],
     gamephase: i32,
+    network: Option<Arc<Network>>,
+    accumulator: Accumulator,
     gamestate: GameState,
     history: Vec<GameState>,
 }
@@ -48,8 +55,12 @@ impl EnPassant {
 #[derive(Debug, Clone)]
 pub struct GameState {
     hash: ZobristHash,
+    pawn_hash: ZobristHash,
     active: Color,
     halfmoves: u16,
+    // The plies since the last null move. Positions before it can not be
+    // repeated, as it is not a legal move.
+    plies_from_null: u16,
     fullmoves: u16,
     en_passant: Option<EnPassant>,
     white_kingside: bool,
@@ -59,6 +70,8 @@ pub struct GameState {
     pinned: Bitboard,
     checkers: Bitboard,
     attacked: Bitboard,
+    check_squares: [Bitboard; Piece::COUNT],
+    discoverers: Bitboard,
     captured_piece: Piece,
 }
 
@@ -66,6 +79,7 @@ impl Default for GameState {
     fn default() -> Self {
         Self {
             hash: ZobristHash::default(),
+            pawn_hash: ZobristHash::default(),
             active: Color::White,
             black_kingside: false,
             white_kingside: false,
@@ -73,10 +87,13 @@ impl Default for GameState {
             white_queenside: false,
             en_passant: None,
             halfmoves: 0,
+            plies_from_null: u16::MAX,
             fullmoves: 0,
             pinned: Bitboard::default(),
             checkers: Bitboard::default(),
             attacked: Bitboard::default(),
+            check_squares: [Bitboard::default(); Piece::COUNT],
+            discoverers: Bitboard::default(),
             captured_piece: Piece::None,
         }
     }
@@ -107,6 +124,8 @@ impl Board {
             midgame: [0; Color::COUNT],
             endgame: [0; Color::COUNT],
             gamephase: 0,
+            network: None,
+            accumulator: Accumulator::default(),
             gamestate: GameState::default(),
             history: Vec::with_capacity(128),
         }
@@ -117,6 +136,11 @@ impl Board {
         self.hasher.hash(self)
     }
 
+    #[inline(always)]
+    pub fn board_pawn_hash(&self) -> ZobristHash {
+        self.hasher.pawn_hash(self)
+    }
+
     #[inline(always)]
     pub fn swap_active(&mut self) {
         self.gamestate.active = self.gamestate.active.other();
@@ -237,6 +261,131 @@ impl Board {
         self.gamestate.attacked = attacked;
         self.gamestate.checkers = checkers;
         self.gamestate.pinned = pinned;
+
+        self.update_check_squares();
+    }
+
+    /// Calculates all the squares the active side can use to check the
+    /// opponent king, as well as the own pieces which would discover a
+    /// check if they move away.
+    fn update_check_squares(&mut self) {
+        let color = self.gamestate.active;
+
+        let king_square = self.get_king_square(color.other());
+        let all_occupied = self.get_all_occupied();
+
+        let bishop_attacks = king_square.get_bishop_attacks(all_occupied);
+        let rook_attacks = king_square.get_rook_attacks(all_occupied);
+
+        let mut check_squares = [Bitboard::default(); Piece::COUNT];
+        check_squares[Piece::Pawn.index()] = king_square.get_pawn_attacks(color.other());
+        check_squares[Piece::Knight.index()] = king_square.get_knight_moves();
+        check_squares[Piece::Bishop.index()] = bishop_attacks;
+        check_squares[Piece::Rook.index()] = rook_attacks;
+        check_squares[Piece::Queen.index()] = bishop_attacks | rook_attacks;
+
+        let queens = self.get_piece_board(color, Piece::Queen);
+        let bishops = self.get_piece_board(color, Piece::Bishop);
+        let rooks = self.get_piece_board(color, Piece::Rook);
+
+        let mut snipers = Bitboard::default();
+        snipers |= king_square.get_bishop_attacks(Bitboard::EMPTY) & (bishops | queens);
+        snipers |= king_square.get_rook_attacks(Bitboard::EMPTY) & (rooks | queens);
+
+        let own_occupied = self.get_occupied(color);
+
+        let mut discoverers = Bitboard::default();
+        for sniper in snipers {
+            let between = sniper.get_between(king_square) & all_occupied;
+            if between.count_ones() == 1 && own_occupied.is_set(between) {
+                discoverers |= between;
+            }
+        }
+
+        self.gamestate.check_squares = check_squares;
+        self.gamestate.discoverers = discoverers;
+    }
+
+    /// Checks if the given move would check the opponent king without
+    /// making the move. This includes direct checks, discovered checks as
+    /// well as the checks of the rook while castling and the discovered
+    /// checks of en passant captures.
+    pub fn gives_check(&self, mov: Move) -> bool {
+        let from = mov.from();
+        let to = mov.to();
+
+        let piece = match self.get_tile(from) {
+            Some(tile) => tile.piece,
+            None => panic!("No piece found at {}", from),
+        };
+
+        let king_square = self.get_king_square(self.other());
+        let all_occupied = self.get_all_occupied();
+
+        if mov.is_promotion() {
+            // The promoted piece can attack through the square of the pawn,
+            // thus we can't use the precalculated check squares.
+            let occupied = (all_occupied ^ from) | to;
+            let promoted = mov.flag().get_promotion_piece();
+            let attacks = match promoted {
+                Piece::Knight => to.get_knight_moves(),
+                Piece::Bishop => to.get_bishop_attacks(occupied),
+                Piece::Rook => to.get_rook_attacks(occupied),
+                Piece::Queen => to.get_bishop_attacks(occupied) | to.get_rook_attacks(occupied),
+                _ => unreachable!(),
+            };
+
+            if attacks.is_set(king_square) {
+                return true;
+            }
+        } else if self.check_squares(piece).is_set(to) {
+            return true;
+        }
+
+        // If the piece is moving away from the line to the king, the slider
+        // behind it is checking the king.
+        let is_discoverer = self.discoverers().is_set(from);
+        if is_discoverer && !king_square.get_line(from).is_set(to) {
+            return true;
+        }
+
+        if mov.is_castling() {
+            let (rook_from, rook_to) = match to {
+                G1 => (H1, F1),
+                C1 => (A1, D1),
+                G8 => (H8, F8),
+                C8 => (A8, D8),
+                _ => panic!("Invalid castling move"),
+            };
+
+            let occupied = (all_occupied ^ from ^ rook_from) | to | rook_to;
+            let attacks = rook_to.get_rook_attacks(occupied);
+            return attacks.is_set(king_square);
+        }
+
+        if mov.is_en_passant() {
+            // Both pawns are leaving their squares, which could discover a
+            // check of a slider on the same rank or diagonal.
+            let to_capture = match self.en_passant() {
+                Some(en_passant) => en_passant.to_capture,
+                None => panic!("No en passant square found"),
+            };
+
+            let occupied = (all_occupied ^ from ^ to_capture) | to;
+
+            let color = self.active();
+            let queens = self.get_piece_board(color, Piece::Queen);
+            let bishops = self.get_piece_board(color, Piece::Bishop);
+            let rooks = self.get_piece_board(color, Piece::Rook);
+
+            let mut attackers = Bitboard::default();
+            attackers |= king_square.get_bishop_attacks(occupied) & (bishops | queens);
+            attackers |= king_square.get_rook_attacks(occupied) & (rooks | queens);
+
+            return !attackers.is_empty();
+        }
+
+        false
     }
 
     pub fn toggle(&mut self, color: Color, piece: Piece, square: Square) {
@@ -260,12 +409,20 @@ impl Board {
             self.midgame[color.index()] -= midgame_value;
             self.endgame[color.index()] -= endgame_value;
             self.gamephase -= gamephase;
+
+            if let Some(network) = &self.network {
+                self.accumulator.remove(network, color, piece, square);
+            }
         } else {
             self.set_piece_type(square, Some(Tile::new(piece, color)));
 
             self.midgame[color.index()] += midgame_value;
             self.endgame[color.index()] += endgame_value;
             self.gamephase += gamephase;
+
+            if let Some(network) = &self.network {
+                self.accumulator.add(network, color, piece, square);
+            }
         }
 
         match color {
@@ -276,6 +433,9 @@ impl Board {
         self.occupied ^= square;
 
         self.gamestate.hash ^= self.hasher.piece_hash(piece, color, square);
+        if piece == Piece::Pawn {
+            self.gamestate.pawn_hash ^= self.hasher.piece_hash(piece, color, square);
+        }
     }
 
     pub fn remove_castle(&mut self, color: Color, short: bool) {
@@ -335,6 +495,7 @@ impl Board {
         } else {
             self.gamestate.halfmoves += 1;
         }
+        self.gamestate.plies_from_null = self.gamestate.plies_from_null.saturating_add(1);
 
         if self.gamestate.active == Color::Black {
             self.gamestate.fullmoves += 1;
@@ -503,6 +664,9 @@ impl Board {
             self.gamestate.fullmoves += 1;
         }
 
+        self.gamestate.halfmoves += 1;
+        self.gamestate.plies_from_null = 0;
+
         self.swap_active();
 
         self.update_game_state();
@@ -515,8 +679,202 @@ impl Board {
         }
     }
 
-    pub fn is_draw(&self) -> bool {
-        self.gamestate.halfmoves >= 100 || !self.has_sufficient_material() || self.is_repetition()
+    /// Returns true if the position should be scored as a draw inside the
+    /// search, where `ply` is the distance to the root of the search.
+    pub fn is_draw(&self, ply: u8) -> bool {
+        self.is_fifty_move_draw() || !self.has_sufficient_material() || self.is_repetition(ply)
+    }
+
+    /// Returns the outcome of the game by the rules. A checkmate or stalemate
+    /// takes precedence over the other draws, as the game ended with the move
+    /// that caused it.
+    pub fn outcome(&self) -> Outcome {
+        if !self.has_legal_moves() {
+            if self.is_check() {
+                return Outcome::Checkmate(self.other());
+            }
+
+            return Outcome::Stalemate;
+        }
+
+        if self.is_dead_position() {
+            Outcome::InsufficientMaterial
+        } else if self.is_fifty_move_draw() {
+            Outcome::FiftyMoveRule
+        } else if self.is_threefold_repetition() {
+            Outcome::ThreefoldRepetition
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Checks if the side to move has at least one legal move, without
+    /// generating all of them. Castling never needs to be checked, as the
+    /// king could also just move to the square next to it.
+    pub fn has_legal_moves(&self) -> bool {
+        let color = self.active();
+
+        let king_square = self.get_king_square(color);
+        let own_occupied = self.get_occupied(color);
+        let enemy_occupied = self.get_occupied(color.other());
+        let all_occupied = self.get_all_occupied();
+
+        let king_moves = king_square.get_king_moves() & !own_occupied & !self.attacked();
+        if !king_moves.is_empty() {
+            return true;
+        }
+
+        // Only the king can escape a double check.
+        let check_mask = self.check_mask();
+        if check_mask.is_empty() {
+            return false;
+        }
+
+        let pieces = own_occupied ^ king_square;
+        for from in pieces {
+            let piece = match self.get_tile(from) {
+                Some(tile) => tile.piece,
+                None => continue,
+            };
+
+            let mut targets = match piece {
+                Piece::Pawn => {
+                    let mut pushes = from.get_pawn_pushes(color) & !all_occupied;
+                    let start_rank = match color {
+                        Color::White => 1,
+                        Color::Black => 6,
+                    };
+
+                    if !pushes.is_empty() && from.rank() == start_rank {
+                        let single = Square::from_index(pushes.get_trailing_index());
+                        pushes |= single.get_pawn_pushes(color) & !all_occupied;
+                    }
+
+                    pushes | (from.get_pawn_attacks(color) & enemy_occupied)
+                }
+                Piece::Knight => from.get_knight_moves(),
+                Piece::Bishop => from.get_bishop_attacks(all_occupied),
+                Piece::Rook => from.get_rook_attacks(all_occupied),
+                Piece::Queen => {
+                    from.get_bishop_attacks(all_occupied) | from.get_rook_attacks(all_occupied)
+                }
+                Piece::King | Piece::None => Bitboard::EMPTY,
+            };
+
+            targets &= !own_occupied;
+            targets &= check_mask;
+            if self.pinned().is_set(from) {
+                targets &= king_square.get_line(from);
+            }
+
+            if !targets.is_empty() {
+                return true;
+            }
+        }
+
+        let en_passant = match &self.gamestate.en_passant {
+            Some(en_passant) => en_passant,
+            None => return false,
+        };
+
+        let pawns = self.get_piece_board(color, Piece::Pawn);
+        let attackers = en_passant.to_move.get_pawn_attacks(color.other()) & pawns;
+        attackers
+            .into_iter()
+            .any(|from| self.is_legal_en_passant(from))
+    }
+
+    /// The squares the pieces other than the king can move to. In check the
+    /// checker either needs to be captured or blocked, while in a double
+    /// check only the king can move.
+    pub fn check_mask(&self) -> Bitboard {
+        let checkers = self.checkers();
+        match checkers.count_ones() {
+            0 => Bitboard::ALL_BITS,
+            1 => {
+                let king_square = self.get_king_square(self.active());
+                let checker = Square::from(checkers);
+
+                // The checker is inclusive and the king is exclusive.
+                checker.get_between(king_square) | checker
+            }
+            _ => Bitboard::EMPTY,
+        }
+    }
+
+    /// En passant can uncover the king on the rank of both pawns, which
+    /// the pins do not catch. So the attacks of the sliders are calculated
+    /// after both pawns were removed and the capturing pawn was placed.
+    /// This also covers the checks, as the only checks after a double push
+    /// are by the pushed pawn itself or discovered by a slider.
+    pub fn is_legal_en_passant(&self, source: Square) -> bool {
+        let en_passant = match &self.gamestate.en_passant {
+            Some(en_passant) => en_passant,
+            None => return false,
+        };
+
+        let color = self.active();
+        let enemy = color.other();
+        let king_square = self.get_king_square(color);
+
+        let mut blockers = self.get_all_occupied();
+        blockers ^= source;
+        blockers ^= en_passant.to_capture;
+        blockers |= en_passant.to_move;
+
+        let queens = self.get_piece_board(enemy, Piece::Queen);
+        let bishops = self.get_piece_board(enemy, Piece::Bishop);
+        let rooks = self.get_piece_board(enemy, Piece::Rook);
+
+        let mut attackers = king_square.get_bishop_attacks(blockers) & (bishops | queens);
+        attackers |= king_square.get_rook_attacks(blockers) & (rooks | queens);
+
+        attackers.is_empty()
+    }
+
+    /// Returns true if no sequence of legal moves could lead to a checkmate.
+    /// Unlike the search, which also treats positions as drawn where no side
+    /// can force a mate (like two knights against a lone king), this only
+    /// covers the dead positions of the FIDE rules:
+    /// - King against king
+    /// - King and a single minor piece against king
+    /// - Kings and bishops, where all bishops are on squares of the same color
+    pub fn is_dead_position(&self) -> bool {
+        for color in [Color::White, Color::Black] {
+            let pawns = self.get_piece_board(color, Piece::Pawn);
+            let rooks = self.get_piece_board(color, Piece::Rook);
+            let queens = self.get_piece_board(color, Piece::Queen);
+            if !(pawns | rooks | queens).is_empty() {
+                return false;
+            }
+        }
+
+        let knights = self.get_piece_board(Color::White, Piece::Knight)
+            | self.get_piece_board(Color::Black, Piece::Knight);
+        let bishops = self.get_piece_board(Color::White, Piece::Bishop)
+            | self.get_piece_board(Color::Black, Piece::Bishop);
+
+        let minors = (knights | bishops).count_ones();
+        if minors <= 1 {
+            return true;
+        }
+
+        if !knights.is_empty() {
+            return false;
+        }
+
+        let white_squares = bishops.filter(|square| square.is_white()).count();
+        white_squares == 0 || white_squares == bishops.count_ones()
+    }
+
+    /// Returns true if the game is drawn by the rules. This is used to
+    /// adjudicate the game, so a repetition has to occur three times.
+    pub fn is_game_draw(&self) -> bool {
+        self.outcome().is_draw()
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.gamestate.halfmoves >= 100
     }
 
     pub fn has_sufficient_material(&self) -> bool {
@@ -540,6 +898,16 @@ impl Board {
             || (self.has_bishop_pair(Color::Black) || self.has_bishop_pair(Color::White))
     }
 
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        let knights = self.get_piece_board(color, Piece::Knight);
+        let bishops = self.get_piece_board(color, Piece::Bishop);
+        let rooks = self.get_piece_board(color, Piece::Rook);
+        let queens = self.get_piece_board(color, Piece::Queen);
+
+        let non_pawn_material = knights | bishops | rooks | queens;
+        !non_pawn_material.is_empty()
+    }
+
     pub fn has_bishop_pair(&self, color: Color) -> bool {
         let bishops = self.get_piece_board(color, Piece::Bishop);
         if bishops.count_ones() < 2 {
@@ -559,21 +927,54 @@ impl Board {
         white_squares >= 1 && black_squares >= 1
     }
 
-    pub fn is_repetition(&self) -> bool {
-        if self.history.is_empty() {
-            return false;
-        }
+    /// Returns true if the position is repeated, where `ply` is the distance
+    /// to the root of the search. A position that already occurred inside
+    /// the search tree, after the root, is a draw on its first repetition,
+    /// as the side that repeated it could just do it again. The root and the
+    /// positions of the game before it still need to occur three times.
+    /// Source: https://www.chessprogramming.org/Repetitions
+    pub fn is_repetition(&self, ply: u8) -> bool {
+        let mut repetitions = 0;
+        for (distance, gamestate) in self.reversible_history() {
+            if gamestate.hash != self.gamestate.hash {
+                continue;
+            }
 
-        let from = self.history.len() - self.gamestate.halfmoves as usize;
-        let to = self.history.len() - 1;
-        for index in from..to {
-            let gamestate = &self.history[index];
-            if gamestate.hash == self.gamestate.hash {
+            if distance < ply as usize {
+                return true;
+            }
+
+            repetitions += 1;
+            if repetitions >= 2 {
                 return true;
             }
         }
 
-        return false;
+        false
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 2
+    }
+
+    /// Returns how often the current position occurred before.
+    pub fn repetitions(&self) -> usize {
+        self.reversible_history()
+            .filter(|(_, gamestate)| gamestate.hash == self.gamestate.hash)
+            .count()
+    }
+
+    /// Iterates over the previous positions with the same side to move,
+    /// that could still be repeated, together with their distance in plies.
+    /// A position can only be repeated after at least four plies and only
+    /// since the last irreversible move or null move.
+    fn reversible_history(&self) -> impl Iterator<Item = (usize, &GameState)> {
+        let max_distance = self.gamestate.halfmoves.min(self.gamestate.plies_from_null) as usize;
+        let max_distance = max_distance.min(self.history.len());
+
+        (4..=max_distance)
+            .step_by(2)
+            .map(|distance| (distance, &self.history[self.history.len() - distance]))
     }
 
     pub fn to_fen(&self) -> String {
@@ -635,7 +1036,7 @@ impl Board {
 
         fen.push(' ');
         let en_passant = match &self.gamestate.en_passant {
-            Some(en_passant) => en_passant.to_capture.to_string(),
+            Some(en_passant) => en_passant.to_move.to_string(),
             None => "-".to_string(),
         };
         fen.push_str(&en_passant);
@@ -666,6 +1067,13 @@ impl Board {
         self.gamestate.hash
     }
 
+    /// The hash of only the pawns, which is used to cache the evaluation of
+    /// the pawn structure.
+    #[inline(always)]
+    pub const fn pawn_hash(&self) -> ZobristHash {
+        self.gamestate.pawn_hash
+    }
+
     #[inline(always)]
     pub const fn hasher(&self) -> &ZobristHasher {
         &self.hasher
@@ -676,6 +1084,11 @@ impl Board {
         self.gamestate.halfmoves
     }
 
+    #[inline(always)]
+    pub const fn fullmoves(&self) -> u16 {
+        self.gamestate.fullmoves
+    }
+
     #[inline(always)]
     pub const fn en_passant(&self) -> &Option<EnPassant> {
         &self.gamestate.en_passant
@@ -716,6 +1129,37 @@ impl Board {
         self.gamephase
     }
 
+    #[inline(always)]
+    pub fn network(&self) -> Option<&Network> {
+        self.network.as_deref()
+    }
+
+    #[inline(always)]
+    pub const fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+
+    /// Sets the network whose accumulator is updated together with the
+    /// pieces. The accumulator is computed from scratch once.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.accumulator = match &network {
+            Some(network) => self.refresh_accumulator(network),
+            None => Accumulator::default(),
+        };
+        self.network = network;
+    }
+
+    pub fn refresh_accumulator(&self, network: &Network) -> Accumulator {
+        let mut accumulator = Accumulator::new(network);
+        for (index, tile) in self.pieces.iter().enumerate() {
+            if let Some(Tile { piece, color }) = tile {
+                accumulator.add(network, *color, *piece, Square::from_index(index as u8));
+            }
+        }
+
+        accumulator
+    }
+
     #[inline(always)]
     pub const fn checkers(&self) -> Bitboard {
         self.gamestate.checkers
@@ -736,6 +1180,16 @@ impl Board {
         self.gamestate.attacked
     }
 
+    #[inline(always)]
+    pub const fn check_squares(&self, piece: Piece) -> Bitboard {
+        self.gamestate.check_squares[piece.index()]
+    }
+
+    #[inline(always)]
+    pub const fn discoverers(&self) -> Bitboard {
+        self.gamestate.discoverers
+    }
+
     #[inline(always)]
     pub const fn gamestate(&self) -> &GameState {
         &self.gamestate
@@ -856,6 +1310,9 @@ impl Board {
         let hash = board.board_hash();
         board.gamestate.hash = hash;
 
+        let pawn_hash = board.board_pawn_hash();
+        board.gamestate.pawn_hash = pawn_hash;
+
         board.update_game_state();
 
         Ok(board)
@@ -1,6 +1,7 @@
 pub mod board;
 pub mod color;
 pub mod error;
+pub mod outcome;
 pub mod piece;
 mod tests;
 
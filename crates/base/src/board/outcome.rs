@@ -0,0 +1,41 @@
+use super::color::Color;
+
+/// The state of the game according to the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ongoing,
+    /// The given color delivered the checkmate and won the game.
+    Checkmate(Color),
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    /// Neither side can checkmate the opponent with any series of legal
+    /// moves, which is also called a dead position.
+    InsufficientMaterial,
+}
+
+impl Outcome {
+    #[inline(always)]
+    pub const fn is_ongoing(&self) -> bool {
+        matches!(self, Self::Ongoing)
+    }
+
+    #[inline(always)]
+    pub const fn is_draw(&self) -> bool {
+        matches!(
+            self,
+            Self::Stalemate
+                | Self::FiftyMoveRule
+                | Self::ThreefoldRepetition
+                | Self::InsufficientMaterial
+        )
+    }
+
+    #[inline(always)]
+    pub const fn winner(&self) -> Option<Color> {
+        match self {
+            Self::Checkmate(color) => Some(*color),
+            _ => None,
+        }
+    }
+}
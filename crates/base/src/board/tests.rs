@@ -322,13 +322,28 @@ mod fen {
         let queen_bb = board.get_piece_board(Color::Black, Piece::Queen);
         assert_eq!(queen_bb.bits(), 0x800000000000000);
     }
+
+    #[test]
+    fn fen_en_passant() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let mut board = Board::default(hasher.clone());
+        board.make_moves(&vec!["e2e4".to_string()]).unwrap();
+
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(board.to_fen(), fen);
+
+        let board = Board::from_str(fen, hasher).unwrap();
+        assert_eq!(board.to_fen(), fen);
+    }
 }
 
 #[cfg(test)]
 mod zobrist {
     use rand::{rngs::StdRng, SeedableRng};
 
-    use crate::{board::Board, zobrist::ZobristHasher};
+    use crate::{board::Board, r#move::Move, zobrist::ZobristHasher};
 
     #[test]
     fn check_startpos() {
@@ -381,4 +396,331 @@ mod zobrist {
 
         assert_ne!(first_hash, second_hash);
     }
+
+    #[test]
+    fn pawn_hash_incremental() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        // Covers an en passant capture, a capture of a pawn and a promotion
+        // with a capture.
+        let fen = "r3k2r/1P6/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1";
+        let mut board = Board::from_str(fen, hasher).unwrap();
+        let start_hash = board.pawn_hash();
+        assert_eq!(start_hash, board.board_pawn_hash());
+
+        let mut moves = Vec::new();
+        for mov in ["e2e4", "d4e3", "a1a7", "e8g8", "b7a8q"] {
+            let mov = Move::parse(&board, mov.to_string()).unwrap();
+            board.make(mov);
+            moves.push(mov);
+
+            assert_eq!(board.pawn_hash(), board.board_pawn_hash());
+        }
+
+        for mov in moves.into_iter().rev() {
+            board.unmake(mov);
+            assert_eq!(board.pawn_hash(), board.board_pawn_hash());
+        }
+
+        assert_eq!(board.pawn_hash(), start_hash);
+    }
+
+    #[test]
+    fn pawn_hash_ignores_pieces() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let first = "4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1";
+        let first = Board::from_str(first, hasher.clone()).unwrap();
+
+        let second = "3rk3/pp6/8/8/8/2N5/PP6/3QK3 b - - 0 1";
+        let second = Board::from_str(second, hasher.clone()).unwrap();
+        assert_eq!(first.pawn_hash(), second.pawn_hash());
+        assert_ne!(first.hash(), second.hash());
+
+        let third = "4k3/p1p5/8/8/8/8/PP6/4K3 w - - 0 1";
+        let third = Board::from_str(third, hasher).unwrap();
+        assert_ne!(first.pawn_hash(), third.pawn_hash());
+    }
+}
+
+#[cfg(test)]
+mod material {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        board::{color::Color, Board},
+        zobrist::ZobristHasher,
+    };
+
+    #[test]
+    fn non_pawn_material() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let board = Board::default(hasher.clone());
+        assert!(board.has_non_pawn_material(Color::White));
+        assert!(board.has_non_pawn_material(Color::Black));
+
+        let fen = "8/5k2/4p3/3pP3/3P4/8/4K3/7n w - - 0 1";
+        let board = Board::from_str(fen, hasher).unwrap();
+        assert!(!board.has_non_pawn_material(Color::White));
+        assert!(board.has_non_pawn_material(Color::Black));
+    }
+}
+
+#[cfg(test)]
+mod gives_check {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{board::Board, r#move::Move, zobrist::ZobristHasher};
+
+    fn gives_check(fen: &str, mov: &str) -> bool {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let board = Board::from_str(fen, hasher).unwrap();
+        let mov = Move::parse(&board, mov).unwrap();
+        board.gives_check(mov)
+    }
+
+    #[test]
+    fn direct() {
+        let fen = "4k3/8/8/8/8/8/1N6/Q3K3 w - - 0 1";
+        assert!(gives_check(fen, "a1a4"));
+        assert!(gives_check(fen, "a1e5"));
+        assert!(!gives_check(fen, "a1a3"));
+        assert!(!gives_check(fen, "b2d3"));
+        assert!(gives_check("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1", "e4f6"));
+        assert!(gives_check("4k3/8/3P4/8/8/8/8/4K3 w - - 0 1", "d6d7"));
+    }
+
+    #[test]
+    fn discovered() {
+        let fen = "4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1";
+        assert!(gives_check(fen, "e2c3"));
+        assert!(gives_check(fen, "e2g3"));
+
+        let fen = "4k3/8/8/8/4P3/8/8/4R1K1 w - - 0 1";
+        assert!(!gives_check(fen, "e4e5"));
+    }
+
+    #[test]
+    fn castling() {
+        assert!(gives_check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+        assert!(!gives_check("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+        assert!(gives_check("r3k3/8/8/8/8/8/8/3K4 b q - 0 1", "e8c8"));
+    }
+
+    #[test]
+    fn en_passant() {
+        // The capture discovers the rook on the fifth rank.
+        let fen = "8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1";
+        assert!(gives_check(fen, "e5d6"));
+
+        // The captured pawn was blocking the bishop.
+        let fen = "8/8/8/3pP3/8/1B6/8/4K2k w - d6 0 1";
+        assert!(!gives_check(fen, "e5d6"));
+        let fen = "7k/8/8/3pP3/8/1B6/8/4K3 w - d6 0 1";
+        assert!(!gives_check(fen, "e5d6"));
+        let fen = "6k1/8/8/3pP3/8/1B6/8/4K3 w - d6 0 1";
+        assert!(gives_check(fen, "e5d6"));
+    }
+
+    #[test]
+    fn promotion() {
+        assert!(gives_check("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"));
+        assert!(!gives_check("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8n"));
+        assert!(gives_check("5k2/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8r"));
+
+        // The promoted piece is attacking through the square of the pawn.
+        assert!(gives_check("3r4/4P3/8/8/7k/8/8/4K3 w - - 0 1", "e7d8q"));
+    }
+}
+
+#[cfg(test)]
+mod repetition {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{board::Board, zobrist::ZobristHasher};
+
+    const SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    fn shuffle(board: &mut Board, times: usize) {
+        for _ in 0..times {
+            let moves = SHUFFLE.iter().map(|mov| mov.to_string()).collect();
+            board.make_moves(&moves).unwrap();
+        }
+    }
+
+    #[test]
+    fn twofold_inside_search() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let mut board = Board::default(hasher);
+        shuffle(&mut board, 1);
+
+        assert_eq!(board.repetitions(), 1);
+        assert!(!board.is_threefold_repetition());
+        assert!(!board.is_game_draw());
+
+        // The first occurrence is only part of the game history.
+        assert!(!board.is_repetition(0));
+        assert!(!board.is_repetition(3));
+        assert!(!board.is_draw(0));
+
+        // The first occurrence is part of the search tree.
+        assert!(board.is_repetition(5));
+        assert!(board.is_draw(5));
+    }
+
+    #[test]
+    fn root_repeat() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        // The search returns to the root, which only occurred once before.
+        let mut board = Board::default(hasher);
+        shuffle(&mut board, 1);
+
+        assert!(!board.is_repetition(4));
+        assert!(!board.is_draw(4));
+
+        // If the root was already repeated in the game, it is the third time.
+        shuffle(&mut board, 1);
+        assert!(board.is_repetition(4));
+    }
+
+    #[test]
+    fn threefold_in_history() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let mut board = Board::default(hasher);
+        shuffle(&mut board, 2);
+
+        assert_eq!(board.repetitions(), 2);
+        assert!(board.is_threefold_repetition());
+        assert!(board.is_repetition(0));
+        assert!(board.is_game_draw());
+    }
+
+    #[test]
+    fn irreversible_moves() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        // The halfmove clock is bigger than the known history.
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 40 60";
+        let mut board = Board::from_str(fen, hasher.clone()).unwrap();
+        assert_eq!(board.repetitions(), 0);
+
+        let moves = ["a1a2", "e8d8", "a2a1", "d8e8"];
+        board
+            .make_moves(&moves.iter().map(|mov| mov.to_string()).collect())
+            .unwrap();
+        assert_eq!(board.repetitions(), 1);
+
+        // A pawn move resets the positions that can be repeated.
+        let mut board = Board::default(hasher);
+        board.make_moves(&vec!["e2e4".to_string()]).unwrap();
+        shuffle(&mut board, 1);
+        board.make_moves(&vec!["e4e5".to_string()]).unwrap();
+        assert_eq!(board.repetitions(), 0);
+    }
+
+    #[test]
+    fn null_move_is_irreversible() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        // Without the null moves, this would be the starting position again.
+        let mut board = Board::default(hasher);
+        board.make_moves(&vec!["g1f3".to_string()]).unwrap();
+        board.make_null();
+        board.make_moves(&vec!["f3g1".to_string()]).unwrap();
+        board.make_null();
+
+        assert_eq!(board.hash(), Board::default(board.hasher().clone()).hash());
+        assert_eq!(board.repetitions(), 0);
+
+        // The null moves still count towards the fifty move rule.
+        assert_eq!(board.halfmoves(), 4);
+    }
+}
+
+#[cfg(test)]
+mod outcome {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        board::{color::Color, outcome::Outcome, Board},
+        zobrist::ZobristHasher,
+    };
+
+    fn outcome(fen: &str) -> Outcome {
+        let mut rand = StdRng::seed_from_u64(42);
+        let hasher = ZobristHasher::random(&mut rand);
+
+        let board = Board::from_str(fen, hasher).unwrap();
+        board.outcome()
+    }
+
+    #[test]
+    fn ongoing() {
+        assert_eq!(outcome(Board::STARTPOS_FEN), Outcome::Ongoing);
+        // Two knights can not force a mate, but the position is not dead.
+        assert_eq!(
+            outcome("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"),
+            Outcome::Ongoing
+        );
+        assert_eq!(
+            outcome("4k3/8/8/8/8/8/8/1N2KB2 w - - 0 1"),
+            Outcome::Ongoing
+        );
+        assert_eq!(outcome("4kb2/8/8/8/8/8/8/4KB2 w - - 0 1"), Outcome::Ongoing);
+        assert_eq!(outcome("4kn2/8/8/8/8/8/8/4KN2 w - - 0 1"), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn checkmate() {
+        let fen = "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4";
+        assert_eq!(outcome(fen), Outcome::Checkmate(Color::White));
+        assert_eq!(outcome(fen).winner(), Some(Color::White));
+
+        // The mate takes precedence over the fifty move rule.
+        let fen = "6rk/5Npp/8/8/8/8/8/6K1 b - - 100 80";
+        assert_eq!(outcome(fen), Outcome::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn stalemate() {
+        assert_eq!(outcome("k7/8/1Q6/8/8/8/8/7K b - - 0 1"), Outcome::Stalemate);
+        // The only move is an en passant capture, which exposes the king.
+        let fen = "8/8/1r5r/KPp5/7r/8/8/7k w - c6 0 1";
+        assert_eq!(outcome(fen), Outcome::Ongoing);
+        let fen = "8/8/1r5r/KPp4r/7r/8/8/7k w - c6 0 1";
+        assert_eq!(outcome(fen), Outcome::Stalemate);
+    }
+
+    #[test]
+    fn draws() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 100 80";
+        assert_eq!(outcome(fen), Outcome::FiftyMoveRule);
+        assert!(outcome(fen).is_draw());
+
+        assert_eq!(
+            outcome("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Outcome::InsufficientMaterial
+        );
+        assert_eq!(
+            outcome("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"),
+            Outcome::InsufficientMaterial
+        );
+        // All bishops are on squares of the same color.
+        let fen = "4k1b1/8/8/8/8/3B4/8/4KB2 w - - 0 1";
+        assert_eq!(outcome(fen), Outcome::InsufficientMaterial);
+    }
 }
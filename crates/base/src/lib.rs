@@ -1,6 +1,9 @@
 pub mod bitboard;
 pub mod board;
 pub mod r#move;
+pub mod nnue;
 pub mod polyglot;
 pub mod square;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod zobrist;
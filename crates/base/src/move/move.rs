@@ -126,6 +126,11 @@ impl Move {
             return None;
         }
 
+        // The captured pawn is not on the target square of the move.
+        if self.is_en_passant() {
+            return Some(Piece::Pawn);
+        }
+
         let captured_tile = board.get_tile(self.to()).expect("There should be a piece.");
         Some(captured_tile.piece)
     }
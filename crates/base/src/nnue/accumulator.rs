@@ -0,0 +1,60 @@
+use std::fmt::{Debug, Formatter};
+
+use crate::{
+    board::{color::Color, piece::Piece},
+    square::Square,
+};
+
+use super::network::{Network, Neurons};
+
+/// The hidden layer of the network from the view of both colors. Moving
+/// a piece only changes a few inputs, so the board adds and removes their
+/// weights instead of computing the layer again.
+/// Source: https://www.chessprogramming.org/NNUE
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Accumulator {
+    values: [Neurons; Color::COUNT],
+}
+
+impl Accumulator {
+    /// An accumulator of an empty board.
+    pub fn new(network: &Network) -> Self {
+        let bias = network.feature_bias().clone();
+        Self {
+            values: [bias.clone(), bias],
+        }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, perspective: Color) -> &Neurons {
+        &self.values[perspective.index()]
+    }
+
+    #[inline(always)]
+    pub fn add(&mut self, network: &Network, color: Color, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = network.feature_weights(perspective, color, piece, square);
+            let values = &mut self.values[perspective.index()].0;
+            for (value, weight) in values.iter_mut().zip(&weights.0) {
+                *value = value.wrapping_add(*weight);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn remove(&mut self, network: &Network, color: Color, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = network.feature_weights(perspective, color, piece, square);
+            let values = &mut self.values[perspective.index()].0;
+            for (value, weight) in values.iter_mut().zip(&weights.0) {
+                *value = value.wrapping_sub(*weight);
+            }
+        }
+    }
+}
+
+impl Debug for Accumulator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Accumulator").finish_non_exhaustive()
+    }
+}
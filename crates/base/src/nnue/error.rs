@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub enum NetworkError {
+    WrongSize(#[from] WrongSize),
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("the network needs {expected} bytes, but {given} were given")]
+pub struct WrongSize {
+    expected: usize,
+    given: usize,
+}
+
+impl WrongSize {
+    pub fn new(expected: usize, given: usize) -> Self {
+        Self { expected, given }
+    }
+}
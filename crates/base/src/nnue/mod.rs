@@ -0,0 +1,5 @@
+pub mod accumulator;
+pub mod error;
+pub mod network;
+
+mod tests;
@@ -0,0 +1,164 @@
+use std::{
+    fmt::{Debug, Formatter},
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use crate::{
+    board::{color::Color, piece::Piece},
+    square::Square,
+};
+
+use super::{
+    accumulator::Accumulator,
+    error::{NetworkError, WrongSize},
+};
+
+/// One input for every piece of both colors on every square.
+pub const INPUT_SIZE: usize = 768;
+pub const HIDDEN_SIZE: usize = 256;
+
+// The quantisation of the hidden layer, the output layer and the factor
+// that turns the output into centipawns.
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+pub const SCALE: i32 = 400;
+
+/// The neurons of the hidden layer. They are aligned to the cache lines,
+/// so that the compiler can use aligned SIMD loads for them.
+#[derive(Clone, PartialEq, Eq)]
+#[repr(C, align(64))]
+pub struct Neurons(pub [i16; HIDDEN_SIZE]);
+
+impl Default for Neurons {
+    fn default() -> Self {
+        Self([0; HIDDEN_SIZE])
+    }
+}
+
+/// A perspective network with the architecture (768 -> 256)x2 -> 1 and
+/// a squared clipped ReLU activation. The hidden layer is computed once
+/// from the view of each color and updated incrementally by the board, the
+/// side to move is put first in the output layer.
+///
+/// The file contains little-endian `i16` values in the following order:
+/// the weights of the 768 inputs, the biases of the hidden layer, the output
+/// weights for the side to move and for the other side and the output bias.
+pub struct Network {
+    feature_weights: Vec<Neurons>,
+    feature_bias: Neurons,
+    output_weights: [Neurons; Color::COUNT],
+    output_bias: i16,
+}
+
+impl Network {
+    pub const SIZE: usize = (INPUT_SIZE * HIDDEN_SIZE + 3 * HIDDEN_SIZE + 1) * 2;
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Network, NetworkError> {
+        let mut file = File::open(path)?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Network, NetworkError> {
+        if data.len() != Self::SIZE {
+            return Err(WrongSize::new(Self::SIZE, data.len()).into());
+        }
+
+        let mut values = data
+            .chunks_exact(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]));
+        let mut neurons = || {
+            let mut neurons = Neurons::default();
+            for (neuron, value) in neurons.0.iter_mut().zip(&mut values) {
+                *neuron = value;
+            }
+            neurons
+        };
+
+        let feature_weights = (0..INPUT_SIZE).map(|_| neurons()).collect();
+        let feature_bias = neurons();
+        let output_weights = [neurons(), neurons()];
+        let output_bias = values.next().unwrap_or_default();
+
+        Ok(Self {
+            feature_weights,
+            feature_bias,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    /// Evaluates the position of the accumulator from the view of the
+    /// active color.
+    pub fn evaluate(&self, accumulator: &Accumulator, active: Color) -> i32 {
+        let mut output = 0;
+        output += screlu_dot(accumulator.get(active), &self.output_weights[0]);
+        output += screlu_dot(accumulator.get(active.other()), &self.output_weights[1]);
+        output /= QA as i64;
+        output += self.output_bias as i64;
+
+        (output * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+
+    #[inline(always)]
+    pub(crate) fn feature_bias(&self) -> &Neurons {
+        &self.feature_bias
+    }
+
+    #[inline(always)]
+    pub(crate) fn feature_weights(
+        &self,
+        perspective: Color,
+        color: Color,
+        piece: Piece,
+        square: Square,
+    ) -> &Neurons {
+        &self.feature_weights[feature_index(perspective, color, piece, square)]
+    }
+}
+
+impl Debug for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Network")
+            .field("inputs", &INPUT_SIZE)
+            .field("hidden", &HIDDEN_SIZE)
+            .finish()
+    }
+}
+
+/// The index of the input for the piece, as seen by the perspective. The
+/// board is mirrored for black, so that both colors see their own pieces
+/// first and on the lower ranks.
+#[inline(always)]
+pub fn feature_index(perspective: Color, color: Color, piece: Piece, square: Square) -> usize {
+    debug_assert!(piece != Piece::None);
+
+    let side = (color != perspective) as usize;
+    let square = match perspective {
+        Color::White => square.index() as usize,
+        Color::Black => square.index() as usize ^ 56,
+    };
+
+    side * 384 + (piece.index() - 1) * 64 + square
+}
+
+/// Activates the neurons with the squared clipped ReLU and multiplies them
+/// with the weights. A single product already fits into an `i32`, but the
+/// sum of all of them doesn't with large weights, so it is summed as `i64`.
+#[inline(always)]
+fn screlu_dot(neurons: &Neurons, weights: &Neurons) -> i64 {
+    neurons
+        .0
+        .iter()
+        .zip(&weights.0)
+        .map(|(&neuron, &weight)| {
+            let neuron = (neuron as i32).clamp(0, QA);
+            (neuron * weight as i32 * neuron) as i64
+        })
+        .sum()
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod network {
+    use std::sync::Arc;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        board::{color::Color, Board},
+        nnue::{
+            error::NetworkError,
+            network::{Network, HIDDEN_SIZE, INPUT_SIZE, QA, QB, SCALE},
+        },
+        testing::board,
+    };
+
+    fn random_network() -> Arc<Network> {
+        let mut rand = StdRng::seed_from_u64(42);
+
+        let mut data = Vec::with_capacity(Network::SIZE);
+        for _ in 0..Network::SIZE / 2 {
+            let value = rand.gen_range(-64i16..64);
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        Arc::new(Network::parse(&data).unwrap())
+    }
+
+    #[test]
+    fn wrong_size() {
+        let result = Network::parse(&[0; 16]);
+        assert!(matches!(result, Err(NetworkError::WrongSize(_))));
+    }
+
+    #[test]
+    fn output_bias() {
+        let mut data = vec![0; Network::SIZE];
+        let bias = (QA * QB) as i16;
+        data[Network::SIZE - 2..].copy_from_slice(&bias.to_le_bytes());
+
+        let network = Network::parse(&data).unwrap();
+        let mut board = board(Board::STARTPOS_FEN);
+        board.set_network(Some(Arc::new(network)));
+
+        let network = board.network().unwrap();
+        assert_eq!(network.evaluate(board.accumulator(), Color::White), SCALE);
+    }
+
+    #[test]
+    fn large_weights() {
+        // Every neuron is fully activated and has the largest output weight,
+        // which overflows an `i32` sum of the products.
+        let mut values = vec![0; INPUT_SIZE * HIDDEN_SIZE];
+        values.extend(vec![QA as i16; HIDDEN_SIZE]);
+        values.extend(vec![i16::MAX; 2 * HIDDEN_SIZE]);
+        values.push(0);
+
+        let data = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let network = Network::parse(&data).unwrap();
+        let mut board = board(Board::STARTPOS_FEN);
+        board.set_network(Some(Arc::new(network)));
+
+        let sum = 2 * HIDDEN_SIZE as i64 * (QA * QA) as i64 * i16::MAX as i64;
+        let expected = sum / QA as i64 * SCALE as i64 / (QA * QB) as i64;
+        let network = board.network().unwrap();
+        assert_eq!(
+            network.evaluate(board.accumulator(), Color::White) as i64,
+            expected
+        );
+    }
+
+    #[test]
+    fn symmetric_startpos() {
+        let mut board = board(Board::STARTPOS_FEN);
+        board.set_network(Some(random_network()));
+
+        let accumulator = board.accumulator();
+        assert!(accumulator.get(Color::White).0 == accumulator.get(Color::Black).0);
+
+        let network = board.network().unwrap();
+        assert_eq!(
+            network.evaluate(accumulator, Color::White),
+            network.evaluate(accumulator, Color::Black)
+        );
+    }
+
+    #[test]
+    fn mirrored_positions() {
+        let network = random_network();
+
+        let mut white =
+            board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut black =
+            board("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1");
+        white.set_network(Some(network.clone()));
+        black.set_network(Some(network.clone()));
+
+        assert_eq!(
+            network.evaluate(white.accumulator(), Color::White),
+            network.evaluate(black.accumulator(), Color::Black)
+        );
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let network = random_network();
+
+        // Covers quiet moves, captures, both castlings, en passant and
+        // promotions with and without a capture.
+        let mut board =
+            board("r3k2r/1P1pqpb1/bn2pnp1/2pPN3/1p2P3/2N2Q1p/1PPBBPPP/R3K2R w KQkq c6 0 1");
+        board.set_network(Some(network.clone()));
+        let initial = board.accumulator().clone();
+
+        let moves = ["d5c6", "e8g8", "e1c1", "h3g2", "b7a8q", "g2h1n", "c6d7"]
+            .iter()
+            .map(|mov| mov.to_string())
+            .collect::<Vec<String>>();
+        let moves = board.make_moves(&moves).unwrap();
+        assert!(*board.accumulator() == board.refresh_accumulator(&network));
+
+        for mov in moves.into_iter().rev() {
+            board.unmake(mov);
+        }
+        assert!(*board.accumulator() == initial);
+    }
+
+    #[test]
+    fn removed_network() {
+        let mut board = board(Board::STARTPOS_FEN);
+        board.set_network(Some(random_network()));
+        board.set_network(None);
+
+        assert!(board.network().is_none());
+    }
+}
@@ -65,7 +65,7 @@ impl Square {
     #[inline(always)]
     pub fn file_bb(&self) -> Bitboard {
         unsafe {
-            let file = FILES.get_unchecked(self.rank() as usize);
+            let file = FILES.get_unchecked(self.file() as usize);
             *file
         }
     }
@@ -2,7 +2,10 @@
 mod square {
     use std::str::FromStr;
 
-    use crate::square::{constants::*, Square};
+    use crate::{
+        bitboard::constants::*,
+        square::{constants::*, Square},
+    };
 
     #[test]
     fn index() {
@@ -13,6 +16,16 @@ mod square {
         assert_eq!(u8::from(F6), 45);
     }
 
+    #[test]
+    fn rank_file_bitboards() {
+        assert_eq!(A1.rank_bb(), RANK_1);
+        assert_eq!(A1.file_bb(), FILE_A);
+        assert_eq!(D4.rank_bb(), RANK_4);
+        assert_eq!(D4.file_bb(), FILE_D);
+        assert_eq!(H7.rank_bb(), RANK_7);
+        assert_eq!(H7.file_bb(), FILE_H);
+    }
+
     #[test]
     fn rank_file() {
         let square = A1;
@@ -0,0 +1,15 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{board::Board, zobrist::ZobristHasher};
+
+/// The zobrist keys of a fixed seed, so the hashes are the same in every
+/// test run.
+pub fn hasher() -> ZobristHasher {
+    let mut rand = StdRng::seed_from_u64(42);
+    ZobristHasher::random(&mut rand)
+}
+
+/// Parses the board with the zobrist keys of `hasher`.
+pub fn board(fen: &str) -> Board {
+    Board::from_str(fen, hasher()).unwrap()
+}
@@ -93,6 +93,18 @@ impl ZobristHasher {
         hash
     }
 
+    pub fn pawn_hash(&self, board: &Board) -> ZobristHash {
+        let mut hash = ZobristHash::new(0);
+
+        for color in [Color::White, Color::Black] {
+            for square in board.get_piece_board(color, Piece::Pawn) {
+                hash ^= self.piece_hash(Piece::Pawn, color, square);
+            }
+        }
+
+        hash
+    }
+
     pub fn piece_hash(&self, piece: Piece, color: Color, square: Square) -> ZobristHash {
         debug_assert!(color.index() < Color::COUNT);
         debug_assert!(piece.index() < Piece::COUNT);
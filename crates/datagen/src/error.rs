@@ -0,0 +1,42 @@
+use thiserror::Error;
+
+use base::{board::error::BoardError, polyglot::error::PolyglotError};
+use engine::search::error::SearchError;
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub enum DatagenError {
+    IOError(#[from] std::io::Error),
+    BoardError(#[from] BoardError),
+    PolyglotError(#[from] PolyglotError),
+    SearchError(#[from] SearchError),
+    ParseIntError(#[from] std::num::ParseIntError),
+    InvalidArgument(#[from] InvalidArgument),
+    InvalidRecord(#[from] InvalidRecord),
+}
+
+#[derive(Debug, Error)]
+#[error("passed an invalid argument '{argument}'")]
+pub struct InvalidArgument {
+    argument: String,
+}
+
+impl InvalidArgument {
+    pub fn new(argument: impl Into<String>) -> Self {
+        Self {
+            argument: argument.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the record is invalid, as {reason}")]
+pub struct InvalidRecord {
+    reason: &'static str,
+}
+
+impl InvalidRecord {
+    pub fn new(reason: &'static str) -> Self {
+        Self { reason }
+    }
+}
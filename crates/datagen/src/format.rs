@@ -0,0 +1,293 @@
+use std::io::Write;
+
+use base::{
+    board::{color::Color, piece::Piece, piece::Tile, Board},
+    square::Square,
+    zobrist::ZobristHasher,
+};
+
+use crate::error::{DatagenError, InvalidRecord};
+
+/// The result of the game from the view of white.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    BlackWin,
+    Draw,
+    WhiteWin,
+}
+
+impl GameResult {
+    pub const fn winner(color: Color) -> Self {
+        match color {
+            Color::White => Self::WhiteWin,
+            Color::Black => Self::BlackWin,
+        }
+    }
+
+    /// The result as it is written in the text format, which is the same
+    /// as the expected score of white.
+    pub const fn text(&self) -> &'static str {
+        match self {
+            Self::BlackWin => "0.0",
+            Self::Draw => "0.5",
+            Self::WhiteWin => "1.0",
+        }
+    }
+
+    const fn byte(&self) -> u8 {
+        match self {
+            Self::BlackWin => 0,
+            Self::Draw => 1,
+            Self::WhiteWin => 2,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::BlackWin),
+            1 => Some(Self::Draw),
+            2 => Some(Self::WhiteWin),
+            _ => None,
+        }
+    }
+}
+
+/// A position of a game together with the score of the search, which is
+/// always in centipawns from the view of white.
+///
+/// The plain-text format has one position per line:
+///
+/// ```text
+/// <fen> | <score> | <result>
+/// ```
+///
+/// where the result is `1.0`, `0.5` or `0.0` from the view of white.
+///
+/// The binary format stores every position in 32 bytes, all values are
+/// little-endian:
+///
+/// | Bytes  | Content                                                       |
+/// |--------|---------------------------------------------------------------|
+/// | 0..8   | The occupancy as a `u64`, bit 0 is a1 and bit 63 is h8.       |
+/// | 8..24  | A nibble per occupied square from a1 to h8, low nibble first: |
+/// |        | the color in bit 3 (set for black) and the piece from 1 to 6. |
+/// | 24..26 | The score as an `i16`.                                        |
+/// | 26     | The result: 0 if black won, 1 for a draw and 2 if white won.  |
+/// | 27     | Bit 0 is set if black is to move, bits 1 to 4 are the castling |
+/// |        | rights in the order `KQkq`.                                   |
+/// | 28     | The en passant target square, or 255 if there is none.        |
+/// | 29     | The halfmove clock, capped at 255.                            |
+/// | 30..32 | The fullmove number as a `u16`.                               |
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    occupied: u64,
+    pieces: [u8; 16],
+    score: i16,
+    result: GameResult,
+    flags: u8,
+    en_passant: u8,
+    halfmoves: u8,
+    fullmoves: u16,
+}
+
+impl Position {
+    pub const SIZE: usize = 32;
+
+    const NO_EN_PASSANT: u8 = 255;
+
+    /// Packs the board. The result is not known yet during the game, so it
+    /// is a draw until it is set.
+    pub fn new(board: &Board, score: i32) -> Self {
+        let occupied = board.get_all_occupied();
+
+        let mut pieces = [0; 16];
+        for (index, square) in occupied.into_iter().enumerate() {
+            let Tile { piece, color } = board.get_tile(square).unwrap();
+            let nibble = ((color == Color::Black) as u8) << 3 | piece.index() as u8;
+            pieces[index / 2] |= nibble << (4 * (index % 2));
+        }
+
+        let mut flags = (board.active() == Color::Black) as u8;
+        flags |= (board.can_white_kingside() as u8) << 1;
+        flags |= (board.can_white_queenside() as u8) << 2;
+        flags |= (board.can_black_kingside() as u8) << 3;
+        flags |= (board.can_black_queenside() as u8) << 4;
+
+        let en_passant = match board.en_passant() {
+            Some(en_passant) => en_passant.to_move.index(),
+            None => Self::NO_EN_PASSANT,
+        };
+
+        Self {
+            occupied: occupied.bits(),
+            pieces,
+            score: score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            result: GameResult::Draw,
+            flags,
+            en_passant,
+            halfmoves: board.halfmoves().min(u8::MAX as u16) as u8,
+            fullmoves: board.fullmoves(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+    }
+
+    #[inline(always)]
+    pub const fn score(&self) -> i16 {
+        self.score
+    }
+
+    #[inline(always)]
+    pub const fn result(&self) -> GameResult {
+        self.result
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.occupied.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24..26].copy_from_slice(&self.score.to_le_bytes());
+        bytes[26] = self.result.byte();
+        bytes[27] = self.flags;
+        bytes[28] = self.en_passant;
+        bytes[29] = self.halfmoves;
+        bytes[30..32].copy_from_slice(&self.fullmoves.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatagenError> {
+        if bytes.len() != Self::SIZE {
+            return Err(InvalidRecord::new("the record is not 32 bytes long").into());
+        }
+
+        let occupied = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        if occupied.count_ones() > 32 {
+            return Err(InvalidRecord::new("more than 32 pieces").into());
+        }
+
+        let pieces: [u8; 16] = bytes[8..24].try_into().unwrap();
+        for index in 0..occupied.count_ones() as usize {
+            let nibble = pieces[index / 2] >> (4 * (index % 2)) & 0b111;
+            if !(1..=6).contains(&nibble) {
+                return Err(InvalidRecord::new("a piece is not between 1 and 6").into());
+            }
+        }
+
+        let result = GameResult::from_byte(bytes[26])
+            .ok_or_else(|| InvalidRecord::new("the result is not 0, 1 or 2"))?;
+
+        Ok(Self {
+            occupied,
+            pieces,
+            score: i16::from_le_bytes([bytes[24], bytes[25]]),
+            result,
+            flags: bytes[27],
+            en_passant: bytes[28],
+            halfmoves: bytes[29],
+            fullmoves: u16::from_le_bytes([bytes[30], bytes[31]]),
+        })
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut tiles = [None; Board::SIZE];
+        let mut occupied = self.occupied;
+        let mut index = 0;
+        while occupied != 0 {
+            let square = occupied.trailing_zeros() as usize;
+            occupied &= occupied - 1;
+
+            let nibble = self.pieces[index / 2] >> (4 * (index % 2)) & 0b1111;
+            tiles[square] = Some(Tile::new(piece(nibble & 0b111), color(nibble >> 3)));
+            index += 1;
+        }
+
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match tiles[rank * 8 + file] {
+                    Some(tile) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(tile.to_fen());
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        let active = match self.flags & 1 {
+            0 => "w",
+            _ => "b",
+        };
+
+        let mut castling = String::new();
+        for (bit, right) in [(1, 'K'), (2, 'Q'), (3, 'k'), (4, 'q')] {
+            if self.flags >> bit & 1 == 1 {
+                castling.push(right);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.en_passant {
+            Self::NO_EN_PASSANT => "-".to_string(),
+            square => Square::from_index(square).to_string(),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            fen, active, castling, en_passant, self.halfmoves, self.fullmoves
+        )
+    }
+
+    pub fn to_board(&self, hasher: ZobristHasher) -> Result<Board, DatagenError> {
+        Ok(Board::from_str(&self.to_fen(), hasher)?)
+    }
+
+    pub fn write_text(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "{} | {} | {}",
+            self.to_fen(),
+            self.score,
+            self.result.text()
+        )
+    }
+
+    pub fn write_binary(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+fn piece(index: u8) -> Piece {
+    match index {
+        1 => Piece::Pawn,
+        2 => Piece::Knight,
+        3 => Piece::Bishop,
+        4 => Piece::Rook,
+        5 => Piece::Queen,
+        6 => Piece::King,
+        _ => Piece::None,
+    }
+}
+
+fn color(index: u8) -> Color {
+    match index {
+        0 => Color::White,
+        _ => Color::Black,
+    }
+}
@@ -0,0 +1,136 @@
+use base::{
+    board::{color::Color, outcome::Outcome, Board},
+    polyglot::parser::PolyglotBook,
+    r#move::Move,
+    zobrist::ZobristHasher,
+};
+use engine::{
+    generator::{AllMoves, MoveGenerator},
+    search::{communication::Score, engine::Engine, limits::SearchLimits},
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    error::DatagenError,
+    format::{GameResult, Position},
+};
+
+// The games are adjudicated as a draw after this many plies, as they most
+// likely won't end anyway.
+pub const MAX_PLIES: usize = 400;
+
+// Openings that are already decided don't give any useful positions.
+pub const MAX_OPENING_SCORE: i32 = 1000;
+
+// The book is only followed for the first moves, so that the random moves
+// still lead to many different positions.
+pub const MAX_BOOK_PLIES: usize = 16;
+
+/// The settings of the games that are played by each thread.
+#[derive(Debug, Clone)]
+pub struct GameSettings {
+    pub nodes: usize,
+    pub random_plies: usize,
+}
+
+/// Plays a whole game of the engine against itself and returns the quiet
+/// positions of it, labelled with the result of the game. Returns nothing if
+/// the opening was already decided or the game ended during it.
+pub fn play_game(
+    engine: &Engine,
+    hasher: &ZobristHasher,
+    book: Option<&PolyglotBook>,
+    settings: &GameSettings,
+    rand: &mut impl Rng,
+) -> Result<Option<Vec<Position>>, DatagenError> {
+    let Some(mut board) = opening(hasher, book, settings.random_plies, rand) else {
+        return Ok(None);
+    };
+
+    engine.clear_cache();
+
+    let mut positions = Vec::new();
+    let mut plies = 0;
+    let result = loop {
+        match board.outcome() {
+            Outcome::Ongoing => {}
+            Outcome::Checkmate(color) => break GameResult::winner(color),
+            _ => break GameResult::Draw,
+        }
+
+        if plies >= MAX_PLIES {
+            break GameResult::Draw;
+        }
+
+        let limits = SearchLimits::new().nodes(settings.nodes);
+        let search = engine.search(&board, limits)?;
+
+        match search.score {
+            // A mate that was found by the search will be played out by it
+            // anyway, so the game is adjudicated right away.
+            Some(Score::Mate(moves, _)) => match moves > 0 {
+                true => break GameResult::winner(board.active()),
+                false => break GameResult::winner(board.active().other()),
+            },
+            Some(Score::Centipawns(score, _)) => {
+                if plies == 0 && score.abs() > MAX_OPENING_SCORE {
+                    return Ok(None);
+                }
+
+                if is_quiet(&board, search.best_move) {
+                    let score = match board.active() {
+                        Color::White => score,
+                        Color::Black => -score,
+                    };
+                    positions.push(Position::new(&board, score));
+                }
+            }
+            None => {}
+        }
+
+        board.make(search.best_move);
+        plies += 1;
+    };
+
+    for position in &mut positions {
+        position.set_result(result);
+    }
+
+    Ok(Some(positions))
+}
+
+/// Positions where the side to move is in check or the best move wins
+/// material are noisy, as their static evaluation is far off the score of
+/// the search.
+pub fn is_quiet(board: &Board, best_move: Move) -> bool {
+    !board.is_check() && !best_move.is_capture() && !best_move.is_promotion()
+}
+
+/// Plays the moves of the book and then the random moves. Returns nothing
+/// if the game is already over after them.
+pub fn opening(
+    hasher: &ZobristHasher,
+    book: Option<&PolyglotBook>,
+    random_plies: usize,
+    rand: &mut impl Rng,
+) -> Option<Board> {
+    let mut board = Board::default(hasher.clone());
+
+    if let Some(book) = book {
+        for _ in 0..MAX_BOOK_PLIES {
+            let Ok(mov) = book.get_random_move(&board) else {
+                break;
+            };
+
+            board.make(mov);
+        }
+    }
+
+    for _ in 0..random_plies {
+        let moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+        let mov = *moves.choose(rand)?;
+        board.make(mov);
+    }
+
+    board.outcome().is_ongoing().then_some(board)
+}
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod format;
+pub mod game;
+
+mod tests;
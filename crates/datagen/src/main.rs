@@ -0,0 +1,196 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+use base::{polyglot::parser::PolyglotBook, zobrist::ZobristHasher};
+use engine::search::engine::Engine;
+use rand::{rngs::StdRng, SeedableRng};
+
+use datagen::{
+    error::{DatagenError, InvalidArgument},
+    format::Position,
+    game::{play_game, GameSettings},
+};
+
+struct Arguments {
+    games: usize,
+    threads: usize,
+    hash: usize,
+    book: Option<String>,
+    output: String,
+    text: bool,
+    binary: bool,
+    seed: u64,
+    settings: GameSettings,
+}
+
+impl Arguments {
+    fn parse(mut arguments: impl Iterator<Item = String>) -> Result<Self, DatagenError> {
+        let mut parsed = Self {
+            games: 100,
+            threads: thread::available_parallelism().map_or(1, |count| count.get()),
+            hash: 16,
+            book: None,
+            output: "data".to_string(),
+            text: true,
+            binary: true,
+            seed: rand::random(),
+            settings: GameSettings {
+                nodes: 5000,
+                random_plies: 8,
+            },
+        };
+
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| InvalidArgument::new(&argument))
+            };
+
+            match argument.as_str() {
+                "--games" => parsed.games = value()?.parse()?,
+                "--threads" => parsed.threads = value()?.parse::<usize>()?.max(1),
+                "--hash" => parsed.hash = value()?.parse()?,
+                "--book" => parsed.book = Some(value()?),
+                "--output" => parsed.output = value()?,
+                "--seed" => parsed.seed = value()?.parse()?,
+                "--nodes" => parsed.settings.nodes = value()?.parse()?,
+                "--random-plies" => parsed.settings.random_plies = value()?.parse()?,
+                "--format" => match value()?.as_str() {
+                    "text" => (parsed.text, parsed.binary) = (true, false),
+                    "binary" => (parsed.text, parsed.binary) = (false, true),
+                    "both" => (parsed.text, parsed.binary) = (true, true),
+                    format => return Err(InvalidArgument::new(format).into()),
+                },
+                _ => return Err(InvalidArgument::new(argument).into()),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// The files the positions are written to, shared by all the threads.
+struct Output {
+    text: Option<BufWriter<File>>,
+    binary: Option<BufWriter<File>>,
+    games: usize,
+    positions: usize,
+}
+
+impl Output {
+    fn create(arguments: &Arguments) -> Result<Self, DatagenError> {
+        let create = |extension: &str| -> Result<BufWriter<File>, DatagenError> {
+            let file = File::create(format!("{}.{}", arguments.output, extension))?;
+            Ok(BufWriter::new(file))
+        };
+
+        Ok(Self {
+            text: arguments.text.then(|| create("txt")).transpose()?,
+            binary: arguments.binary.then(|| create("bin")).transpose()?,
+            games: 0,
+            positions: 0,
+        })
+    }
+
+    fn write(&mut self, positions: &[Position]) -> Result<(), DatagenError> {
+        for position in positions {
+            if let Some(text) = &mut self.text {
+                position.write_text(text)?;
+            }
+            if let Some(binary) = &mut self.binary {
+                position.write_binary(binary)?;
+            }
+        }
+
+        self.games += 1;
+        self.positions += positions.len();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), DatagenError> {
+        for writer in [&mut self.text, &mut self.binary].into_iter().flatten() {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let arguments = Arguments::parse(env::args().skip(1))?;
+
+    let book = match &arguments.book {
+        Some(path) => Some(PolyglotBook::from_file(path)?),
+        None => None,
+    };
+
+    let output = Mutex::new(Output::create(&arguments)?);
+    let started = AtomicUsize::new(0);
+    let start = Instant::now();
+
+    thread::scope(|scope| -> Result<(), DatagenError> {
+        let workers = (0..arguments.threads)
+            .map(|index| {
+                let arguments = &arguments;
+                let book = book.as_ref();
+                let output = &output;
+                let started = &started;
+
+                scope.spawn(move || -> Result<(), DatagenError> {
+                    let mut rand = StdRng::seed_from_u64(arguments.seed.wrapping_add(index as u64));
+                    let hasher = ZobristHasher::random(&mut rand);
+
+                    let mut engine = Engine::new(arguments.hash);
+                    engine.set_threads(1);
+
+                    while started.fetch_add(1, Ordering::Relaxed) < arguments.games {
+                        // Openings that are decided are skipped, so the game
+                        // is played again with another one.
+                        let positions = loop {
+                            let settings = &arguments.settings;
+                            if let Some(positions) =
+                                play_game(&engine, &hasher, book, settings, &mut rand)?
+                            {
+                                break positions;
+                            }
+                        };
+
+                        let mut output = output.lock().unwrap();
+                        output.write(&positions)?;
+
+                        let elapsed = start.elapsed().as_secs_f64();
+                        println!(
+                            "Games {}/{}, positions {}, {:.1} positions/s",
+                            output.games,
+                            arguments.games,
+                            output.positions,
+                            output.positions as f64 / elapsed
+                        );
+                    }
+
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            worker.join().unwrap()?;
+        }
+
+        Ok(())
+    })?;
+
+    output.lock().unwrap().flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod format {
+    use base::{
+        board::Board,
+        testing::{board, hasher},
+    };
+
+    use crate::{
+        error::DatagenError,
+        format::{GameResult, Position},
+    };
+
+    #[test]
+    fn binary_roundtrip() {
+        let fens = [
+            Board::STARTPOS_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 57 300",
+        ];
+
+        for fen in fens {
+            let board = board(fen);
+            let mut position = Position::new(&board, -123);
+            position.set_result(GameResult::WhiteWin);
+
+            let decoded = Position::from_bytes(&position.to_bytes()).unwrap();
+            assert_eq!(decoded, position);
+            assert_eq!(decoded.to_fen(), fen);
+            assert_eq!(decoded.score(), -123);
+            assert_eq!(decoded.result(), GameResult::WhiteWin);
+            assert_eq!(decoded.to_board(hasher()).unwrap().hash(), board.hash());
+        }
+    }
+
+    #[test]
+    fn text_line() {
+        let board = board(Board::STARTPOS_FEN);
+        let mut position = Position::new(&board, 35);
+        position.set_result(GameResult::Draw);
+
+        let mut text = Vec::new();
+        position.write_text(&mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            format!("{} | 35 | 0.5\n", Board::STARTPOS_FEN)
+        );
+    }
+
+    #[test]
+    fn invalid_records() {
+        let board = board(Board::STARTPOS_FEN);
+        let bytes = Position::new(&board, 0).to_bytes();
+
+        let result = Position::from_bytes(&bytes[..31]);
+        assert!(matches!(result, Err(DatagenError::InvalidRecord(_))));
+
+        let mut wrong_result = bytes;
+        wrong_result[26] = 3;
+        let result = Position::from_bytes(&wrong_result);
+        assert!(matches!(result, Err(DatagenError::InvalidRecord(_))));
+
+        let mut wrong_piece = bytes;
+        wrong_piece[8] = 0b0111;
+        let result = Position::from_bytes(&wrong_piece);
+        assert!(matches!(result, Err(DatagenError::InvalidRecord(_))));
+    }
+}
+
+#[cfg(test)]
+mod game {
+    use base::{
+        r#move::Move,
+        testing::{board, hasher},
+    };
+    use engine::search::engine::Engine;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::game::{is_quiet, opening, play_game, GameSettings};
+
+    #[test]
+    fn noisy_positions() {
+        let open = board("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+
+        let capture = Move::parse(&open, "e4d5".to_string()).unwrap();
+        let quiet = Move::parse(&open, "g1f3".to_string()).unwrap();
+        assert!(!is_quiet(&open, capture));
+        assert!(is_quiet(&open, quiet));
+
+        // The side to move is in check, even though the move blocks it.
+        let check = board("rnbqkbnr/ppp2ppp/8/1B1pp3/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 3");
+        let block = Move::parse(&check, "c7c6".to_string()).unwrap();
+        assert!(!is_quiet(&check, block));
+    }
+
+    #[test]
+    fn random_opening() {
+        let mut rand = StdRng::seed_from_u64(42);
+
+        // The random moves can lead to a mate, which is never returned.
+        let mut openings = 0;
+        for _ in 0..20 {
+            if let Some(board) = opening(&hasher(), None, 8, &mut rand) {
+                assert_eq!(board.fullmoves(), 5);
+                assert!(board.outcome().is_ongoing());
+                openings += 1;
+            }
+        }
+
+        assert!(openings > 0);
+    }
+
+    #[test]
+    fn labelled_game() {
+        let mut rand = StdRng::seed_from_u64(42);
+        let engine = Engine::new(1);
+        let settings = GameSettings {
+            nodes: 500,
+            random_plies: 4,
+        };
+
+        let positions = loop {
+            if let Some(positions) =
+                play_game(&engine, &hasher(), None, &settings, &mut rand).unwrap()
+            {
+                break positions;
+            }
+        };
+
+        assert!(!positions.is_empty());
+        let result = positions[0].result();
+        assert!(positions.iter().all(|position| position.result() == result));
+
+        for position in positions {
+            let board = position.to_board(hasher()).unwrap();
+            assert!(!board.is_check());
+        }
+    }
+}
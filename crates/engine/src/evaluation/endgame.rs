@@ -0,0 +1,430 @@
+use std::fmt::{self, Display};
+
+use base::{
+    bitboard::constants::FILES,
+    board::{color::Color, piece::Piece, Board},
+    square::Square,
+};
+use lookup::{
+    utils::kpk::{kpk_index, KPK_POSITIONS},
+    KPK_BITBASE,
+};
+
+// The score of a won endgame, which is above any normal evaluation but far
+// below the mate scores of the search.
+pub const KNOWN_WIN: i32 = 10_000;
+
+// The bonuses that drive the losing king to the edge and the winning king
+// towards it, so the search finds the mating net without a tablebase.
+pub const PUSH_TO_EDGE: i32 = 20;
+pub const PUSH_TO_CORNER: i32 = 20;
+pub const PUSH_CLOSE: i32 = 10;
+pub const PUSH_PAWN: i32 = 20;
+
+// The scale factors are applied to the evaluation of the stronger side and
+// are out of `SCALE_NORMAL`.
+pub const SCALE_NORMAL: i32 = 64;
+pub const SCALE_OPPOSITE_BISHOPS: i32 = 22;
+pub const SCALE_OPPOSITE_BISHOPS_PIECES: i32 = 46;
+pub const SCALE_PAWNLESS: i32 = 16;
+pub const SCALE_DRAW: i32 = 0;
+
+const PIECES: [Piece; 5] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+];
+
+/// The amount of every piece on the board for both colors. Positions with
+/// the same signature are the same endgame, no matter where the pieces are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialSignature {
+    counts: [[u8; Piece::COUNT]; Color::COUNT],
+}
+
+impl MaterialSignature {
+    pub fn new(board: &Board) -> Self {
+        let mut counts = [[0; Piece::COUNT]; Color::COUNT];
+        for color in [Color::White, Color::Black] {
+            for piece in PIECES {
+                counts[color.index()][piece.index()] = board.get_piece_count(color, piece) as u8;
+            }
+        }
+
+        Self { counts }
+    }
+
+    /// Parses a signature in the usual notation, like `KBNvK`, where the
+    /// pieces of white come first. Returns `None` if it is not one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+
+        let mut counts = [[0; Piece::COUNT]; Color::COUNT];
+        for (color, pieces) in [(Color::White, white), (Color::Black, black)] {
+            let pieces = pieces.strip_prefix('K')?;
+            for letter in pieces.chars() {
+                let piece = match letter {
+                    'P' => Piece::Pawn,
+                    'N' => Piece::Knight,
+                    'B' => Piece::Bishop,
+                    'R' => Piece::Rook,
+                    'Q' => Piece::Queen,
+                    _ => return None,
+                };
+
+                counts[color.index()][piece.index()] += 1;
+            }
+        }
+
+        Some(Self { counts })
+    }
+
+    /// The same endgame with the colors swapped.
+    pub fn flipped(&self) -> Self {
+        let [black, white] = self.counts;
+        Self {
+            counts: [white, black],
+        }
+    }
+
+    /// The amount of pieces on the board, including both kings.
+    pub fn piece_count(&self) -> usize {
+        let pieces: usize = self
+            .counts
+            .iter()
+            .flatten()
+            .map(|&count| count as usize)
+            .sum();
+        pieces + Color::COUNT
+    }
+
+    #[inline(always)]
+    pub const fn count(&self, color: Color, piece: Piece) -> u8 {
+        self.counts[color.index()][piece.index()]
+    }
+
+    /// Returns true if the color has exactly the given amount of pawns,
+    /// knights, bishops, rooks and queens.
+    pub fn is(&self, color: Color, pieces: [u8; 5]) -> bool {
+        PIECES
+            .iter()
+            .zip(pieces)
+            .all(|(&piece, count)| self.count(color, piece) == count)
+    }
+
+    /// Returns true if the color only has its king left.
+    #[inline(always)]
+    pub fn is_bare(&self, color: Color) -> bool {
+        self.is(color, [0, 0, 0, 0, 0])
+    }
+
+    /// The value of all pieces of the color apart from the pawns.
+    pub fn non_pawn_material(&self, color: Color) -> i32 {
+        PIECES[1..]
+            .iter()
+            .map(|&piece| self.count(color, piece) as i32 * piece.get_estimate_value())
+            .sum()
+    }
+
+    #[inline(always)]
+    fn minors(&self, color: Color) -> u8 {
+        self.count(color, Piece::Knight) + self.count(color, Piece::Bishop)
+    }
+
+    #[inline(always)]
+    fn majors(&self, color: Color) -> u8 {
+        self.count(color, Piece::Rook) + self.count(color, Piece::Queen)
+    }
+}
+
+impl Display for MaterialSignature {
+    /// Writes the signature in the usual notation, like `KBNvK`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pieces = [
+            (Piece::Queen, 'Q'),
+            (Piece::Rook, 'R'),
+            (Piece::Bishop, 'B'),
+            (Piece::Knight, 'N'),
+            (Piece::Pawn, 'P'),
+        ];
+
+        for (index, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            if index > 0 {
+                write!(f, "v")?;
+            }
+
+            write!(f, "K")?;
+            for (piece, letter) in pieces {
+                for _ in 0..self.count(color, piece) {
+                    write!(f, "{}", letter)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An endgame whose evaluation is known, so the normal evaluation is
+/// replaced by a specialised one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endgame {
+    /// Neither side can win, like KBvK, KNNvK or a bishop of the wrong
+    /// color with rook pawns.
+    Draw,
+    /// The stronger side mates the bare king with a queen, a rook or the
+    /// bishop pair, like KQvK and KRvK.
+    Mate(Color),
+    /// The stronger side mates the bare king with a bishop and a knight,
+    /// which is only possible in a corner of the bishop's color.
+    BishopKnightMate(Color),
+    /// The stronger side has a single pawn against the bare king, which is
+    /// looked up in the KPK bitbase.
+    PawnVsKing(Color),
+}
+
+impl Endgame {
+    /// Returns the endgame of the board, if it is one of the known ones.
+    pub fn probe(board: &Board, signature: &MaterialSignature) -> Option<Self> {
+        for (strong, weak) in [(Color::White, Color::Black), (Color::Black, Color::White)] {
+            if !signature.is_bare(weak) {
+                continue;
+            }
+
+            if signature.is(strong, [0, 1, 1, 0, 0]) {
+                return Some(Self::BishopKnightMate(strong));
+            }
+
+            if signature.is(strong, [0, 2, 0, 0, 0]) {
+                return Some(Self::Draw);
+            }
+
+            if signature.is(strong, [1, 0, 0, 0, 0]) {
+                return Some(Self::PawnVsKing(strong));
+            }
+
+            let bishops = board.get_piece_board(strong, Piece::Bishop);
+            let light_bishops = bishops.filter(|square| square.is_white()).count();
+            let bishop_pair = light_bishops > 0 && light_bishops < bishops.count_ones();
+            if signature.majors(strong) > 0 || bishop_pair {
+                return Some(Self::Mate(strong));
+            }
+
+            if is_wrong_bishop(board, signature, strong) {
+                return Some(Self::Draw);
+            }
+        }
+
+        let pawns =
+            signature.count(Color::White, Piece::Pawn) + signature.count(Color::Black, Piece::Pawn);
+        let majors = signature.majors(Color::White) + signature.majors(Color::Black);
+        if pawns == 0
+            && majors == 0
+            && signature.minors(Color::White) <= 1
+            && signature.minors(Color::Black) <= 1
+        {
+            return Some(Self::Draw);
+        }
+
+        None
+    }
+
+    /// The evaluation of the endgame from the view of the active color.
+    pub fn evaluate(&self, board: &Board, signature: &MaterialSignature, active: Color) -> i32 {
+        let (strong, eval) = match *self {
+            Self::Draw => return 0,
+            Self::Mate(strong) => {
+                let weak_king = board.get_king_square(strong.other());
+                let mut eval = mate_eval(board, signature, strong);
+                eval += PUSH_TO_EDGE * center_distance(weak_king);
+                (strong, eval)
+            }
+            Self::BishopKnightMate(strong) => {
+                let weak_king = board.get_king_square(strong.other());
+                let bishop = board.get_piece_board(strong, Piece::Bishop).pop_trailing();
+                let corners = match bishop.is_white() == Square::from_index(0).is_white() {
+                    true => [Square::from_index(0), Square::from_index(63)],
+                    false => [Square::from_index(7), Square::from_index(56)],
+                };
+
+                // The king is driven to the edge first, as it can only be
+                // pushed to the right corner along it.
+                let corner_distance = corners
+                    .iter()
+                    .map(|&corner| manhattan_distance(weak_king, corner))
+                    .min()
+                    .unwrap();
+
+                let mut eval = mate_eval(board, signature, strong);
+                eval += PUSH_TO_EDGE * center_distance(weak_king);
+                eval += PUSH_TO_CORNER * (14 - corner_distance);
+                (strong, eval)
+            }
+            Self::PawnVsKing(strong) => {
+                if !probe_kpk(board, strong, active) {
+                    return 0;
+                }
+
+                // The pawn still has to promote, so it is pushed forward.
+                let pawn = board.get_piece_board(strong, Piece::Pawn).pop_trailing();
+                let rank = match strong {
+                    Color::White => pawn.rank(),
+                    Color::Black => 7 - pawn.rank(),
+                } as i32;
+
+                let eval = KNOWN_WIN + Piece::Pawn.get_estimate_value() + PUSH_PAWN * rank;
+                (strong, eval)
+            }
+        };
+
+        match active == strong {
+            true => eval,
+            false => -eval,
+        }
+    }
+}
+
+/// Evaluates the board if it is a known endgame, from the view of the
+/// active color.
+pub fn evaluate_endgame(board: &Board, active: Color) -> Option<i32> {
+    let signature = MaterialSignature::new(board);
+    let endgame = Endgame::probe(board, &signature)?;
+    Some(endgame.evaluate(board, &signature, active))
+}
+
+/// The scale factor of the endgame score from the view of the active color,
+/// which is lower in endgames that are hard to win even with more material.
+pub fn evaluation_scale(board: &Board, active: Color, eval: i32) -> i32 {
+    let strong = match eval > 0 {
+        true => active,
+        false => active.other(),
+    };
+
+    let signature = MaterialSignature::new(board);
+    scale_factor(board, &signature, strong)
+}
+
+/// The scale factor of the evaluation of the stronger side. Opposite
+/// colored bishops are drawish, even more so without other pieces, and so
+/// are pawnless endgames where the stronger side is up less than a rook.
+pub fn scale_factor(board: &Board, signature: &MaterialSignature, strong: Color) -> i32 {
+    let weak = strong.other();
+
+    if signature.count(strong, Piece::Pawn) == 0 {
+        let difference = signature.non_pawn_material(strong) - signature.non_pawn_material(weak);
+        if difference <= Piece::Bishop.get_estimate_value() {
+            return match signature.non_pawn_material(strong) < Piece::Rook.get_estimate_value() {
+                true => SCALE_DRAW,
+                false => SCALE_PAWNLESS,
+            };
+        }
+    }
+
+    if signature.count(strong, Piece::Bishop) == 1 && signature.count(weak, Piece::Bishop) == 1 {
+        let strong_bishop = board.get_piece_board(strong, Piece::Bishop).pop_trailing();
+        let weak_bishop = board.get_piece_board(weak, Piece::Bishop).pop_trailing();
+
+        if strong_bishop.is_white() != weak_bishop.is_white() {
+            let only_bishops = [strong, weak].iter().all(|&color| {
+                signature.count(color, Piece::Knight) == 0 && signature.majors(color) == 0
+            });
+
+            return match only_bishops {
+                true => SCALE_OPPOSITE_BISHOPS,
+                false => SCALE_OPPOSITE_BISHOPS_PIECES,
+            };
+        }
+    }
+
+    SCALE_NORMAL
+}
+
+/// A bishop that doesn't control the promotion square of its rook pawns
+/// can't drive the defending king out of the corner.
+fn is_wrong_bishop(board: &Board, signature: &MaterialSignature, strong: Color) -> bool {
+    let pawn_count = signature.count(strong, Piece::Pawn);
+    if pawn_count == 0 || !signature.is(strong, [pawn_count, 0, 1, 0, 0]) {
+        return false;
+    }
+
+    let pawns = board.get_piece_board(strong, Piece::Pawn);
+    let file = match pawns {
+        pawns if (pawns & !FILES[0]).is_empty() => 0,
+        pawns if (pawns & !FILES[7]).is_empty() => 7,
+        _ => return false,
+    };
+
+    let promotion = match strong {
+        Color::White => Square::new(7, file),
+        Color::Black => Square::new(0, file),
+    };
+
+    let bishop = board.get_piece_board(strong, Piece::Bishop).pop_trailing();
+    let weak_king = board.get_king_square(strong.other());
+
+    bishop.is_white() != promotion.is_white() && distance(weak_king, promotion) <= 1
+}
+
+/// Returns true if the stronger side wins the KPK endgame. The bitbase only
+/// contains positions of white with the pawn on the files a to d, so the
+/// board is flipped and mirrored to match them.
+pub fn probe_kpk(board: &Board, strong: Color, active: Color) -> bool {
+    let pawn = board.get_piece_board(strong, Piece::Pawn).pop_trailing();
+    let flip = match strong {
+        Color::White => 0,
+        Color::Black => 56,
+    };
+    let mirror = match pawn.file() > 3 {
+        true => 7,
+        false => 0,
+    };
+
+    let normalize = |square: Square| square.index() as usize ^ flip ^ mirror;
+    let index = kpk_index(
+        active == strong,
+        normalize(board.get_king_square(strong)),
+        normalize(board.get_king_square(strong.other())),
+        normalize(pawn),
+    );
+
+    debug_assert!(index < KPK_POSITIONS);
+    KPK_BITBASE[index / 64] >> (index % 64) & 1 == 1
+}
+
+/// The base of the evaluation of a won endgame, which still prefers more
+/// material and the kings close to each other.
+fn mate_eval(board: &Board, signature: &MaterialSignature, strong: Color) -> i32 {
+    let strong_king = board.get_king_square(strong);
+    let weak_king = board.get_king_square(strong.other());
+
+    let pawns = signature.count(strong, Piece::Pawn) as i32;
+    let material = signature.non_pawn_material(strong) + pawns * Piece::Pawn.get_estimate_value();
+
+    KNOWN_WIN + material + PUSH_CLOSE * (7 - distance(strong_king, weak_king))
+}
+
+/// The amount of king moves between the squares.
+#[inline(always)]
+fn distance(first: Square, second: Square) -> i32 {
+    let ranks = (first.rank() as i32 - second.rank() as i32).abs();
+    let files = (first.file() as i32 - second.file() as i32).abs();
+    ranks.max(files)
+}
+
+#[inline(always)]
+fn manhattan_distance(first: Square, second: Square) -> i32 {
+    let ranks = (first.rank() as i32 - second.rank() as i32).abs();
+    let files = (first.file() as i32 - second.file() as i32).abs();
+    ranks + files
+}
+
+/// The distance of the square to the four center squares, from 0 in the
+/// center to 6 in the corners.
+#[inline(always)]
+fn center_distance(square: Square) -> i32 {
+    let rank = square.rank() as i32;
+    let file = square.file() as i32;
+    (3 - rank).max(rank - 4) + (3 - file).max(file - 4)
+}
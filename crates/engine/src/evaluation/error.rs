@@ -0,0 +1,55 @@
+use std::num::ParseIntError;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub enum ParameterError {
+    UnknownParameter(#[from] UnknownParameter),
+    WrongValueCount(#[from] WrongValueCount),
+    MissingSeparator(#[from] MissingSeparator),
+    ParseInt(#[from] ParseIntError),
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("the parameter '{name}' is unknown")]
+pub struct UnknownParameter {
+    name: String,
+}
+
+impl UnknownParameter {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the parameter '{name}' needs {expected} values, but {given} were given")]
+pub struct WrongValueCount {
+    name: String,
+    expected: usize,
+    given: usize,
+}
+
+impl WrongValueCount {
+    pub fn new(name: impl Into<String>, expected: usize, given: usize) -> Self {
+        Self {
+            name: name.into(),
+            expected,
+            given,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the line '{line}' is missing the '=' between the name and the values")]
+pub struct MissingSeparator {
+    line: String,
+}
+
+impl MissingSeparator {
+    pub fn new(line: impl Into<String>) -> Self {
+        Self { line: line.into() }
+    }
+}
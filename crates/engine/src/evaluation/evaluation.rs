@@ -1,19 +1,220 @@
-use base::board::{color::Color, Board};
+use base::board::{color::Color, piece::Piece, Board};
+
+use super::{
+    endgame::{evaluate_endgame, evaluation_scale, SCALE_NORMAL},
+    king_safety::evaluate_king_safety,
+    mobility::evaluate_mobility,
+    parameters::{Parameters, Psqt},
+    pawns::{evaluate_color, evaluate_passed_kings, PawnEntry, PawnTable},
+    trace::{EvaluationTracer, NoTrace, Term},
+};
+
+pub const BISHOP_PAIR: i32 = 50;
+
+const PIECES: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
 
 pub fn evaluate(board: &Board, active: Color) -> i32 {
-    let mut eval = 0;
+    evaluate_with(board, active, &Parameters::DEFAULT)
+}
 
-    eval += pesto_evaluation(board, active);
-    eval += get_bishop_pair_difference(board, active);
+/// Evaluates the board just like `evaluate`, but with the given weights
+/// instead of the compiled-in ones. Known endgames are evaluated by their
+/// specialised evaluation instead, even if the board has a network.
+pub fn evaluate_with(board: &Board, active: Color, parameters: &Parameters) -> i32 {
+    if let Some(eval) = evaluate_endgame(board, active) {
+        return eval;
+    }
 
-    eval
+    if let Some(eval) = nnue_evaluation(board, active) {
+        return eval;
+    }
+
+    let pawns = PawnEntry::new(board, parameters);
+    evaluate_traced(board, active, parameters, &pawns, &mut NoTrace)
 }
 
-pub(crate) fn pesto_evaluation(board: &Board, active: Color) -> i32 {
-    let midgame_score = board.midgame(active) - board.midgame(active.other());
-    let endgame_score = board.endgame(active) - board.endgame(active.other());
+/// Evaluates the board just like `evaluate_with`, but the pawn structure
+/// is taken from the table if it was already evaluated.
+pub fn evaluate_cached(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    pawn_table: &mut PawnTable,
+) -> i32 {
+    if let Some(eval) = evaluate_endgame(board, active) {
+        return eval;
+    }
+
+    if let Some(eval) = nnue_evaluation(board, active) {
+        return eval;
+    }
 
-    let mut midgame_phase = board.gamephase();
+    let pawns = PawnEntry::probe(pawn_table, board, parameters);
+    evaluate_traced(board, active, parameters, &pawns, &mut NoTrace)
+}
+
+/// The evaluation of the network, if the board has one. The parameters
+/// are only used by the handcrafted evaluation, which stays the fallback.
+#[inline(always)]
+pub fn nnue_evaluation(board: &Board, active: Color) -> Option<i32> {
+    let network = board.network()?;
+    Some(network.evaluate(board.accumulator(), active))
+}
+
+pub(crate) fn evaluate_traced<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    pawns: &PawnEntry,
+    trace: &mut T,
+) -> i32 {
+    let terms = [
+        pesto_evaluation(board, active, parameters, trace),
+        pawn_evaluation(board, active, parameters, pawns, trace),
+        mobility_evaluation(board, active, parameters, trace),
+        king_safety_evaluation(board, active, parameters, trace),
+        get_bishop_pair_difference(board, active, parameters, trace),
+    ];
+
+    let (midgame, endgame) = terms.iter().fold(
+        (0, 0),
+        |(midgame, endgame), &(term_midgame, term_endgame)| {
+            (midgame + term_midgame, endgame + term_endgame)
+        },
+    );
+
+    // The scale factor only describes how drawish an endgame is, so it
+    // leaves the midgame score untouched.
+    let scale = evaluation_scale(board, active, endgame);
+    trace.scale(scale);
+
+    taper(board, midgame, endgame * scale / SCALE_NORMAL)
+}
+
+pub(crate) fn pesto_evaluation<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    trace: &mut T,
+) -> (i32, i32) {
+    let (own_midgame, own_endgame) = psqt_score(board, active, parameters);
+    let (other_midgame, other_endgame) = psqt_score(board, active.other(), parameters);
+
+    if T::ENABLED {
+        for (color, (midgame, endgame)) in [
+            (active, (own_midgame, own_endgame)),
+            (active.other(), (other_midgame, other_endgame)),
+        ] {
+            let (material_midgame, material_endgame) = material(board, color, parameters);
+            trace.add(Term::Material, color, material_midgame, material_endgame);
+
+            let psqt_midgame = midgame - material_midgame;
+            let psqt_endgame = endgame - material_endgame;
+            trace.add(Term::Psqt, color, psqt_midgame, psqt_endgame);
+        }
+    }
+
+    (own_midgame - other_midgame, own_endgame - other_endgame)
+}
+
+/// The material and piece square score of the color. The board keeps the
+/// score of the default tables up to date, so it only has to be summed up
+/// for other tables.
+#[inline(always)]
+fn psqt_score(board: &Board, color: Color, parameters: &Parameters) -> (i32, i32) {
+    let Some(psqt) = &parameters.psqt else {
+        return (board.midgame(color), board.endgame(color));
+    };
+
+    let mut midgame = 0;
+    let mut endgame = 0;
+    for piece in PIECES {
+        let (midgame_table, endgame_table) = psqt.tables(piece);
+        for square in board.get_piece_board(color, piece) {
+            let index = square.get_relative_index(color);
+            midgame += psqt.midgame_piece_values[piece.index()] + midgame_table[index];
+            endgame += psqt.endgame_piece_values[piece.index()] + endgame_table[index];
+        }
+    }
+
+    (midgame, endgame)
+}
+
+pub(crate) fn pawn_evaluation<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    pawns: &PawnEntry,
+    trace: &mut T,
+) -> (i32, i32) {
+    let mut midgame_score = pawns.midgame();
+    let mut endgame_score = pawns.endgame();
+
+    let white_kings = evaluate_passed_kings(board, pawns, Color::White, parameters);
+    let black_kings = evaluate_passed_kings(board, pawns, Color::Black, parameters);
+    endgame_score += white_kings - black_kings;
+
+    if T::ENABLED {
+        for (color, kings) in [(Color::White, white_kings), (Color::Black, black_kings)] {
+            let (midgame, endgame, _) = evaluate_color(board, color, parameters);
+            trace.add(Term::PawnStructure, color, midgame, endgame + kings);
+        }
+    }
+
+    if active == Color::Black {
+        midgame_score = -midgame_score;
+        endgame_score = -endgame_score;
+    }
+
+    (midgame_score, endgame_score)
+}
+
+pub(crate) fn mobility_evaluation<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    trace: &mut T,
+) -> (i32, i32) {
+    let (own_midgame, own_endgame) = evaluate_mobility(board, active, parameters);
+    let (other_midgame, other_endgame) = evaluate_mobility(board, active.other(), parameters);
+
+    trace.add(Term::Mobility, active, own_midgame, own_endgame);
+    trace.add(Term::Mobility, active.other(), other_midgame, other_endgame);
+
+    (own_midgame - other_midgame, own_endgame - other_endgame)
+}
+
+pub(crate) fn king_safety_evaluation<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    trace: &mut T,
+) -> (i32, i32) {
+    let own_midgame = evaluate_king_safety(board, active, parameters);
+    let other_midgame = evaluate_king_safety(board, active.other(), parameters);
+
+    trace.add(Term::KingSafety, active, own_midgame, 0);
+    trace.add(Term::KingSafety, active.other(), other_midgame, 0);
+
+    (own_midgame - other_midgame, 0)
+}
+
+/// Interpolates between the midgame and the endgame score by the amount of
+/// pieces that are left on the board.
+/// Source: https://www.chessprogramming.org/Tapered_Eval
+pub(crate) fn taper(board: &Board, midgame_score: i32, endgame_score: i32) -> i32 {
+    taper_phase(board.gamephase(), midgame_score, endgame_score)
+}
+
+pub(crate) fn taper_phase(phase: i32, midgame_score: i32, endgame_score: i32) -> i32 {
+    let mut midgame_phase = phase;
     if midgame_phase > 24 {
         midgame_phase = 24;
     }
@@ -26,21 +227,43 @@ pub(crate) fn pesto_evaluation(board: &Board, active: Color) -> i32 {
     eval
 }
 
-pub(crate) fn get_bishop_pair_difference(board: &Board, active: Color) -> i32 {
-    let mut eval = 0;
+pub(crate) fn get_bishop_pair_difference<T: EvaluationTracer>(
+    board: &Board,
+    active: Color,
+    parameters: &Parameters,
+    trace: &mut T,
+) -> (i32, i32) {
+    let own = get_bishop_pair_eval(board, active, parameters);
+    let other = get_bishop_pair_eval(board, active.other(), parameters);
 
-    eval += get_bishop_pair_eval(board, active);
-    eval -= get_bishop_pair_eval(board, active.other());
+    trace.add(Term::BishopPair, active, own, own);
+    trace.add(Term::BishopPair, active.other(), other, other);
 
-    eval
+    (own - other, own - other)
 }
 
-pub(crate) fn get_bishop_pair_eval(board: &Board, color: Color) -> i32 {
+pub(crate) fn get_bishop_pair_eval(board: &Board, color: Color, parameters: &Parameters) -> i32 {
     let mut eval = 0;
 
     if board.has_bishop_pair(color) {
-        eval += 50;
+        eval += parameters.bishop_pair;
     }
 
     eval
 }
+
+/// The midgame and endgame value of the pieces of the color, without the
+/// bonuses of the squares they stand on.
+fn material(board: &Board, color: Color, parameters: &Parameters) -> (i32, i32) {
+    let psqt = parameters.psqt.as_ref().unwrap_or(&Psqt::DEFAULT);
+
+    let mut midgame = 0;
+    let mut endgame = 0;
+    for piece in PIECES {
+        let count = board.get_piece_board(color, piece).count_ones() as i32;
+        midgame += count * psqt.midgame_piece_values[piece.index()];
+        endgame += count * psqt.endgame_piece_values[piece.index()];
+    }
+
+    (midgame, endgame)
+}
@@ -0,0 +1,158 @@
+use base::{
+    bitboard::{
+        constants::{FILES, RANKS},
+        Bitboard,
+    },
+    board::{color::Color, piece::Piece, Board},
+    square::Square,
+};
+
+use super::{mobility::piece_attacks, parameters::Parameters};
+
+// The attack units of a piece for every square of the king zone it attacks.
+pub const KNIGHT_ATTACK_WEIGHT: usize = 2;
+pub const BISHOP_ATTACK_WEIGHT: usize = 2;
+pub const ROOK_ATTACK_WEIGHT: usize = 3;
+pub const QUEEN_ATTACK_WEIGHT: usize = 5;
+
+// A single piece can rarely mate on its own, thus the attack units are
+// only counted if at least this many pieces attack the king zone.
+pub const MIN_ATTACKERS: usize = 2;
+
+// The penalty is indexed by the attack units and grows quickly, as the
+// king is only in danger once a few pieces work together.
+// Source: https://www.chessprogramming.org/King_Safety
+#[rustfmt::skip]
+pub const SAFETY_TABLE: [i32; 100] = [
+      0,   0,   1,   2,   3,   5,   7,   9,  12,  15,
+     18,  22,  26,  30,  35,  39,  44,  50,  56,  62,
+     68,  75,  82,  85,  89,  97, 105, 113, 122, 131,
+    140, 150, 169, 180, 191, 202, 213, 225, 237, 248,
+    260, 272, 283, 295, 307, 319, 330, 342, 354, 366,
+    377, 389, 401, 412, 424, 436, 448, 459, 471, 483,
+    494, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+// The bonuses for own pawns on the king file and the adjacent files, which
+// are one or two ranks in front of the king.
+pub const SHIELD_CLOSE: i32 = 12;
+pub const SHIELD_FAR: i32 = 6;
+
+// The penalties for the files next to the king without own pawns. An open
+// file without any pawns gets both penalties.
+pub const SEMI_OPEN_FILE_PENALTY: i32 = 15;
+pub const OPEN_FILE_PENALTY: i32 = 10;
+
+/// Returns the king safety of the color. King safety only matters while
+/// there are enough pieces left to attack the king, so this is a midgame
+/// score.
+pub fn evaluate_king_safety(board: &Board, color: Color, parameters: &Parameters) -> i32 {
+    let king = board.get_king_square(color);
+
+    let mut eval = 0;
+    eval += evaluate_pawn_shield(board, king, color, parameters);
+    eval -= evaluate_open_files(board, king, color, parameters);
+    eval -= evaluate_king_attacks(board, king, color, parameters);
+
+    eval
+}
+
+/// Counts the pieces of the opponent that attack the squares around the
+/// king and looks up the penalty for their attack units.
+fn evaluate_king_attacks(
+    board: &Board,
+    king: Square,
+    color: Color,
+    parameters: &Parameters,
+) -> i32 {
+    let zone = king.get_king_moves() | king;
+    let occupied = board.get_all_occupied();
+    let other = color.other();
+
+    let mut attackers = 0;
+    let mut units = 0;
+
+    let pieces = [
+        (Piece::Knight, parameters.knight_attack_weight),
+        (Piece::Bishop, parameters.bishop_attack_weight),
+        (Piece::Rook, parameters.rook_attack_weight),
+        (Piece::Queen, parameters.queen_attack_weight),
+    ];
+    for (piece, weight) in pieces {
+        for square in board.get_piece_board(other, piece) {
+            let attacks = piece_attacks(piece, square, occupied) & zone;
+            if !attacks.is_empty() {
+                attackers += 1;
+                units += weight * attacks.count_ones();
+            }
+        }
+    }
+
+    if attackers < parameters.min_attackers {
+        return 0;
+    }
+
+    let table = &parameters.safety_table;
+    table[units.min(table.len() - 1)]
+}
+
+fn evaluate_pawn_shield(board: &Board, king: Square, color: Color, parameters: &Parameters) -> i32 {
+    let pawns = board.get_piece_board(color, Piece::Pawn) & shield_files(king);
+
+    let mut eval = 0;
+    eval +=
+        parameters.shield_close * (pawns & relative_rank_bb(king, color, 1)).count_ones() as i32;
+    eval += parameters.shield_far * (pawns & relative_rank_bb(king, color, 2)).count_ones() as i32;
+
+    eval
+}
+
+fn evaluate_open_files(board: &Board, king: Square, color: Color, parameters: &Parameters) -> i32 {
+    let own_pawns = board.get_piece_board(color, Piece::Pawn);
+    let other_pawns = board.get_piece_board(color.other(), Piece::Pawn);
+
+    let mut eval = 0;
+    for file in king_files(king) {
+        let file = FILES[file];
+        if (own_pawns & file).is_empty() {
+            eval += parameters.semi_open_file_penalty;
+
+            if (other_pawns & file).is_empty() {
+                eval += parameters.open_file_penalty;
+            }
+        }
+    }
+
+    eval
+}
+
+/// The file of the king and the files next to it.
+#[inline(always)]
+fn king_files(king: Square) -> std::ops::RangeInclusive<usize> {
+    let file = king.file() as usize;
+    file.saturating_sub(1)..=(file + 1).min(7)
+}
+
+#[inline(always)]
+fn shield_files(king: Square) -> Bitboard {
+    king_files(king).fold(Bitboard::default(), |files, file| files | FILES[file])
+}
+
+/// The rank that is the given amount of ranks in front of the square from
+/// the view of the color, or an empty board if it is outside of the board.
+#[inline(always)]
+fn relative_rank_bb(square: Square, color: Color, offset: i8) -> Bitboard {
+    let rank = match color {
+        Color::White => square.rank() as i8 + offset,
+        Color::Black => square.rank() as i8 - offset,
+    };
+
+    if !(0..8).contains(&rank) {
+        return Bitboard::default();
+    }
+
+    RANKS[rank as usize]
+}
@@ -0,0 +1,88 @@
+use base::{
+    bitboard::Bitboard,
+    board::{color::Color, piece::Piece, Board},
+    square::Square,
+};
+
+use super::parameters::Parameters;
+
+// The bonuses are indexed by the amount of squares the piece can move to.
+// A piece that is trapped gets a penalty, while a piece with a lot of
+// squares gets a bonus, which is bigger in the endgame.
+// Source: https://www.chessprogramming.org/Mobility
+#[rustfmt::skip]
+pub const KNIGHT_MOBILITY: [(i32, i32); 9] = [
+    (-31, -40), (-26, -28), (-6, -15), (-2, -8), (2, 3), (6, 6), (11, 8), (14, 10), (16, 12),
+];
+
+#[rustfmt::skip]
+pub const BISHOP_MOBILITY: [(i32, i32); 14] = [
+    (-24, -30), (-10, -12), (8, -2), (13, 6), (19, 12), (25, 21), (28, 27),
+    (31, 29), (32, 33), (34, 36), (40, 39), (41, 43), (45, 44), (49, 48),
+];
+
+#[rustfmt::skip]
+pub const ROOK_MOBILITY: [(i32, i32); 15] = [
+    (-30, -39), (-10, -9), (1, 11), (2, 20), (2, 35), (6, 50), (11, 52), (15, 60),
+    (20, 67), (20, 70), (21, 79), (24, 82), (28, 84), (29, 85), (31, 86),
+];
+
+#[rustfmt::skip]
+pub const QUEEN_MOBILITY: [(i32, i32); 28] = [
+    (-15, -24), (-6, -15), (-4, -4), (-4, 10), (10, 20), (11, 27), (12, 30),
+    (17, 37), (19, 39), (26, 48), (32, 48), (33, 50), (33, 60), (33, 64),
+    (34, 66), (34, 67), (36, 68), (36, 71), (38, 74), (40, 75), (46, 76),
+    (54, 84), (54, 84), (54, 86), (55, 91), (57, 91), (57, 96), (58, 110),
+];
+
+/// Returns the midgame and endgame mobility score of the color. Squares
+/// that are occupied by own pieces or attacked by opponent pawns are not
+/// counted, as the piece can't safely move there.
+pub fn evaluate_mobility(board: &Board, color: Color, parameters: &Parameters) -> (i32, i32) {
+    let occupied = board.get_all_occupied();
+    let area = !board.get_occupied(color) & !pawn_attacks(board, color.other());
+
+    let mut midgame = 0;
+    let mut endgame = 0;
+
+    let pieces = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+    for piece in pieces {
+        for square in board.get_piece_board(color, piece) {
+            let moves = (piece_attacks(piece, square, occupied) & area).count_ones();
+            let (piece_midgame, piece_endgame) = match piece {
+                Piece::Knight => parameters.knight_mobility[moves],
+                Piece::Bishop => parameters.bishop_mobility[moves],
+                Piece::Rook => parameters.rook_mobility[moves],
+                Piece::Queen => parameters.queen_mobility[moves],
+                _ => unreachable!(),
+            };
+
+            midgame += piece_midgame;
+            endgame += piece_endgame;
+        }
+    }
+
+    (midgame, endgame)
+}
+
+#[inline(always)]
+pub(crate) fn piece_attacks(piece: Piece, square: Square, occupied: Bitboard) -> Bitboard {
+    match piece {
+        Piece::Knight => square.get_knight_moves(),
+        Piece::Bishop => square.get_bishop_attacks(occupied),
+        Piece::Rook => square.get_rook_attacks(occupied),
+        Piece::Queen => square.get_bishop_attacks(occupied) | square.get_rook_attacks(occupied),
+        _ => panic!("Only knights and sliders are supported"),
+    }
+}
+
+/// All the squares that are attacked by the pawns of the color.
+#[inline(always)]
+pub(crate) fn pawn_attacks(board: &Board, color: Color) -> Bitboard {
+    let mut attacks = Bitboard::default();
+    for square in board.get_piece_board(color, Piece::Pawn) {
+        attacks |= square.get_pawn_attacks(color);
+    }
+
+    attacks
+}
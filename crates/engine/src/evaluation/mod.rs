@@ -1,2 +1,12 @@
 pub mod evaluation;
 pub use evaluation::*;
+
+pub mod endgame;
+pub mod error;
+pub mod king_safety;
+pub mod mobility;
+pub mod parameters;
+pub mod pawns;
+pub mod trace;
+
+mod tests;
@@ -0,0 +1,481 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+use base::board::piece::Piece;
+use lookup::pesto::*;
+
+use crate::search::{negamax::FUTILITY_MARGINS, quiescence::QUEEN_VALUE};
+
+use super::{
+    error::{MissingSeparator, ParameterError, UnknownParameter, WrongValueCount},
+    king_safety::*,
+    mobility::*,
+    pawns::*,
+    BISHOP_PAIR,
+};
+
+/// A value that can be stored as a flat list of integers, so that all the
+/// parameters share the same file format.
+trait ParameterValue {
+    fn len(&self) -> usize;
+
+    fn write(&self, values: &mut Vec<i32>);
+
+    /// Reads the value from the slice, which has exactly `len` values.
+    fn read(&mut self, values: &[i32]);
+
+    fn rust_type(&self) -> String;
+
+    fn rust_value(&self) -> String;
+}
+
+impl ParameterValue for i32 {
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn write(&self, values: &mut Vec<i32>) {
+        values.push(*self);
+    }
+
+    fn read(&mut self, values: &[i32]) {
+        *self = values[0];
+    }
+
+    fn rust_type(&self) -> String {
+        "i32".to_string()
+    }
+
+    fn rust_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ParameterValue for usize {
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn write(&self, values: &mut Vec<i32>) {
+        values.push(*self as i32);
+    }
+
+    fn read(&mut self, values: &[i32]) {
+        *self = values[0].max(0) as usize;
+    }
+
+    fn rust_type(&self) -> String {
+        "usize".to_string()
+    }
+
+    fn rust_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ParameterValue for (i32, i32) {
+    fn len(&self) -> usize {
+        2
+    }
+
+    fn write(&self, values: &mut Vec<i32>) {
+        values.push(self.0);
+        values.push(self.1);
+    }
+
+    fn read(&mut self, values: &[i32]) {
+        self.0 = values[0];
+        self.1 = values[1];
+    }
+
+    fn rust_type(&self) -> String {
+        "(i32, i32)".to_string()
+    }
+
+    fn rust_value(&self) -> String {
+        format!("({}, {})", self.0, self.1)
+    }
+}
+
+impl<T: ParameterValue, const N: usize> ParameterValue for [T; N] {
+    fn len(&self) -> usize {
+        self.iter().map(|value| value.len()).sum()
+    }
+
+    fn write(&self, values: &mut Vec<i32>) {
+        for value in self {
+            value.write(values);
+        }
+    }
+
+    fn read(&mut self, mut values: &[i32]) {
+        for value in self {
+            let (current, rest) = values.split_at(value.len());
+            value.read(current);
+            values = rest;
+        }
+    }
+
+    fn rust_type(&self) -> String {
+        match self.first() {
+            Some(value) => format!("[{}; {}]", value.rust_type(), N),
+            None => "[i32; 0]".to_string(),
+        }
+    }
+
+    fn rust_value(&self) -> String {
+        let values = self
+            .iter()
+            .map(|value| value.rust_value())
+            .collect::<Vec<String>>();
+
+        format!("[{}]", values.join(", "))
+    }
+}
+
+/// Lists the fields of a parameter struct by their name, which is the name
+/// used in the parameter file as well.
+macro_rules! parameter_fields {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl $name {
+            fn fields(&self) -> Vec<(&'static str, &dyn ParameterValue)> {
+                vec![$((stringify!($field), &self.$field as &dyn ParameterValue)),*]
+            }
+
+            fn fields_mut(&mut self) -> Vec<(&'static str, &mut dyn ParameterValue)> {
+                vec![$((stringify!($field), &mut self.$field as &mut dyn ParameterValue)),*]
+            }
+        }
+    };
+}
+
+/// The piece values and piece square tables. The tables are indexed from
+/// the view of black, just like the PeSTO tables in the lookup crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psqt {
+    pub midgame_piece_values: [i32; Piece::COUNT],
+    pub endgame_piece_values: [i32; Piece::COUNT],
+    pub midgame_pawn_table: [i32; 64],
+    pub endgame_pawn_table: [i32; 64],
+    pub midgame_knight_table: [i32; 64],
+    pub endgame_knight_table: [i32; 64],
+    pub midgame_bishop_table: [i32; 64],
+    pub endgame_bishop_table: [i32; 64],
+    pub midgame_rook_table: [i32; 64],
+    pub endgame_rook_table: [i32; 64],
+    pub midgame_queen_table: [i32; 64],
+    pub endgame_queen_table: [i32; 64],
+    pub midgame_king_table: [i32; 64],
+    pub endgame_king_table: [i32; 64],
+}
+
+parameter_fields!(Psqt {
+    midgame_piece_values,
+    endgame_piece_values,
+    midgame_pawn_table,
+    endgame_pawn_table,
+    midgame_knight_table,
+    endgame_knight_table,
+    midgame_bishop_table,
+    endgame_bishop_table,
+    midgame_rook_table,
+    endgame_rook_table,
+    midgame_queen_table,
+    endgame_queen_table,
+    midgame_king_table,
+    endgame_king_table,
+});
+
+impl Psqt {
+    pub const DEFAULT: Psqt = Psqt {
+        midgame_piece_values: MIDGAME_PIECE_VALUE,
+        endgame_piece_values: ENDGAME_PIECE_VALUE,
+        midgame_pawn_table: MIDGAME_PAWN_TABLE,
+        endgame_pawn_table: ENDGAME_PAWN_TABLE,
+        midgame_knight_table: MIDGAME_KNIGHT_TABLE,
+        endgame_knight_table: ENDGAME_KNIGHT_TABLE,
+        midgame_bishop_table: MIDGAME_BISHOP_TABLE,
+        endgame_bishop_table: ENDGAME_BISHOP_TABLE,
+        midgame_rook_table: MIDGAME_ROOK_TABLE,
+        endgame_rook_table: ENDGAME_ROOK_TABLE,
+        midgame_queen_table: MIDGAME_QUEEN_TABLE,
+        endgame_queen_table: ENDGAME_QUEEN_TABLE,
+        midgame_king_table: MIDGAME_KING_TABLE,
+        endgame_king_table: ENDGAME_KING_TABLE,
+    };
+
+    /// The midgame and endgame tables of the piece.
+    pub fn tables(&self, piece: Piece) -> (&[i32; 64], &[i32; 64]) {
+        match piece {
+            Piece::Pawn => (&self.midgame_pawn_table, &self.endgame_pawn_table),
+            Piece::Knight => (&self.midgame_knight_table, &self.endgame_knight_table),
+            Piece::Bishop => (&self.midgame_bishop_table, &self.endgame_bishop_table),
+            Piece::Rook => (&self.midgame_rook_table, &self.endgame_rook_table),
+            Piece::Queen => (&self.midgame_queen_table, &self.endgame_queen_table),
+            Piece::King => (&self.midgame_king_table, &self.endgame_king_table),
+            Piece::None => panic!("There is no table for an empty square"),
+        }
+    }
+}
+
+impl Default for Psqt {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// All the weights of the evaluation and the pruning margins that depend on
+/// them. The defaults are the compiled-in constants, so the parameters can
+/// be tuned and compared without recompiling the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameters {
+    /// The board keeps the piece square scores up to date with every move.
+    /// Only if other tables are given, they are summed up during the
+    /// evaluation, which is a lot slower.
+    pub psqt: Option<Psqt>,
+    pub bishop_pair: i32,
+    pub doubled_penalty: (i32, i32),
+    pub isolated_penalty: (i32, i32),
+    pub backward_penalty: (i32, i32),
+    pub connected_midgame: [i32; 8],
+    pub connected_endgame: [i32; 8],
+    pub passed_midgame: [i32; 8],
+    pub passed_endgame: [i32; 8],
+    pub passed_king_weight: [i32; 8],
+    pub passed_own_king: i32,
+    pub passed_other_king: i32,
+    pub knight_mobility: [(i32, i32); 9],
+    pub bishop_mobility: [(i32, i32); 14],
+    pub rook_mobility: [(i32, i32); 15],
+    pub queen_mobility: [(i32, i32); 28],
+    pub knight_attack_weight: usize,
+    pub bishop_attack_weight: usize,
+    pub rook_attack_weight: usize,
+    pub queen_attack_weight: usize,
+    pub min_attackers: usize,
+    pub safety_table: [i32; 100],
+    pub shield_close: i32,
+    pub shield_far: i32,
+    pub semi_open_file_penalty: i32,
+    pub open_file_penalty: i32,
+    pub futility_margins: [i32; 3],
+    pub delta_margin: i32,
+}
+
+parameter_fields!(Parameters {
+    bishop_pair,
+    doubled_penalty,
+    isolated_penalty,
+    backward_penalty,
+    connected_midgame,
+    connected_endgame,
+    passed_midgame,
+    passed_endgame,
+    passed_king_weight,
+    passed_own_king,
+    passed_other_king,
+    knight_mobility,
+    bishop_mobility,
+    rook_mobility,
+    queen_mobility,
+    knight_attack_weight,
+    bishop_attack_weight,
+    rook_attack_weight,
+    queen_attack_weight,
+    min_attackers,
+    safety_table,
+    shield_close,
+    shield_far,
+    semi_open_file_penalty,
+    open_file_penalty,
+    futility_margins,
+    delta_margin,
+});
+
+impl Parameters {
+    pub const DEFAULT: Parameters = Parameters {
+        psqt: None,
+        bishop_pair: BISHOP_PAIR,
+        doubled_penalty: DOUBLED_PENALTY,
+        isolated_penalty: ISOLATED_PENALTY,
+        backward_penalty: BACKWARD_PENALTY,
+        connected_midgame: CONNECTED_MIDGAME,
+        connected_endgame: CONNECTED_ENDGAME,
+        passed_midgame: PASSED_MIDGAME,
+        passed_endgame: PASSED_ENDGAME,
+        passed_king_weight: PASSED_KING_WEIGHT,
+        passed_own_king: PASSED_OWN_KING,
+        passed_other_king: PASSED_OTHER_KING,
+        knight_mobility: KNIGHT_MOBILITY,
+        bishop_mobility: BISHOP_MOBILITY,
+        rook_mobility: ROOK_MOBILITY,
+        queen_mobility: QUEEN_MOBILITY,
+        knight_attack_weight: KNIGHT_ATTACK_WEIGHT,
+        bishop_attack_weight: BISHOP_ATTACK_WEIGHT,
+        rook_attack_weight: ROOK_ATTACK_WEIGHT,
+        queen_attack_weight: QUEEN_ATTACK_WEIGHT,
+        min_attackers: MIN_ATTACKERS,
+        safety_table: SAFETY_TABLE,
+        shield_close: SHIELD_CLOSE,
+        shield_far: SHIELD_FAR,
+        semi_open_file_penalty: SEMI_OPEN_FILE_PENALTY,
+        open_file_penalty: OPEN_FILE_PENALTY,
+        futility_margins: FUTILITY_MARGINS,
+        delta_margin: QUEEN_VALUE,
+    };
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParameterError> {
+        let content = fs::read_to_string(path)?;
+        content.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ParameterError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// The names of all the parameters, including the piece square tables.
+    pub fn names() -> Vec<&'static str> {
+        let mut names = Vec::new();
+        names.extend(Self::DEFAULT.fields().into_iter().map(|(name, _)| name));
+        names.extend(Psqt::DEFAULT.fields().into_iter().map(|(name, _)| name));
+        names
+    }
+
+    pub fn get(&self, name: &str) -> Option<Vec<i32>> {
+        let value = self.field(name)?;
+
+        let mut values = Vec::with_capacity(value.len());
+        value.write(&mut values);
+        Some(values)
+    }
+
+    /// The type and the value of the parameter as Rust source, so that the
+    /// parameters can be written back as constants.
+    pub fn rust_constant(&self, name: &str) -> Option<(String, String)> {
+        let value = self.field(name)?;
+        Some((value.rust_type(), value.rust_value()))
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn ParameterValue> {
+        let psqt = self.psqt.as_ref().unwrap_or(&Psqt::DEFAULT);
+        let mut fields = self.fields();
+        fields.extend(psqt.fields());
+
+        let (_, value) = fields.into_iter().find(|(field, _)| *field == name)?;
+        Some(value)
+    }
+
+    pub fn set(&mut self, name: &str, values: &[i32]) -> Result<(), ParameterError> {
+        if let Some((_, value)) = self
+            .fields_mut()
+            .into_iter()
+            .find(|(field, _)| *field == name)
+        {
+            return read_value(name, value, values);
+        }
+
+        let psqt = self.psqt.get_or_insert_with(Psqt::default);
+        let result = match psqt
+            .fields_mut()
+            .into_iter()
+            .find(|(field, _)| *field == name)
+        {
+            Some((_, value)) => read_value(name, value, values),
+            None => Err(UnknownParameter::new(name).into()),
+        };
+
+        // The default tables are already part of the board, so they don't
+        // have to be summed up during the evaluation.
+        if self.psqt.as_ref() == Some(&Psqt::DEFAULT) {
+            self.psqt = None;
+        }
+
+        result
+    }
+
+    /// The parameters that consist of a single value, which can be changed
+    /// with a spin option.
+    pub fn scalars(&self) -> Vec<(&'static str, i32)> {
+        self.fields()
+            .into_iter()
+            .filter(|(_, value)| value.len() == 1)
+            .map(|(name, value)| {
+                let mut values = Vec::with_capacity(1);
+                value.write(&mut values);
+                (name, values[0])
+            })
+            .collect()
+    }
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+fn read_value(
+    name: &str,
+    value: &mut dyn ParameterValue,
+    values: &[i32],
+) -> Result<(), ParameterError> {
+    if value.len() != values.len() {
+        return Err(WrongValueCount::new(name, value.len(), values.len()).into());
+    }
+
+    value.read(values);
+    Ok(())
+}
+
+/// Parses the parameters from lines of the form `name = 1 2 3`. Empty lines
+/// and comments starting with `#` are skipped. Parameters that are not
+/// given keep their default value.
+impl FromStr for Parameters {
+    type Err = ParameterError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut parameters = Parameters::default();
+
+        for line in input.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, values) = line
+                .split_once('=')
+                .ok_or_else(|| MissingSeparator::new(line))?;
+
+            let values = values
+                .split_whitespace()
+                .map(|value| value.parse::<i32>())
+                .collect::<Result<Vec<i32>, _>>()?;
+
+            parameters.set(name.trim(), &values)?;
+        }
+
+        Ok(parameters)
+    }
+}
+
+impl Display for Parameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in Self::names() {
+            let values = self.get(name).unwrap_or_default();
+            let values = values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            writeln!(f, "{} = {}", name, values)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use base::{
+    bitboard::{constants::FILES, Bitboard},
+    board::{color::Color, piece::Piece, Board},
+    square::Square,
+    zobrist::ZobristHash,
+};
+
+use crate::hashtable::{GenericTable, HashEntry};
+
+use super::parameters::Parameters;
+
+// The pawn structure rarely changes during the search, thus a small table
+// is already enough to get most of the evaluations from it.
+pub const PAWN_TABLE_SIZE: usize = 1;
+
+pub const DOUBLED_PENALTY: (i32, i32) = (10, 25);
+pub const ISOLATED_PENALTY: (i32, i32) = (10, 15);
+pub const BACKWARD_PENALTY: (i32, i32) = (8, 12);
+
+// The bonuses are indexed by the rank relative to the side of the pawn.
+pub const CONNECTED_MIDGAME: [i32; 8] = [0, 4, 6, 9, 15, 25, 40, 0];
+pub const CONNECTED_ENDGAME: [i32; 8] = [0, 2, 4, 8, 15, 25, 35, 0];
+pub const PASSED_MIDGAME: [i32; 8] = [0, 5, 10, 12, 20, 35, 60, 0];
+pub const PASSED_ENDGAME: [i32; 8] = [0, 10, 15, 25, 45, 75, 120, 0];
+
+// In the endgame a passed pawn is a lot stronger if the own king is close
+// to it and the opponent king is far away. This depends on the position of
+// the kings, so it is not part of the cached evaluation.
+pub const PASSED_KING_WEIGHT: [i32; 8] = [0, 0, 0, 1, 2, 3, 4, 0];
+pub const PASSED_OWN_KING: i32 = 2;
+pub const PASSED_OTHER_KING: i32 = 5;
+
+pub type PawnTable = GenericTable<PawnEntry>;
+
+/// The evaluation of the pawn structure from the view of white. As it only
+/// depends on the pawns, it is cached by the pawn hash of the board.
+#[derive(Debug, Clone)]
+pub struct PawnEntry {
+    key: ZobristHash,
+    midgame: i32,
+    endgame: i32,
+    passed: [Bitboard; Color::COUNT],
+}
+
+impl PawnEntry {
+    pub fn new(board: &Board, parameters: &Parameters) -> Self {
+        let mut midgame = 0;
+        let mut endgame = 0;
+        let mut passed = [Bitboard::default(); Color::COUNT];
+
+        for color in [Color::White, Color::Black] {
+            let (color_midgame, color_endgame, color_passed) =
+                evaluate_color(board, color, parameters);
+            let sign = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+
+            midgame += sign * color_midgame;
+            endgame += sign * color_endgame;
+            passed[color.index()] = color_passed;
+        }
+
+        Self {
+            key: board.pawn_hash(),
+            midgame,
+            endgame,
+            passed,
+        }
+    }
+
+    /// Looks the pawn structure of the board up in the table, or evaluates
+    /// and stores it if it is not found.
+    pub fn probe(table: &mut PawnTable, board: &Board, parameters: &Parameters) -> Self {
+        let key = board.pawn_hash();
+        if let Some(entry) = table.probe(key) {
+            return entry;
+        }
+
+        let entry = Self::new(board, parameters);
+        table.store(key, entry.clone());
+        entry
+    }
+
+    #[inline(always)]
+    pub const fn midgame(&self) -> i32 {
+        self.midgame
+    }
+
+    #[inline(always)]
+    pub const fn endgame(&self) -> i32 {
+        self.endgame
+    }
+
+    #[inline(always)]
+    pub fn passed(&self, color: Color) -> Bitboard {
+        self.passed[color.index()]
+    }
+}
+
+impl HashEntry<PawnEntry> for PawnEntry {
+    fn key(&self) -> ZobristHash {
+        self.key
+    }
+
+    fn replaceable(&self, _other: &PawnEntry) -> bool {
+        true
+    }
+}
+
+/// Evaluates the passed pawns of the color by the distance of both kings to
+/// the square in front of them. Only returns the endgame score.
+pub fn evaluate_passed_kings(
+    board: &Board,
+    pawns: &PawnEntry,
+    color: Color,
+    parameters: &Parameters,
+) -> i32 {
+    let own_king = board.get_king_square(color);
+    let other_king = board.get_king_square(color.other());
+
+    let mut eval = 0;
+    for square in pawns.passed(color) {
+        let rank = relative_rank(square, color);
+        let stop = match color {
+            Color::White => Square::new(square.rank() + 1, square.file()),
+            Color::Black => Square::new(square.rank() - 1, square.file()),
+        };
+
+        let weight = parameters.passed_king_weight[rank];
+        eval += parameters.passed_other_king * distance(other_king, stop) * weight;
+        eval -= parameters.passed_own_king * distance(own_king, stop) * weight;
+    }
+
+    eval
+}
+
+/// Returns the midgame and endgame score of the pawns of the color, as well
+/// as the passed pawns.
+/// Source: https://www.chessprogramming.org/Pawn_Structure
+pub(crate) fn evaluate_color(
+    board: &Board,
+    color: Color,
+    parameters: &Parameters,
+) -> (i32, i32, Bitboard) {
+    let own_pawns = board.get_piece_board(color, Piece::Pawn);
+    let other_pawns = board.get_piece_board(color.other(), Piece::Pawn);
+
+    let mut midgame = 0;
+    let mut endgame = 0;
+    let mut passed = Bitboard::default();
+
+    for square in own_pawns {
+        let rank = relative_rank(square, color);
+        let file = square.file_bb();
+        let adjacent = adjacent_files(square);
+        let front = forward_ranks(square, color);
+
+        let is_doubled = !(own_pawns & file & front).is_empty();
+        let is_isolated = (own_pawns & adjacent).is_empty();
+
+        // A pawn is supported if it is protected by another pawn, and part
+        // of a phalanx if another pawn stands right next to it.
+        let is_supported = !(own_pawns & square.get_pawn_attacks(color.other())).is_empty();
+        let is_phalanx = !(own_pawns & adjacent & square.rank_bb()).is_empty();
+
+        if is_doubled {
+            midgame -= parameters.doubled_penalty.0;
+            endgame -= parameters.doubled_penalty.1;
+        }
+
+        if is_isolated {
+            midgame -= parameters.isolated_penalty.0;
+            endgame -= parameters.isolated_penalty.1;
+        } else if is_backward(square, color, own_pawns, other_pawns) {
+            midgame -= parameters.backward_penalty.0;
+            endgame -= parameters.backward_penalty.1;
+        }
+
+        if is_supported || is_phalanx {
+            midgame += parameters.connected_midgame[rank];
+            endgame += parameters.connected_endgame[rank];
+        }
+
+        // The pawn behind a doubled pawn is never counted as passed, as it
+        // would be blocked by its own pawn.
+        let is_passed = (other_pawns & (file | adjacent) & front).is_empty();
+        if is_passed && !is_doubled {
+            midgame += parameters.passed_midgame[rank];
+            endgame += parameters.passed_endgame[rank];
+            passed |= square;
+        }
+    }
+
+    (midgame, endgame, passed)
+}
+
+/// A pawn is backward if all the pawns on the adjacent files are in front
+/// of it and the square in front of it is controlled by an opponent pawn.
+/// Thus it can neither be protected nor safely advanced.
+fn is_backward(square: Square, color: Color, own_pawns: Bitboard, other_pawns: Bitboard) -> bool {
+    let behind = !forward_ranks(square, color);
+    if !(own_pawns & adjacent_files(square) & behind).is_empty() {
+        return false;
+    }
+
+    let stop = match color {
+        Color::White => Square::new(square.rank() + 1, square.file()),
+        Color::Black => Square::new(square.rank() - 1, square.file()),
+    };
+
+    !(other_pawns & stop.get_pawn_attacks(color)).is_empty()
+}
+
+#[inline(always)]
+fn relative_rank(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.rank() as usize,
+        Color::Black => 7 - square.rank() as usize,
+    }
+}
+
+/// All the ranks in front of the square from the view of the color.
+#[inline(always)]
+fn forward_ranks(square: Square, color: Color) -> Bitboard {
+    let rank = square.rank() as u32;
+    let bits = match color {
+        Color::White => u64::MAX.checked_shl((rank + 1) * 8).unwrap_or(0),
+        Color::Black => (1u64 << (rank * 8)) - 1,
+    };
+
+    Bitboard::from_bits(bits)
+}
+
+#[inline(always)]
+fn adjacent_files(square: Square) -> Bitboard {
+    let file = square.file() as usize;
+
+    let mut adjacent = Bitboard::default();
+    if file > 0 {
+        adjacent |= FILES[file - 1];
+    }
+    if file < 7 {
+        adjacent |= FILES[file + 1];
+    }
+
+    adjacent
+}
+
+#[inline(always)]
+fn distance(first: Square, second: Square) -> i32 {
+    let ranks = (first.rank() as i32 - second.rank() as i32).abs();
+    let files = (first.file() as i32 - second.file() as i32).abs();
+    ranks.max(files)
+}
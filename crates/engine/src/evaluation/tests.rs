@@ -0,0 +1,593 @@
+#[cfg(test)]
+mod pawns {
+    use base::{
+        board::{color::Color, Board},
+        square::constants::*,
+        testing::board,
+    };
+
+    use crate::evaluation::{
+        evaluate, evaluate_cached,
+        parameters::Parameters,
+        pawns::{PawnEntry, PawnTable, DOUBLED_PENALTY, ISOLATED_PENALTY, PASSED_ENDGAME},
+    };
+
+    #[test]
+    fn startpos_is_balanced() {
+        let board = board(Board::STARTPOS_FEN);
+        let pawns = PawnEntry::new(&board, &Parameters::DEFAULT);
+
+        assert_eq!(pawns.midgame(), 0);
+        assert_eq!(pawns.endgame(), 0);
+        assert!(pawns.passed(Color::White).is_empty());
+        assert!(pawns.passed(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn doubled_and_isolated() {
+        // Both white pawns are isolated, the rear one is doubled as well and
+        // thus not passed.
+        let doubled = PawnEntry::new(
+            &board("4k3/8/8/8/8/3P4/3P4/4K3 w - - 0 1"),
+            &Parameters::DEFAULT,
+        );
+        let expected = -DOUBLED_PENALTY.1 - 2 * ISOLATED_PENALTY.1 + PASSED_ENDGAME[2];
+        assert_eq!(doubled.endgame(), expected);
+
+        let single = PawnEntry::new(
+            &board("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1"),
+            &Parameters::DEFAULT,
+        );
+        assert_eq!(single.endgame(), -ISOLATED_PENALTY.1 + PASSED_ENDGAME[1]);
+    }
+
+    #[test]
+    fn passed_pawns() {
+        let board = board("4k3/p7/8/3P4/8/8/1P5p/4K3 w - - 0 1");
+        let pawns = PawnEntry::new(&board, &Parameters::DEFAULT);
+
+        // The b2 pawn is blocked by the a7 pawn on the adjacent file.
+        assert_eq!(pawns.passed(Color::White).get_squares(), [D5]);
+        assert_eq!(pawns.passed(Color::Black).get_squares(), [H2]);
+    }
+
+    #[test]
+    fn passed_pawn_needs_king_support() {
+        // The same passed pawn is worth more if the opponent king is far away.
+        let supported = board("8/8/1k6/8/8/5K2/5P2/8 w - - 0 1");
+        let stopped = board("8/8/8/8/5k2/8/5P2/2K5 w - - 0 1");
+
+        assert!(evaluate(&supported, Color::White) > evaluate(&stopped, Color::White));
+    }
+
+    #[test]
+    fn symmetric_evaluation() {
+        let white = board("4k3/pp3p2/8/3P4/8/2P5/P4P2/4K3 w - - 0 1");
+        let black = board("4k3/p4p2/2p5/8/3p4/8/PP3P2/4K3 b - - 0 1");
+
+        assert_eq!(
+            evaluate(&white, Color::White),
+            evaluate(&black, Color::Black)
+        );
+    }
+
+    #[test]
+    fn cached_matches_uncached() {
+        let mut table = PawnTable::size(1);
+        let fens = [
+            Board::STARTPOS_FEN,
+            "4k3/pp3p2/8/3P4/8/2P5/P4P2/4K3 w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ];
+
+        for _ in 0..2 {
+            for fen in fens {
+                let board = board(fen);
+                for color in [Color::White, Color::Black] {
+                    let cached = evaluate_cached(&board, color, &Parameters::DEFAULT, &mut table);
+                    assert_eq!(cached, evaluate(&board, color));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod mobility {
+    use base::{board::color::Color, testing::board};
+
+    use crate::evaluation::{
+        evaluate,
+        mobility::{evaluate_mobility, KNIGHT_MOBILITY},
+        parameters::Parameters,
+    };
+
+    #[test]
+    fn centralized_knight() {
+        let center = board("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+        let corner = board("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+
+        assert_eq!(
+            evaluate_mobility(&center, Color::White, &Parameters::DEFAULT),
+            KNIGHT_MOBILITY[8]
+        );
+        assert_eq!(
+            evaluate_mobility(&corner, Color::White, &Parameters::DEFAULT),
+            KNIGHT_MOBILITY[2]
+        );
+    }
+
+    #[test]
+    fn excludes_pawn_attacks_and_own_pieces() {
+        // The pawns on a6 and g6 take b5 and f5 away, the own pawn on e2
+        // blocks another square.
+        let board = board("4k3/8/p5p1/8/3N4/8/4P3/4K3 w - - 0 1");
+
+        assert_eq!(
+            evaluate_mobility(&board, Color::White, &Parameters::DEFAULT),
+            KNIGHT_MOBILITY[5]
+        );
+    }
+
+    #[test]
+    fn symmetric_evaluation() {
+        let white = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let black = board("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1");
+
+        assert_eq!(
+            evaluate_mobility(&white, Color::White, &Parameters::DEFAULT),
+            evaluate_mobility(&black, Color::Black, &Parameters::DEFAULT)
+        );
+        assert_eq!(
+            evaluate(&white, Color::White),
+            evaluate(&black, Color::Black)
+        );
+    }
+}
+
+#[cfg(test)]
+mod king_safety {
+    use base::{board::color::Color, testing::board};
+
+    use crate::evaluation::{
+        king_safety::{
+            evaluate_king_safety, OPEN_FILE_PENALTY, SAFETY_TABLE, SEMI_OPEN_FILE_PENALTY,
+            SHIELD_CLOSE, SHIELD_FAR,
+        },
+        parameters::Parameters,
+    };
+
+    #[test]
+    fn pawn_shield() {
+        let close = board("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+        let far = board("4k3/8/8/8/8/5PPP/8/6K1 w - - 0 1");
+        let open = board("4k3/8/8/8/8/8/PPP5/6K1 w - - 0 1");
+
+        assert_eq!(
+            evaluate_king_safety(&close, Color::White, &Parameters::DEFAULT),
+            3 * SHIELD_CLOSE
+        );
+        assert_eq!(
+            evaluate_king_safety(&far, Color::White, &Parameters::DEFAULT),
+            3 * SHIELD_FAR
+        );
+        assert_eq!(
+            evaluate_king_safety(&open, Color::White, &Parameters::DEFAULT),
+            -3 * (SEMI_OPEN_FILE_PENALTY + OPEN_FILE_PENALTY)
+        );
+    }
+
+    #[test]
+    fn semi_open_file() {
+        // The g-file only has a pawn of the opponent.
+        let board = board("4k3/6p1/8/8/8/8/5P1P/6K1 w - - 0 1");
+        let expected = 2 * SHIELD_CLOSE - SEMI_OPEN_FILE_PENALTY;
+
+        assert_eq!(
+            evaluate_king_safety(&board, Color::White, &Parameters::DEFAULT),
+            expected
+        );
+    }
+
+    #[test]
+    fn needs_multiple_attackers() {
+        // Both the queen and the knight attack f7 and h7.
+        let queen = board("6k1/5ppp/8/7Q/8/8/8/6K1 b - - 0 1");
+        let both = board("6k1/5ppp/8/6NQ/8/8/8/6K1 b - - 0 1");
+
+        assert_eq!(
+            evaluate_king_safety(&queen, Color::Black, &Parameters::DEFAULT),
+            3 * SHIELD_CLOSE
+        );
+        assert_eq!(
+            evaluate_king_safety(&both, Color::Black, &Parameters::DEFAULT),
+            3 * SHIELD_CLOSE - SAFETY_TABLE[2 * 5 + 2 * 2]
+        );
+    }
+}
+
+#[cfg(test)]
+mod trace {
+    use base::{
+        board::{color::Color, Board},
+        testing::board,
+    };
+
+    use crate::evaluation::{
+        endgame::{KNOWN_WIN, SCALE_NORMAL, SCALE_OPPOSITE_BISHOPS, SCALE_OPPOSITE_BISHOPS_PIECES},
+        evaluate,
+        trace::{EvaluationTrace, Term},
+    };
+
+    #[test]
+    fn matches_evaluation() {
+        let fens = [
+            Board::STARTPOS_FEN,
+            "4k3/pp3p2/8/3P4/8/2P5/P4P2/4K3 w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        ];
+
+        for fen in fens {
+            let board = board(fen);
+            let trace = EvaluationTrace::new(&board);
+            assert_eq!(trace.eval(), evaluate(&board, Color::White));
+
+            let (midgame, endgame) = Term::ALL.iter().fold((0, 0), |(mg, eg), &term| {
+                let (term_mg, term_eg) = trace.total(term);
+                (mg + term_mg, eg + term_eg)
+            });
+            let endgame = endgame * trace.scale_factor() / SCALE_NORMAL;
+            let phased = (midgame * trace.phase() + endgame * (24 - trace.phase())) / 24;
+            assert_eq!(phased, trace.eval());
+        }
+    }
+
+    #[test]
+    fn mirrored_terms() {
+        let white = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let black = board("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1");
+
+        let white = EvaluationTrace::new(&white);
+        let black = EvaluationTrace::new(&black);
+        for term in Term::ALL {
+            assert_eq!(white.get(term, Color::White), black.get(term, Color::Black));
+            assert_eq!(white.get(term, Color::Black), black.get(term, Color::White));
+        }
+
+        assert_eq!(white.eval(), -black.eval());
+    }
+
+    #[test]
+    fn material() {
+        let board = board("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let trace = EvaluationTrace::new(&board);
+
+        let (midgame, endgame) = trace.total(Term::Material);
+        assert!(midgame > 0 && endgame > 0);
+        assert_eq!(trace.total(Term::BishopPair), (0, 0));
+    }
+
+    #[test]
+    fn scale_factor() {
+        let trace = EvaluationTrace::new(&board(Board::STARTPOS_FEN));
+        assert_eq!(trace.scale_factor(), SCALE_NORMAL);
+
+        let trace = EvaluationTrace::new(&board("4k3/5pp1/4b3/8/8/4B3/5PPP/4K3 w - - 0 1"));
+        assert_eq!(trace.scale_factor(), SCALE_OPPOSITE_BISHOPS);
+        assert!(trace.to_string().contains(&format!(
+            "Scale: {}/{}",
+            SCALE_OPPOSITE_BISHOPS, SCALE_NORMAL
+        )));
+    }
+
+    #[test]
+    fn middlegame_not_scaled() {
+        // Opposite colored bishops with queens and rooks on the board only
+        // scale the endgame score, which barely counts this early.
+        let board = board("r2qk2r/pppb1ppp/8/3p4/8/4B3/PPP2PPP/R2QK2R w KQkq - 0 1");
+        let trace = EvaluationTrace::new(&board);
+        assert_eq!(trace.scale_factor(), SCALE_OPPOSITE_BISHOPS_PIECES);
+
+        let (midgame, endgame) = Term::ALL.iter().fold((0, 0), |(mg, eg), &term| {
+            let (term_mg, term_eg) = trace.total(term);
+            (mg + term_mg, eg + term_eg)
+        });
+        let unscaled = (midgame * trace.phase() + endgame * (24 - trace.phase())) / 24;
+        let scaled = unscaled * SCALE_OPPOSITE_BISHOPS_PIECES / SCALE_NORMAL;
+
+        assert!(trace.phase() > 12);
+        assert!((trace.eval() - unscaled).abs() < (trace.eval() - scaled).abs());
+    }
+
+    #[test]
+    fn endgame_side_to_move() {
+        // White only wins the pawn ending if black has to give way.
+        let board = board("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1");
+        let trace = EvaluationTrace::new(&board);
+
+        assert_eq!(trace.eval(), -evaluate(&board, Color::Black));
+        assert!(trace.eval() > KNOWN_WIN);
+    }
+}
+
+#[cfg(test)]
+mod parameters {
+    use base::{board::color::Color, testing::board};
+
+    use crate::evaluation::{
+        error::ParameterError,
+        evaluate, evaluate_with,
+        parameters::{Parameters, Psqt},
+        trace::{EvaluationTrace, Term},
+    };
+
+    #[test]
+    fn default_matches_constants() {
+        let board = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        for color in [Color::White, Color::Black] {
+            assert_eq!(
+                evaluate_with(&board, color, &Parameters::default()),
+                evaluate(&board, color)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut parameters = Parameters {
+            bishop_pair: 30,
+            ..Parameters::default()
+        };
+        parameters.knight_mobility[4] = (7, -3);
+        parameters.set("midgame_knight_table", &[5; 64]).unwrap();
+
+        let parsed = parameters.to_string().parse::<Parameters>().unwrap();
+        assert_eq!(parsed, parameters);
+
+        // Saving the defaults doesn't switch to the slow piece square tables.
+        let parsed = Parameters::default()
+            .to_string()
+            .parse::<Parameters>()
+            .unwrap();
+        assert_eq!(parsed.psqt, None);
+    }
+
+    #[test]
+    fn partial_file() {
+        let parameters = "# Only the bishop pair\nbishop_pair = 20\n\n"
+            .parse::<Parameters>()
+            .unwrap();
+
+        let expected = Parameters {
+            bishop_pair: 20,
+            ..Parameters::default()
+        };
+        assert_eq!(parameters, expected);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let unknown = "king_tropism = 5".parse::<Parameters>();
+        assert!(matches!(unknown, Err(ParameterError::UnknownParameter(_))));
+
+        let count = "doubled_penalty = 5".parse::<Parameters>();
+        assert!(matches!(count, Err(ParameterError::WrongValueCount(_))));
+
+        let separator = "bishop_pair 5".parse::<Parameters>();
+        assert!(matches!(
+            separator,
+            Err(ParameterError::MissingSeparator(_))
+        ));
+
+        let value = "bishop_pair = five".parse::<Parameters>();
+        assert!(matches!(value, Err(ParameterError::ParseInt(_))));
+    }
+
+    #[test]
+    fn changes_evaluation() {
+        let board = board("4k3/p7/8/8/8/8/P7/2B1KB2 w - - 0 1");
+
+        let parameters = Parameters {
+            bishop_pair: Parameters::DEFAULT.bishop_pair + 25,
+            ..Parameters::default()
+        };
+        assert_eq!(
+            evaluate_with(&board, Color::White, &parameters),
+            evaluate(&board, Color::White) + 25
+        );
+    }
+
+    #[test]
+    fn custom_psqt() {
+        let board = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        // The default tables summed up during the evaluation have to match
+        // the scores that are kept up to date by the board.
+        let mut parameters = Parameters {
+            psqt: Some(Psqt::default()),
+            ..Parameters::default()
+        };
+        for color in [Color::White, Color::Black] {
+            assert_eq!(
+                evaluate_with(&board, color, &parameters),
+                evaluate(&board, color)
+            );
+        }
+
+        parameters.psqt = Some(Psqt {
+            midgame_piece_values: [0; 7],
+            endgame_piece_values: [0; 7],
+            ..Psqt::default()
+        });
+
+        let trace = EvaluationTrace::with_parameters(&board, &parameters);
+        assert_eq!(trace.total(Term::Material), (0, 0));
+    }
+}
+
+#[cfg(test)]
+mod nnue {
+    use std::sync::Arc;
+
+    use base::{
+        board::{color::Color, Board},
+        nnue::network::{Network, QA, QB, SCALE},
+        testing::board,
+    };
+
+    use crate::evaluation::{evaluate, evaluate_cached, parameters::Parameters, pawns::PawnTable};
+
+    #[test]
+    fn replaces_handcrafted() {
+        let mut board = board(Board::STARTPOS_FEN);
+
+        // A network without any weights only returns its output bias.
+        let mut data = vec![0; Network::SIZE];
+        let bias = (QA * QB) as i16;
+        data[Network::SIZE - 2..].copy_from_slice(&bias.to_le_bytes());
+        board.set_network(Some(Arc::new(Network::parse(&data).unwrap())));
+
+        let mut pawn_table = PawnTable::size(1);
+        for color in [Color::White, Color::Black] {
+            assert_eq!(evaluate(&board, color), SCALE);
+            assert_eq!(
+                evaluate_cached(&board, color, &Parameters::DEFAULT, &mut pawn_table),
+                SCALE
+            );
+        }
+
+        board.set_network(None);
+        assert_eq!(evaluate(&board, Color::White), 0);
+    }
+}
+
+#[cfg(test)]
+mod endgame {
+    use base::{board::color::Color, testing::board};
+
+    use crate::evaluation::{
+        endgame::{
+            evaluate_endgame, probe_kpk, scale_factor, Endgame, MaterialSignature, KNOWN_WIN,
+            SCALE_NORMAL, SCALE_OPPOSITE_BISHOPS, SCALE_PAWNLESS,
+        },
+        evaluate,
+    };
+
+    fn probe(fen: &str) -> Option<Endgame> {
+        let board = board(fen);
+        Endgame::probe(&board, &MaterialSignature::new(&board))
+    }
+
+    #[test]
+    fn signature() {
+        let position = board("8/8/8/3k4/8/8/2N5/1B2K3 w - - 0 1");
+        assert_eq!(MaterialSignature::new(&position).to_string(), "KBNvK");
+
+        let position = board("4k3/pp3p2/8/3P4/8/2P5/P4P2/4K3 w - - 0 1");
+        assert_eq!(MaterialSignature::new(&position).to_string(), "KPPPPvKPPP");
+    }
+
+    #[test]
+    fn known_wins() {
+        assert_eq!(
+            probe("8/8/8/3k4/8/8/8/3QK3 w - - 0 1"),
+            Some(Endgame::Mate(Color::White))
+        );
+        assert_eq!(
+            probe("3rk3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Some(Endgame::Mate(Color::Black))
+        );
+        assert_eq!(
+            probe("8/8/8/3k4/8/8/2N5/1B2K3 w - - 0 1"),
+            Some(Endgame::BishopKnightMate(Color::White))
+        );
+
+        let eval = evaluate(&board("8/8/8/3k4/8/8/8/3RK3 b - - 0 1"), Color::Black);
+        assert!(eval < -KNOWN_WIN);
+    }
+
+    #[test]
+    fn mating_net() {
+        // The losing king is worse off on the edge than in the center.
+        let center = evaluate_endgame(&board("8/8/8/3k4/8/8/8/R3K3 w - - 0 1"), Color::White);
+        let edge = evaluate_endgame(&board("3k4/8/8/8/8/8/8/R3K3 w - - 0 1"), Color::White);
+        assert!(edge.unwrap() > center.unwrap());
+
+        // The light-squared bishop can only mate on h1 and a8.
+        let wrong = evaluate_endgame(&board("7k/8/8/8/8/8/8/1BN1K3 w - - 0 1"), Color::White);
+        let right = evaluate_endgame(&board("k7/8/8/8/8/8/8/1BN1K3 w - - 0 1"), Color::White);
+        assert!(right.unwrap() > wrong.unwrap());
+    }
+
+    #[test]
+    fn known_draws() {
+        let fens = [
+            "8/8/8/3k4/8/8/8/2B1K3 w - - 0 1",
+            "8/8/8/3k4/8/8/8/1NN1K3 w - - 0 1",
+            "8/8/3n4/3k4/8/8/8/2B1K3 b - - 0 1",
+            "7k/8/7P/8/8/8/8/3BK3 w - - 0 1",
+        ];
+
+        for fen in fens {
+            assert_eq!(probe(fen), Some(Endgame::Draw));
+            assert_eq!(evaluate(&board(fen), Color::White), 0);
+        }
+
+        // The bishop controls the promotion square, or the king is too far.
+        assert_eq!(probe("7k/8/7P/8/8/8/8/2B1K3 w - - 0 1"), None);
+        assert_eq!(probe("8/8/7P/8/8/2k5/8/2B1K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn scale_factors() {
+        let position = board("8/5k2/5p2/4bP2/3p4/3PB3/4K3/8 w - - 0 1");
+        let signature = MaterialSignature::new(&position);
+        assert_eq!(
+            scale_factor(&position, &signature, Color::White),
+            SCALE_NORMAL
+        );
+
+        let position = board("8/4bk2/5p2/5P2/3pB3/3P4/4K3/8 w - - 0 1");
+        let signature = MaterialSignature::new(&position);
+        assert_eq!(
+            scale_factor(&position, &signature, Color::White),
+            SCALE_OPPOSITE_BISHOPS
+        );
+
+        let position = board("8/5k2/5b2/8/8/8/4K3/4R3 w - - 0 1");
+        let signature = MaterialSignature::new(&position);
+        assert_eq!(
+            scale_factor(&position, &signature, Color::White),
+            SCALE_PAWNLESS
+        );
+    }
+
+    #[test]
+    fn pawn_vs_king() {
+        // The side to move loses the opposition, which decides the game.
+        let white = board("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1");
+        let black = board("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1");
+        assert_eq!(
+            Endgame::probe(&white, &MaterialSignature::new(&white)),
+            Some(Endgame::PawnVsKing(Color::White))
+        );
+        assert!(!probe_kpk(&white, Color::White, Color::White));
+        assert!(probe_kpk(&black, Color::White, Color::Black));
+        assert_eq!(evaluate(&white, Color::White), 0);
+        assert!(evaluate(&black, Color::Black) < -KNOWN_WIN);
+
+        // The same positions with the colors flipped.
+        let white = board("8/8/8/4p3/4k3/8/4K3/8 w - - 0 1");
+        let black = board("8/8/8/4p3/4k3/8/4K3/8 b - - 0 1");
+        assert!(probe_kpk(&white, Color::Black, Color::White));
+        assert!(!probe_kpk(&black, Color::Black, Color::Black));
+
+        // The defending king reaches the corner in front of the rook pawn.
+        let rook_pawn = board("8/8/8/8/8/1k6/P7/K7 b - - 0 1");
+        assert!(!probe_kpk(&rook_pawn, Color::White, Color::Black));
+
+        // The pawn can't be caught by the king.
+        let runaway = board("8/k7/8/8/7P/8/8/K7 w - - 0 1");
+        assert!(probe_kpk(&runaway, Color::White, Color::White));
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::{self, Display};
+
+use base::board::{color::Color, Board};
+
+use super::{
+    endgame::{evaluate_endgame, SCALE_NORMAL},
+    evaluate_traced,
+    parameters::Parameters,
+    pawns::PawnEntry,
+    taper_phase,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    Psqt,
+    PawnStructure,
+    Mobility,
+    KingSafety,
+    BishopPair,
+}
+
+impl Term {
+    pub const COUNT: usize = 6;
+
+    pub const ALL: [Term; Term::COUNT] = [
+        Term::Material,
+        Term::Psqt,
+        Term::PawnStructure,
+        Term::Mobility,
+        Term::KingSafety,
+        Term::BishopPair,
+    ];
+
+    #[inline(always)]
+    pub const fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::Psqt => "PSQT",
+            Term::PawnStructure => "Pawns",
+            Term::Mobility => "Mobility",
+            Term::KingSafety => "King safety",
+            Term::BishopPair => "Bishop pair",
+        }
+    }
+}
+
+/// Receives the midgame and endgame score of every term of the evaluation
+/// for each color. The evaluation only computes the scores of a color if
+/// the tracer is enabled, so `NoTrace` has no cost at all.
+pub trait EvaluationTracer {
+    const ENABLED: bool;
+
+    fn add(&mut self, term: Term, color: Color, midgame: i32, endgame: i32);
+
+    /// Receives the scale factor out of `SCALE_NORMAL`, which is applied to
+    /// the endgame score of the sum of the terms.
+    fn scale(&mut self, scale: i32);
+}
+
+pub struct NoTrace;
+
+impl EvaluationTracer for NoTrace {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn add(&mut self, _term: Term, _color: Color, _midgame: i32, _endgame: i32) {}
+
+    #[inline(always)]
+    fn scale(&mut self, _scale: i32) {}
+}
+
+/// The breakdown of the evaluation into its terms. The terms are from the
+/// view of the color, the evaluation from the view of white.
+#[derive(Debug, Clone)]
+pub struct EvaluationTrace {
+    scores: [[(i32, i32); Color::COUNT]; Term::COUNT],
+    phase: i32,
+    scale: i32,
+    eval: i32,
+}
+
+impl EvaluationTrace {
+    pub fn new(board: &Board) -> Self {
+        Self::with_parameters(board, &Parameters::DEFAULT)
+    }
+
+    pub fn with_parameters(board: &Board, parameters: &Parameters) -> Self {
+        let mut trace = Self {
+            scores: [[(0, 0); Color::COUNT]; Term::COUNT],
+            phase: board.gamephase().min(24),
+            scale: SCALE_NORMAL,
+            eval: 0,
+        };
+
+        let pawns = PawnEntry::new(board, parameters);
+        trace.eval = evaluate_traced(board, Color::White, parameters, &pawns, &mut trace);
+
+        // The terms are still traced for known endgames, even though their
+        // evaluation replaces the one of the terms. Some of them depend on
+        // the side to move, so they are evaluated from its view.
+        if let Some(eval) = evaluate_endgame(board, board.active()) {
+            trace.eval = match board.active() {
+                Color::White => eval,
+                Color::Black => -eval,
+            };
+        }
+        trace
+    }
+
+    #[inline(always)]
+    pub fn get(&self, term: Term, color: Color) -> (i32, i32) {
+        self.scores[term.index()][color.index()]
+    }
+
+    /// The midgame and endgame score of the term from the view of white.
+    pub fn total(&self, term: Term) -> (i32, i32) {
+        let (white_midgame, white_endgame) = self.get(term, Color::White);
+        let (black_midgame, black_endgame) = self.get(term, Color::Black);
+
+        (white_midgame - black_midgame, white_endgame - black_endgame)
+    }
+
+    /// The phase of the game, from 24 at the start to 0 without pieces.
+    #[inline(always)]
+    pub const fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// The scale factor of the endgame score out of `SCALE_NORMAL`.
+    #[inline(always)]
+    pub const fn scale_factor(&self) -> i32 {
+        self.scale
+    }
+
+    /// The evaluation from the view of white, which is the same as the one
+    /// of `evaluate`.
+    #[inline(always)]
+    pub const fn eval(&self) -> i32 {
+        self.eval
+    }
+}
+
+impl EvaluationTracer for EvaluationTrace {
+    const ENABLED: bool = true;
+
+    fn add(&mut self, term: Term, color: Color, midgame: i32, endgame: i32) {
+        let score = &mut self.scores[term.index()][color.index()];
+        score.0 += midgame;
+        score.1 += endgame;
+    }
+
+    fn scale(&mut self, scale: i32) {
+        self.scale = scale;
+    }
+}
+
+impl Display for EvaluationTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = "-------------+-------------+-------------+-------------+--------";
+
+        writeln!(
+            f,
+            "        Term |    White    |    Black    |    Total    | Phased"
+        )?;
+        writeln!(
+            f,
+            "             |   MG    EG  |   MG    EG  |   MG    EG  |"
+        )?;
+        writeln!(f, "{}", separator)?;
+
+        for term in Term::ALL {
+            let (white_midgame, white_endgame) = self.get(term, Color::White);
+            let (black_midgame, black_endgame) = self.get(term, Color::Black);
+            let (midgame, endgame) = self.total(term);
+            let phased = taper_phase(self.phase, midgame, endgame);
+
+            writeln!(
+                f,
+                "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5} | {:>6}",
+                term.name(),
+                white_midgame,
+                white_endgame,
+                black_midgame,
+                black_endgame,
+                midgame,
+                endgame,
+                phased
+            )?;
+        }
+
+        writeln!(f, "{}", separator)?;
+        writeln!(f, "Phase: {}/24", self.phase)?;
+        writeln!(f, "Scale: {}/{}", self.scale, SCALE_NORMAL)?;
+        write!(f, "Evaluation: {} (white side)", self.eval)
+    }
+}
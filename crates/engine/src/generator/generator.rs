@@ -32,6 +32,7 @@ impl CheckType for NotInCheck {
 pub trait MoveType {
     const QUIET: bool;
     const CAPTURE: bool;
+    const CHECKS: bool;
 }
 
 pub struct QuietMoves;
@@ -39,6 +40,7 @@ pub struct QuietMoves;
 impl MoveType for QuietMoves {
     const QUIET: bool = true;
     const CAPTURE: bool = false;
+    const CHECKS: bool = false;
 }
 
 pub struct CaptureMoves;
@@ -46,6 +48,7 @@ pub struct CaptureMoves;
 impl MoveType for CaptureMoves {
     const QUIET: bool = false;
     const CAPTURE: bool = true;
+    const CHECKS: bool = false;
 }
 
 pub struct AllMoves;
@@ -53,6 +56,16 @@ pub struct AllMoves;
 impl MoveType for AllMoves {
     const QUIET: bool = true;
     const CAPTURE: bool = true;
+    const CHECKS: bool = false;
+}
+
+/// Only the quiet moves that are checking the opponent king.
+pub struct QuietChecks;
+
+impl MoveType for QuietChecks {
+    const QUIET: bool = true;
+    const CAPTURE: bool = false;
+    const CHECKS: bool = true;
 }
 
 pub(crate) trait PieceGenerator {
@@ -104,6 +117,10 @@ impl<M: MoveType> MoveGenerator<M> {
             KingGenerator::legals::<InCheck, M>(&mut generator, board);
         }
 
+        if M::CHECKS {
+            generator.retain_checks(board);
+        }
+
         generator
     }
 
@@ -112,6 +129,22 @@ impl<M: MoveType> MoveGenerator<M> {
         self.size += 1;
     }
 
+    /// Removes every move that is not checking the opponent king.
+    fn retain_checks(&mut self, board: &Board) {
+        let mut size = 0;
+        for index in 0..self.size {
+            let mov = self.moves[index];
+            if !board.gives_check(mov) {
+                continue;
+            }
+
+            self.moves[size] = mov;
+            size += 1;
+        }
+
+        self.size = size;
+    }
+
     // TODO: Do this in a better way.
     #[inline(always)]
     pub fn is_checkmate(&self, board: &Board) -> bool {
@@ -136,11 +169,11 @@ impl<M: MoveType> Iterator for MoveGenerator<M> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.index >= self.len() {
-            return None;
+            None
         } else {
             let mov = self.moves[self.index];
             self.index += 1;
-            return Some(mov);
+            Some(mov)
         }
     }
 }
@@ -4,3 +4,5 @@ pub mod generator;
 pub use generator::*;
 
 pub(crate) mod pieces;
+
+mod tests;
@@ -1,6 +1,6 @@
 use base::{
     bitboard::Bitboard,
-    board::{board::EnPassant, color::Color, piece::Piece, Board},
+    board::{color::Color, piece::Piece, Board},
     r#move::Move,
     square::Square,
 };
@@ -51,17 +51,7 @@ impl PieceGenerator for PawnGenerator {
 
         // All the moves that are allowed based on the check condition.
         let check_mask = if T::IN_CHECK {
-            let king_square = board.get_king_square(board.active());
-
-            // There can only be one checker, otherwise we would only calculate
-            // king moves.
-            let checker = Square::from(board.checkers());
-
-            // Get all the bits between the checker and the king, the checker is
-            // inclusive and the king is exclusive.
-            let check_mask = checker.get_between(king_square) ^ checker;
-
-            check_mask
+            board.check_mask()
         } else {
             // We want to allow all moves if we are not in check.
             Bitboard::ALL_BITS
@@ -69,7 +59,7 @@ impl PieceGenerator for PawnGenerator {
 
         // All moves are valid where no own piece is on the destination and
         // the checkmask is set.
-        let allowed = if M::QUIET && M::QUIET {
+        let allowed = if M::QUIET && M::CAPTURE {
             let own_occupied = board.get_occupied(board.active());
             !own_occupied & check_mask
         } else if M::QUIET {
@@ -129,7 +119,7 @@ impl PieceGenerator for PawnGenerator {
 
                 let allowed = pawns & rank & adjacent_files;
                 for source in allowed {
-                    if !Self::is_legal_en_passant(board, source, en_passant) {
+                    if !board.is_legal_en_passant(source) {
                         continue;
                     }
 
@@ -142,30 +132,6 @@ impl PieceGenerator for PawnGenerator {
 }
 
 impl PawnGenerator {
-    fn is_legal_en_passant(board: &Board, source: Square, en_passant: &EnPassant) -> bool {
-        let king_square = board.get_king_square(board.active());
-        let all_occupied = board.get_all_occupied();
-
-        let mut blockers = all_occupied;
-        blockers ^= source;
-        blockers ^= en_passant.to_capture;
-        blockers ^= en_passant.to_move;
-
-        let mut attackers = Bitboard::default();
-
-        let queens = board.get_piece_board(board.other(), Piece::Queen);
-
-        let bishops = board.get_piece_board(board.other(), Piece::Bishop);
-        let bishop_attacks = king_square.get_bishop_attacks(blockers);
-        attackers ^= bishop_attacks & (bishops | queens);
-
-        let rooks = board.get_piece_board(board.other(), Piece::Rook);
-        let rook_attacks = king_square.get_rook_attacks(blockers);
-        attackers ^= rook_attacks & (rooks | queens);
-
-        attackers.is_empty()
-    }
-
     fn is_double_pawn(from: Square, to: Square) -> bool {
         let source_rank = from.rank();
 
@@ -38,17 +38,7 @@ impl PieceGenerator for RookGenerator {
 
         // All the moves that are allowed based on the check condition.
         let check_mask = if C::IN_CHECK {
-            let king_square = board.get_king_square(board.active());
-
-            // There can only be one checker, otherwise we would only calculate
-            // king moves.
-            let checker = Square::from(board.checkers());
-
-            // Get all the bits between the checker and the king, the checker is
-            // inclusive and the king is exclusive.
-            let check_mask = checker.get_between(king_square) ^ checker;
-
-            check_mask
+            board.check_mask()
         } else {
             // We want to allow all moves if we are not in check.
             Bitboard::ALL_BITS
@@ -56,7 +46,7 @@ impl PieceGenerator for RookGenerator {
 
         // All moves are valid where no own piece is on the destination and
         // the checkmask is set.
-        let allowed = if M::QUIET && M::QUIET {
+        let allowed = if M::QUIET && M::CAPTURE {
             let own_occupied = board.get_occupied(board.active());
             !own_occupied & check_mask
         } else if M::QUIET {
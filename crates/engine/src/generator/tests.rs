@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod move_types {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::generator::{AllMoves, CaptureMoves, MoveGenerator, QuietChecks, QuietMoves};
+
+    fn sorted_moves(moves: impl Iterator<Item = Move>) -> Vec<String> {
+        let mut moves = moves.map(|mov| mov.to_string()).collect::<Vec<String>>();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn quiet_and_captures_are_all_moves() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let board = board(fen);
+
+        let quiets = MoveGenerator::<QuietMoves>::new(&board).collect::<Vec<Move>>();
+        assert!(quiets.iter().all(|mov| !mov.is_capture()));
+
+        let captures = MoveGenerator::<CaptureMoves>::new(&board).collect::<Vec<Move>>();
+        assert!(captures.iter().all(|mov| mov.is_capture()));
+
+        let mut combined = quiets;
+        combined.extend(captures);
+
+        let all_moves = MoveGenerator::<AllMoves>::new(&board);
+        assert_eq!(sorted_moves(combined.into_iter()), sorted_moves(all_moves));
+    }
+
+    #[test]
+    fn quiet_checks_discovered() {
+        let fen = "4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1";
+        let board = board(fen);
+
+        let checks = MoveGenerator::<QuietChecks>::new(&board);
+        let expected = [
+            "e2a6", "e2b5", "e2c4", "e2d1", "e2d3", "e2f1", "e2f3", "e2g4", "e2h5",
+        ];
+        assert_eq!(sorted_moves(checks), expected);
+    }
+
+    #[test]
+    fn quiet_checks_direct() {
+        let fen = "4k3/8/3P4/8/4N3/8/8/Q5K1 w - - 0 1";
+        let board = board(fen);
+
+        let checks = MoveGenerator::<QuietChecks>::new(&board);
+        let expected = ["a1a4", "a1a8", "a1e5", "a1h8", "d6d7", "e4f6"];
+        assert_eq!(sorted_moves(checks), expected);
+    }
+
+    fn assert_gives_check(board: &mut Board, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+
+        let moves = MoveGenerator::<AllMoves>::new(board).collect::<Vec<Move>>();
+        for mov in moves {
+            let gives_check = board.gives_check(mov);
+
+            board.make(mov);
+            assert_eq!(gives_check, board.is_check(), "{} in {}", mov, board);
+            assert_gives_check(board, depth - 1);
+            board.unmake(mov);
+        }
+    }
+
+    #[test]
+    fn gives_check_matches_make() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+
+        for fen in fens {
+            let mut board = board(fen);
+            assert_gives_check(&mut board, 3);
+        }
+    }
+
+    fn assert_has_legal_moves(board: &mut Board, depth: u8) {
+        let moves = MoveGenerator::<AllMoves>::new(board).collect::<Vec<Move>>();
+        assert_eq!(board.has_legal_moves(), !moves.is_empty(), "{}", board);
+
+        if depth == 0 {
+            return;
+        }
+
+        for mov in moves {
+            board.make(mov);
+            assert_has_legal_moves(board, depth - 1);
+            board.unmake(mov);
+        }
+    }
+
+    #[test]
+    fn has_legal_moves_matches_generator() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/8/1r5r/KPp4r/7r/8/8/7k w - c6 0 1",
+            "k7/8/1Q6/8/8/8/8/6K1 b - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut board = board(fen);
+            assert_has_legal_moves(&mut board, 3);
+        }
+    }
+}
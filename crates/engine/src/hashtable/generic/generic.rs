@@ -8,6 +8,7 @@ pub trait HashEntry<T> {
     fn replaceable(&self, other: &T) -> bool;
 }
 
+#[derive(Debug, Clone)]
 pub struct GenericTable<T: Clone + HashEntry<T>> {
     size: usize,
     entries: Vec<Option<T>>,
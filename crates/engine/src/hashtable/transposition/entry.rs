@@ -21,7 +21,9 @@ impl TranspositionFlag {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TranspositionEntry {
-    pub(crate) depth: u8,
+    /// The depth of the search, which is negative for the plies of the
+    /// quiescence search.
+    pub(crate) depth: i8,
     flag: TranspositionFlag,
     eval: i32,
     best_move: Move,
@@ -31,7 +33,7 @@ pub struct TranspositionEntry {
 pub const _: () = assert!(std::mem::size_of::<TranspositionEntry>() <= 8);
 
 impl TranspositionEntry {
-    pub fn new(depth: u8, flag: TranspositionFlag, eval: i32, best_move: Option<Move>) -> Self {
+    pub fn new(depth: i8, flag: TranspositionFlag, eval: i32, best_move: Option<Move>) -> Self {
         let best_move = if best_move.is_some() {
             best_move.unwrap()
         } else {
@@ -47,7 +49,7 @@ impl TranspositionEntry {
     }
 
     #[inline(always)]
-    pub const fn depth(&self) -> u8 {
+    pub const fn depth(&self) -> i8 {
         self.depth
     }
 
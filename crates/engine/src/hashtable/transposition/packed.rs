@@ -43,7 +43,7 @@ impl PackedEntry {
             None => NULL_MOVE.bits(),
         };
 
-        data |= ((entry.depth() as u64) & DEPTH_MASK) << DEPTH_SHIFT;
+        data |= ((entry.depth() as u8 as u64) & DEPTH_MASK) << DEPTH_SHIFT;
         data |= ((entry.flag() as u64) & FLAG_MASK) << FLAG_SHIFT;
         data |= ((entry.eval() as u64) & EVAL_MASK) << EVAL_SHIFT;
         data |= ((best_move as u64) & BEST_MOVE_MASK) << BEST_MOVE_SHIFT;
@@ -57,7 +57,7 @@ impl PackedEntry {
     }
 
     pub fn unpack(&self) -> TranspositionEntry {
-        let depth = ((self.data >> DEPTH_SHIFT) & DEPTH_MASK) as u8;
+        let depth = ((self.data >> DEPTH_SHIFT) & DEPTH_MASK) as u8 as i8;
 
         let flag = ((self.data >> FLAG_SHIFT) & FLAG_MASK) as u8;
         let flag = TranspositionFlag::from_flag(flag);
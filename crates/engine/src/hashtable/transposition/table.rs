@@ -70,7 +70,9 @@ impl TranspositionTable {
         let stored = unsafe { &mut *self.entries_ptr.add(index) };
         let age = unsafe { *self.age_ptr };
 
-        if age <= stored.age {
+        // An empty slot unpacks to depth zero, which would keep out the
+        // entries of the quiescence search.
+        if age <= stored.age && stored != NULL_ENTRY {
             let stored_entry = stored.unpack();
             if stored_entry.depth > entry.depth {
                 return;
@@ -3,3 +3,4 @@ pub mod generator;
 pub mod hashtable;
 pub mod perft;
 pub mod search;
+pub mod tablebase;
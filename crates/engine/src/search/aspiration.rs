@@ -1,30 +1,81 @@
+use std::sync::atomic::Ordering;
+
 use crate::hashtable::TranspositionTable;
 
-use super::{communication::SearchSender, negamax::negamax, SearchInfo, SearchStats, StopReason};
+use super::{
+    communication::{Info, ScoreBound, SearchSender},
+    error::SearchError,
+    iterative::score_from_eval,
+    negamax::negamax,
+    SearchInfo, SearchStats, MAX_EVAL, MIN_EVAL,
+};
 
 pub const ASPIRATION_WINDOW: i32 = 20;
 
+/// Searches the root with a small window around the evaluation of the last
+/// iteration. If the search fails, only the bound that failed gets widened,
+/// so the re-search is still as narrow as possible.
+/// Source: https://www.chessprogramming.org/Aspiration_Windows
 pub fn aspiration<S: SearchSender>(
     cache: &TranspositionTable,
     info: &mut SearchInfo<S>,
     stats: &mut SearchStats,
     last_eval: i32,
-) -> Result<i32, StopReason> {
+) -> Result<i32, SearchError> {
     let mut window = ASPIRATION_WINDOW;
-    let mut eval = last_eval;
+    let mut alpha = (last_eval - window).max(MIN_EVAL);
+    let mut beta = (last_eval + window).min(MAX_EVAL);
 
     loop {
-        let alpha = eval - window;
-        let beta = eval + window;
+        let eval = negamax(cache, info, stats, alpha, beta, false, false)?;
+
+        if eval <= alpha {
+            // The position is worse than expected. The upper bound is pulled
+            // down as well, as the real evaluation is below the old window.
+            send_bound(cache, info, stats, eval, ScoreBound::Upper)?;
 
-        eval = negamax(cache, info, stats, alpha, beta, false, false)?;
+            beta = (alpha + beta) / 2;
+            alpha = (eval - window).max(MIN_EVAL);
+        } else if eval >= beta {
+            send_bound(cache, info, stats, eval, ScoreBound::Lower)?;
 
-        if alpha < eval && eval < beta {
-            break;
+            beta = (eval + window).min(MAX_EVAL);
+        } else {
+            return Ok(eval);
         }
 
-        window *= 2;
+        window += window / 2;
+    }
+}
+
+/// Reports the bound of a failed search, so that the GUI can show the
+/// evaluation changing before the re-search is done.
+fn send_bound<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &SearchInfo<S>,
+    stats: &mut SearchStats,
+    eval: i32,
+    bound: ScoreBound,
+) -> Result<(), SearchError> {
+    stats.flush_nodes(&info.nodes);
+
+    let elapsed = info.time_frame.elapsed();
+    let nodes = info.nodes.load(Ordering::Relaxed);
+    let nodes_per_second = (nodes as f64 / elapsed.as_secs_f64()) as u64;
+
+    let mut bound_info = Info::new()
+        .depth(stats.depth())
+        .seldepth(stats.max_ply)
+        .time(elapsed.as_millis())
+        .hashfull(cache.full_percentage())
+        .score(score_from_eval(eval, bound))
+        .nodes(nodes)
+        .nps(nodes_per_second);
+    if let Some(multipv) = info.multipv {
+        bound_info = bound_info.multipv(multipv);
     }
 
-    Ok(eval)
+    info.sender.send(bound_info.build())?;
+
+    Ok(())
 }
@@ -3,10 +3,13 @@ use std::num::NonZeroU8;
 use base::r#move::Move;
 use crossbeam_channel::{SendError, Sender};
 
+// The error returns the command, which is boxed so that the results of the
+// search don't become as large as the biggest command.
 pub trait SearchSender {
-    fn send(&self, command: SearchCommand) -> Result<(), SendError<SearchCommand>>;
+    fn send(&self, command: SearchCommand) -> Result<(), Box<SendError<SearchCommand>>>;
 }
 
+#[derive(Clone)]
 pub struct CrossbeamSearchSender {
     sender: Sender<SearchCommand>,
 }
@@ -18,11 +21,12 @@ impl CrossbeamSearchSender {
 }
 
 impl SearchSender for CrossbeamSearchSender {
-    fn send(&self, command: SearchCommand) -> Result<(), SendError<SearchCommand>> {
-        self.sender.send(command)
+    fn send(&self, command: SearchCommand) -> Result<(), Box<SendError<SearchCommand>>> {
+        self.sender.send(command).map_err(Box::new)
     }
 }
 
+#[derive(Clone)]
 pub struct NullSearchSender;
 
 impl NullSearchSender {
@@ -32,7 +36,7 @@ impl NullSearchSender {
 }
 
 impl SearchSender for NullSearchSender {
-    fn send(&self, _command: SearchCommand) -> Result<(), SendError<SearchCommand>> {
+    fn send(&self, _command: SearchCommand) -> Result<(), Box<SendError<SearchCommand>>> {
         Ok(())
     }
 }
@@ -46,24 +50,44 @@ pub enum SearchCommand {
 #[derive(Debug, Copy, Clone)]
 pub struct BestMove {
     pub mov: Move,
+    pub ponder: Option<Move>,
 }
 
 impl BestMove {
-    pub fn new(mov: Move) -> SearchCommand {
-        SearchCommand::BestMove(Self { mov })
+    pub fn new(mov: Move, ponder: Option<Move>) -> SearchCommand {
+        SearchCommand::BestMove(Self { mov, ponder })
     }
 }
 
+/// Tells if the score is exact, or if the search failed high (lower bound)
+/// or low (upper bound) and the real score is only known to be beyond it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScoreBound {
+    Exact,
+    Lower,
+    Upper,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Score {
-    Centipawns(i32),
-    Mate(i32),
+    Centipawns(i32, ScoreBound),
+    Mate(i32, ScoreBound),
+}
+
+impl Score {
+    pub const fn bound(&self) -> ScoreBound {
+        match self {
+            Score::Centipawns(_, bound) => *bound,
+            Score::Mate(_, bound) => *bound,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Info {
     pub depth: Option<u8>,
     pub seldepth: Option<u8>,
+    pub multipv: Option<NonZeroU8>,
     pub time: Option<u128>,
     pub nodes: Option<usize>,
     pub pv: Option<Vec<Move>>,
@@ -71,6 +95,7 @@ pub struct Info {
     pub currmove: Option<Move>,
     pub currmovenumber: Option<NonZeroU8>,
     pub hashfull: Option<u16>,
+    pub tbhits: Option<usize>,
     pub nps: Option<u64>,
     pub string: Option<String>,
 }
@@ -80,6 +105,7 @@ impl Info {
         Self {
             depth: None,
             seldepth: None,
+            multipv: None,
             time: None,
             nodes: None,
             pv: None,
@@ -87,6 +113,7 @@ impl Info {
             currmove: None,
             currmovenumber: None,
             hashfull: None,
+            tbhits: None,
             nps: None,
             string: None,
         }
@@ -102,6 +129,11 @@ impl Info {
         self
     }
 
+    pub fn multipv(mut self, multipv: NonZeroU8) -> Self {
+        self.multipv = Some(multipv);
+        self
+    }
+
     pub fn time(mut self, time: u128) -> Self {
         self.time = Some(time);
         self
@@ -137,6 +169,11 @@ impl Info {
         self
     }
 
+    pub fn tbhits(mut self, tbhits: usize) -> Self {
+        self.tbhits = Some(tbhits);
+        self
+    }
+
     pub fn nps(mut self, nps: u64) -> Self {
         self.nps = Some(nps);
         self
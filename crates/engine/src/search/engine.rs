@@ -0,0 +1,182 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use base::{board::Board, polyglot::parser::PolyglotBook};
+use crossbeam_channel::Receiver;
+
+use crate::{
+    evaluation::parameters::Parameters, hashtable::TranspositionTable, tablebase::Tablebase,
+};
+
+use super::{
+    communication::{CrossbeamSearchSender, Info, SearchCommand},
+    error::{SearchError, SearchPanicked},
+    limits::{SearchLimits, SearchOptions},
+    result::SearchResult,
+    search,
+    skill::Skill,
+};
+
+/// The entry point for using the search as a library. The engine owns the
+/// transposition table and the opening book, so they are kept between
+/// searches just like in a game.
+pub struct Engine {
+    cache: Arc<TranspositionTable>,
+    book: Option<Arc<PolyglotBook>>,
+    options: SearchOptions,
+}
+
+impl Engine {
+    /// Creates an engine with a transposition table of the given size in
+    /// megabytes and without an opening book.
+    pub fn new(cache_size: usize) -> Self {
+        Self {
+            cache: Arc::new(TranspositionTable::size(cache_size)),
+            book: None,
+            options: SearchOptions::default(),
+        }
+    }
+
+    pub fn set_book(&mut self, book: Option<PolyglotBook>) {
+        self.book = book.map(Arc::new);
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.options.threads = threads.max(1);
+    }
+
+    pub fn set_contempt(&mut self, contempt: i32) {
+        self.options.contempt = contempt;
+    }
+
+    pub fn set_skill(&mut self, skill: Option<Skill>) {
+        self.options.skill = skill;
+    }
+
+    pub fn set_parameters(&mut self, parameters: Parameters) {
+        self.options.parameters = Arc::new(parameters);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Option<Tablebase>) {
+        self.options.tablebase = tablebase.map(Arc::new);
+    }
+
+    pub fn set_currmove_delay(&mut self, currmove_delay: u128) {
+        self.options.currmove_delay = currmove_delay;
+    }
+
+    pub fn set_cache_size(&mut self, cache_size: usize) {
+        self.cache = Arc::new(TranspositionTable::size(cache_size));
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    #[inline(always)]
+    pub fn cache(&self) -> &TranspositionTable {
+        &self.cache
+    }
+
+    #[inline(always)]
+    pub const fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    /// Starts searching the board on a background thread. The search can be
+    /// stopped and its info can be read through the returned handle.
+    pub fn start(&self, board: &Board, limits: SearchLimits) -> SearchHandle {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let board = board.clone();
+        let book = self.book.clone();
+        let cache = self.cache.clone();
+        let options = self.options.clone();
+        let search_running = running.clone();
+        let handle = thread::spawn(move || {
+            search(
+                board,
+                book.as_deref(),
+                cache,
+                CrossbeamSearchSender::new(sender),
+                search_running,
+                limits,
+                options,
+            )
+        });
+
+        SearchHandle {
+            running,
+            receiver,
+            handle,
+        }
+    }
+
+    /// Searches the board and blocks until the search is done.
+    pub fn search(&self, board: &Board, limits: SearchLimits) -> Result<SearchResult, SearchError> {
+        self.search_with(board, limits, |_| {})
+    }
+
+    /// Searches the board and blocks until the search is done, while every
+    /// info of the search is passed to the callback.
+    pub fn search_with<F: FnMut(Info)>(
+        &self,
+        board: &Board,
+        limits: SearchLimits,
+        mut callback: F,
+    ) -> Result<SearchResult, SearchError> {
+        let handle = self.start(board, limits);
+        for info in handle.infos() {
+            callback(info);
+        }
+
+        handle.wait()
+    }
+}
+
+/// A search that is running on a background thread.
+pub struct SearchHandle {
+    running: Arc<AtomicBool>,
+    receiver: Receiver<SearchCommand>,
+    handle: JoinHandle<Result<SearchResult, SearchError>>,
+}
+
+impl SearchHandle {
+    /// Tells the search to stop as soon as possible. The best move found so
+    /// far is still returned by `wait`.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Iterates over the info of the search as it is sent. The iterator
+    /// blocks until the next info arrives and ends with the search.
+    pub fn infos(&self) -> impl Iterator<Item = Info> + '_ {
+        self.receiver.iter().filter_map(|command| match command {
+            SearchCommand::Info(info) => Some(info),
+            SearchCommand::BestMove(_) => None,
+        })
+    }
+
+    /// The commands of the search, which also include the best move at the
+    /// end. The channel is disconnected once the search is done.
+    #[inline(always)]
+    pub fn receiver(&self) -> &Receiver<SearchCommand> {
+        &self.receiver
+    }
+
+    /// Blocks until the search is done and returns its result. A panic of
+    /// the search thread is returned as an error.
+    pub fn wait(self) -> Result<SearchResult, SearchError> {
+        self.handle.join().map_err(|_| SearchPanicked)?
+    }
+}
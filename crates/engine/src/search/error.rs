@@ -4,7 +4,7 @@ use thiserror::Error;
 
 use crate::generator::error::MoveGeneratorError;
 
-use super::communication::SearchCommand;
+use super::{communication::SearchCommand, StopReason};
 
 #[derive(Debug, Error)]
 #[error(transparent)]
@@ -12,5 +12,11 @@ pub enum SearchError {
     MoveGeneratorError(#[from] MoveGeneratorError),
     PolyglotError(#[from] PolyglotError),
     BoardError(#[from] BoardError),
-    SendError(#[from] SendError<SearchCommand>),
+    SendError(#[from] Box<SendError<SearchCommand>>),
+    StopReason(#[from] StopReason),
+    SearchPanicked(#[from] SearchPanicked),
 }
+
+#[derive(Debug, Error)]
+#[error("the search thread panicked")]
+pub struct SearchPanicked;
@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::sync::atomic::Ordering;
 
 use base::{board::Board, r#move::Move};
 
@@ -10,65 +10,86 @@ use crate::{
 
 use super::{
     aspiration::aspiration,
-    communication::{Info, Score, SearchSender},
+    communication::{Info, Score, ScoreBound, SearchSender},
     error::SearchError,
+    result::RootMove,
     sort::{pick_next_move, score_moves},
-    SearchInfo, SearchStats, StopReason, CHECKMATE,
+    SearchInfo, SearchStats, CHECKMATE,
 };
 
+/// The outcome of the search of a single thread, used to select the best
+/// move across all threads.
+#[derive(Debug, Clone)]
+pub(crate) struct ThreadResult {
+    pub(crate) best_move: Move,
+    pub(crate) depth: u8,
+    pub(crate) seldepth: u8,
+    pub(crate) eval: i32,
+    pub(crate) pv: Vec<Move>,
+    pub(crate) root_moves: Vec<RootMove>,
+}
+
 pub(crate) fn iterative_deepening<S: SearchSender>(
     cache: &TranspositionTable,
-    mut info: SearchInfo<S>,
-) -> Result<Move, SearchError> {
+    info: &mut SearchInfo<S>,
+) -> Result<ThreadResult, SearchError> {
     let mut last_eval = 0;
 
     let mut best_move = None;
+    let mut best_depth = 0;
+    let mut best_seldepth = 0;
+    let mut best_pv = Vec::new();
     for depth in 1..=info.max_depth {
-        let start = Instant::now();
+        if info.should_skip_depth(depth) {
+            continue;
+        }
 
         let mut stats = SearchStats::new(depth);
 
-        let result = aspiration(cache, &mut info, &mut stats, last_eval);
+        let result = aspiration(cache, info, &mut stats, last_eval);
+        stats.flush_nodes(&info.nodes);
+
+        // A search that was stopped during a re-search only reported the
+        // bound that failed, so the last completed depth is kept instead.
         let eval = match result {
-            Ok(result) => result,
-            Err(StopReason::TimeUp)
-            | Err(StopReason::NodesExceeded)
-            | Err(StopReason::ForcedStop) => break,
+            Ok(eval) => eval,
+            Err(SearchError::StopReason(_)) => break,
+            Err(error) => return Err(error),
         };
 
         last_eval = eval;
 
-        let elapsed = start.elapsed();
-        let nodes_per_second = (stats.nodes as f64 / elapsed.as_secs_f64()) as u64;
-        info.accumulated_nodes += stats.nodes;
-
-        let score = if eval.abs() >= CHECKMATE_MIN {
-            let ply = CHECKMATE - eval.abs();
-            let is_odd = ply % 2 == 1;
-
-            let moves = if is_odd { (ply + 1) / 2 } else { ply / 2 };
-            Score::Mate(moves * eval.signum())
-        } else {
-            Score::Centipawns(eval)
-        };
-
-        let pv_line = get_pv_line(&mut info, cache, depth)?;
+        let pv_line = get_pv_line(info, cache, depth)?;
         best_move = pv_line.get(0).cloned();
+        best_depth = depth;
+        best_seldepth = stats.max_ply;
+        best_pv = pv_line.clone();
+
+        // The reported nodes are the ones of all threads combined, so the
+        // time is taken from the start of the whole search.
+        let elapsed = info.time_frame.elapsed();
+        let nodes = info.nodes.load(Ordering::Relaxed);
+        let nodes_per_second = (nodes as f64 / elapsed.as_secs_f64()) as u64;
 
         let hashfull = cache.full_percentage();
 
-        info.sender.send(
-            Info::new()
-                .depth(depth)
-                .seldepth(stats.max_ply)
-                .time(elapsed.as_millis())
-                .hashfull(hashfull)
-                .score(score)
-                .nodes(stats.nodes)
-                .pv(pv_line)
-                .nps(nodes_per_second)
-                .build(),
-        )?;
+        let mut depth_info = Info::new()
+            .depth(depth)
+            .seldepth(stats.max_ply)
+            .time(elapsed.as_millis())
+            .hashfull(hashfull)
+            .score(score_from_eval(eval, ScoreBound::Exact))
+            .nodes(nodes)
+            .pv(pv_line)
+            .nps(nodes_per_second);
+        if info.tablebase.is_some() {
+            depth_info = depth_info.tbhits(info.tb_hits.load(Ordering::Relaxed));
+        }
+        if let Some(multipv) = info.multipv {
+            depth_info = depth_info.multipv(multipv);
+        }
+
+        info.sender.send(depth_info.build())?;
 
         // If we are not in infinite mode, we want to check if we have enough
         // time for the next iteration. We approximate the time the next iteration
@@ -89,22 +110,46 @@ pub(crate) fn iterative_deepening<S: SearchSender>(
         }
     }
 
-    let best_move = match best_move {
-        Some(mov) => mov,
-        None => {
-            // If there is no best move, choose a random move as we did not
-            // have enough time to search the best move.
-            let move_generator = MoveGenerator::<AllMoves>::new(&info.board);
-            let mut stats = SearchStats::new(0);
-
-            let moves = move_generator.collect::<Vec<Move>>();
-            let mut scored_moves = score_moves(&info, &mut stats, moves, None);
-            let next_move = pick_next_move(0, &mut scored_moves);
-            next_move
-        }
-    };
+    // If there is no best move, choose a random move as we did not
+    // have enough time to search the best move.
+    let best_move = best_move.unwrap_or_else(|| fallback_move(info));
+
+    Ok(ThreadResult {
+        best_move,
+        depth: best_depth,
+        seldepth: best_seldepth,
+        eval: last_eval,
+        pv: best_pv,
+        root_moves: info.root_moves.clone(),
+    })
+}
+
+/// Picks the move that the move ordering rates the best, used whenever
+/// no search was able to find a move.
+pub(crate) fn fallback_move<S: SearchSender>(info: &SearchInfo<S>) -> Move {
+    let move_generator = MoveGenerator::<AllMoves>::new(&info.board);
+    let mut stats = SearchStats::new(0);
 
-    Ok(best_move)
+    let mut moves = move_generator.collect::<Vec<Move>>();
+    if !info.moves.is_empty() {
+        moves.retain(|mov| info.moves.contains(mov));
+    }
+
+    let mut scored_moves = score_moves(info, &mut stats, moves, None);
+    let next_move = pick_next_move(0, &mut scored_moves);
+    next_move
+}
+
+pub(crate) fn score_from_eval(eval: i32, bound: ScoreBound) -> Score {
+    if eval.abs() >= CHECKMATE_MIN {
+        let ply = CHECKMATE - eval.abs();
+        let is_odd = ply % 2 == 1;
+
+        let moves = if is_odd { (ply + 1) / 2 } else { ply / 2 };
+        Score::Mate(moves * eval.signum(), bound)
+    } else {
+        Score::Centipawns(eval, bound)
+    }
 }
 
 pub(crate) fn get_pv_line<S: SearchSender>(
@@ -157,5 +202,5 @@ pub(crate) fn move_exists(board: &Board, given: Move) -> Result<bool, MoveGenera
         }
     }
 
-    return Ok(false);
+    Ok(false)
 }
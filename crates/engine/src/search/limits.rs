@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use base::r#move::Move;
+
+use crate::{evaluation::parameters::Parameters, tablebase::Tablebase};
+
+use super::{skill::Skill, TimeFrame, CURRMOVE_DELAY};
+
+/// Restricts how long a single search runs. Without any limit, the search
+/// runs until it is stopped or the maximum depth is reached.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<usize>,
+    pub move_time: Option<u128>,
+    pub time_left: Option<u128>,
+    pub increment: u128,
+    pub mate: Option<u8>,
+    pub infinite: bool,
+    pub moves: Vec<Move>,
+}
+
+impl SearchLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn depth(mut self, depth: u8) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Searches exactly the given amount of milliseconds.
+    pub fn move_time(mut self, move_time: u128) -> Self {
+        self.move_time = Some(move_time);
+        self
+    }
+
+    /// The remaining time on the clock of the side to move and the increment
+    /// per move, both in milliseconds. The time for the search is estimated
+    /// from them.
+    pub fn clock(mut self, time_left: u128, increment: u128) -> Self {
+        self.time_left = Some(time_left);
+        self.increment = increment;
+        self
+    }
+
+    /// Searches for a mate in the given amount of moves instead of the best move.
+    pub fn mate(mut self, moves: u8) -> Self {
+        self.mate = Some(moves);
+        self
+    }
+
+    pub fn infinite(mut self) -> Self {
+        self.infinite = true;
+        self
+    }
+
+    /// Restricts the search to the given root moves.
+    pub fn search_moves(mut self, moves: Vec<Move>) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    #[inline(always)]
+    pub const fn is_infinite(&self) -> bool {
+        self.infinite
+    }
+
+    /// The time for the search. Without any time control it is unlimited,
+    /// so the search only stops at one of the other limits.
+    pub fn time_frame(&self) -> TimeFrame {
+        match (self.move_time, self.time_left) {
+            (Some(move_time), _) => TimeFrame::new(move_time),
+            (None, Some(time_left)) => TimeFrame::estimate(time_left, self.increment),
+            (None, None) => TimeFrame::new(u128::MAX),
+        }
+    }
+}
+
+/// The settings of the engine that stay the same between searches.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub threads: usize,
+    pub contempt: i32,
+    pub skill: Option<Skill>,
+    pub parameters: Arc<Parameters>,
+    pub tablebase: Option<Arc<Tablebase>>,
+    /// The time in milliseconds after which the root moves are reported.
+    pub currmove_delay: u128,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            threads: 1,
+            contempt: 0,
+            skill: None,
+            parameters: Arc::default(),
+            tablebase: None,
+            currmove_delay: CURRMOVE_DELAY,
+        }
+    }
+}
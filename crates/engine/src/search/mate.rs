@@ -0,0 +1,257 @@
+use std::sync::atomic::Ordering;
+
+use base::r#move::Move;
+
+use crate::{
+    generator::{AllMoves, MoveGenerator},
+    hashtable::{
+        entry::{TranspositionEntry, TranspositionFlag},
+        TranspositionTable,
+    },
+};
+
+use super::{
+    communication::{Info, Score, ScoreBound, SearchSender},
+    error::SearchError,
+    iterative::{fallback_move, ThreadResult},
+    should_stop_search, SearchInfo, SearchStats, StopReason, CHECKMATE, CHECK_TERMINATION,
+    MAX_MATE_MOVES,
+};
+
+/// Tries to prove a forced mate in at most `max_moves` moves for the side to
+/// move. Unlike the regular search, this is an exact AND-OR search without
+/// any pruning or evaluation: every move of the attacker is tried and every
+/// reply of the defender has to lead to a mate as well.
+///
+/// The mate lengths are tried from the shortest to the longest, so the first
+/// proof is the shortest mate. As soon as a mate is proven, the search returns.
+/// Mates longer than [`MAX_MATE_MOVES`] are not searched.
+///
+/// The table has to be a private one of the mate search, as the entries of
+/// the regular search come from a pruned search and are no proofs.
+/// Source: https://www.chessprogramming.org/Mate_Search
+pub(crate) fn mate_search<S: SearchSender>(
+    cache: &TranspositionTable,
+    mut info: SearchInfo<S>,
+    max_moves: u8,
+) -> Result<ThreadResult, SearchError> {
+    let max_moves = max_moves.min(MAX_MATE_MOVES);
+    for moves in 1..=max_moves {
+        let mut stats = SearchStats::new(moves * 2 - 1);
+
+        let result = attack(cache, &mut info, &mut stats, moves);
+        stats.flush_nodes(&info.nodes);
+
+        let line = match result {
+            Ok(Some(line)) => line,
+            Ok(None) => continue,
+            Err(StopReason::TimeUp)
+            | Err(StopReason::NodesExceeded)
+            | Err(StopReason::ForcedStop) => break,
+        };
+
+        let elapsed = info.time_frame.elapsed();
+        let nodes = info.nodes.load(Ordering::Relaxed);
+        let nodes_per_second = (nodes as f64 / elapsed.as_secs_f64()) as u64;
+
+        let mate_moves = line.len().div_ceil(2);
+        info.sender.send(
+            Info::new()
+                .depth(stats.depth())
+                .seldepth(line.len() as u8)
+                .time(elapsed.as_millis())
+                .score(Score::Mate(mate_moves as i32, ScoreBound::Exact))
+                .nodes(nodes)
+                .pv(line.clone())
+                .nps(nodes_per_second)
+                .build(),
+        )?;
+
+        return Ok(ThreadResult {
+            best_move: line[0],
+            depth: stats.depth(),
+            seldepth: line.len() as u8,
+            eval: CHECKMATE - line.len() as i32,
+            pv: line,
+            root_moves: Vec::new(),
+        });
+    }
+
+    info.sender.send(
+        Info::new()
+            .string(format!("no mate in {} found", max_moves))
+            .build(),
+    )?;
+
+    Ok(ThreadResult {
+        best_move: fallback_move(&info),
+        depth: 0,
+        seldepth: 0,
+        eval: 0,
+        pv: Vec::new(),
+        root_moves: Vec::new(),
+    })
+}
+
+/// Returns the mating line if the side to move can force a mate in at most
+/// `moves` moves.
+fn attack<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    stats: &mut SearchStats,
+    moves: u8,
+) -> Result<Option<Vec<Move>>, StopReason> {
+    stats.nodes += 1;
+    if stats.nodes & CHECK_TERMINATION == 0 {
+        should_stop_search(info, stats)?;
+    }
+
+    if moves == 0 {
+        return Ok(None);
+    }
+
+    // Checks are the most forcing moves, so they are tried first. On the
+    // last move only checks can mate, which prunes all the other moves.
+    // At the root only the moves that were asked for are tried.
+    let is_root = stats.ply() == 0;
+    let mut candidates = MoveGenerator::<AllMoves>::new(&info.board)
+        .filter(|mov| !is_root || info.moves.is_empty() || info.moves.contains(mov))
+        .map(|mov| (info.board.gives_check(mov), mov))
+        .filter(|(gives_check, _)| moves > 1 || *gives_check)
+        .collect::<Vec<(bool, Move)>>();
+    candidates.sort_by_key(|(gives_check, mov)| (!gives_check, !mov.is_capture()));
+
+    for (_, mov) in candidates {
+        info.board.make(mov);
+        stats.increase_ply();
+        let result = defend(cache, info, stats, moves - 1);
+        stats.decrease_ply();
+        info.board.unmake(mov);
+
+        if let Some(mut line) = result? {
+            line.insert(0, mov);
+            return Ok(Some(line));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the longest line the side to move can resist, if every reply
+/// leads to a mate within `moves` moves of the attacker.
+///
+/// The result is stored in the table with mate scores relative to the node,
+/// so that they also hold if the node is reached at another ply: a proven
+/// node with the longest resistance as exact score and a disproven node as
+/// lower bound, since the defender survives the moves.
+fn defend<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    stats: &mut SearchStats,
+    moves: u8,
+) -> Result<Option<Vec<Move>>, StopReason> {
+    stats.nodes += 1;
+    if stats.nodes & CHECK_TERMINATION == 0 {
+        should_stop_search(info, stats)?;
+    }
+
+    let mut replies = MoveGenerator::<AllMoves>::new(&info.board).collect::<Vec<Move>>();
+    if replies.is_empty() {
+        // Either we found the mate, or the attacker stalemated the defender.
+        return Ok(info.board.is_check().then(Vec::new));
+    }
+
+    if moves == 0 || info.board.is_draw(stats.ply()) {
+        return Ok(None);
+    }
+
+    // The defender is mated at the latest after its reply and the remaining
+    // moves of the attacker.
+    let depth = moves * 2 - 1;
+    let mate_eval = -CHECKMATE + (moves * 2) as i32;
+
+    // A proven node only has to verify its longest resistance, as all the
+    // other replies were already shown to be mated faster.
+    let mut is_proven = false;
+    if let Some(entry) = cache.probe(info.board.hash()) {
+        if entry.depth() >= depth as i8
+            && entry.flag() != TranspositionFlag::UpperBound
+            && entry.eval() > mate_eval
+        {
+            return Ok(None);
+        }
+
+        // Either the longest resistance or the refutation of an earlier
+        // search, which both are the best guess for the first reply.
+        if let Some(index) = entry
+            .best_move()
+            .and_then(|mov| replies.iter().position(|reply| *reply == mov))
+        {
+            replies.swap(0, index);
+            is_proven = entry.flag() == TranspositionFlag::Exact && entry.eval() <= mate_eval;
+        }
+    }
+
+    let mut longest: Option<Vec<Move>> = None;
+    for reply in replies {
+        info.board.make(reply);
+        stats.increase_ply();
+
+        // The shortest mate after each reply is searched, so that the
+        // reported line is the best defence against it.
+        let mut result = Ok(None);
+        for remaining in 1..=moves {
+            result = attack(cache, info, stats, remaining);
+            if !matches!(result, Ok(None)) {
+                break;
+            }
+        }
+
+        stats.decrease_ply();
+        info.board.unmake(reply);
+
+        let mut line = match result? {
+            Some(line) => line,
+            None => {
+                cache.store(
+                    info.board.hash(),
+                    TranspositionEntry::new(
+                        depth as i8,
+                        TranspositionFlag::LowerBound,
+                        mate_eval + 1,
+                        Some(reply),
+                    ),
+                );
+                return Ok(None);
+            }
+        };
+
+        let is_longer = match &longest {
+            Some(longest) => line.len() + 1 > longest.len(),
+            None => true,
+        };
+
+        if is_longer {
+            line.insert(0, reply);
+            longest = Some(line);
+        }
+
+        if is_proven {
+            break;
+        }
+    }
+
+    if let (Some(line), false) = (&longest, is_proven) {
+        cache.store(
+            info.board.hash(),
+            TranspositionEntry::new(
+                depth as i8,
+                TranspositionFlag::Exact,
+                -CHECKMATE + line.len() as i32,
+                line.first().copied(),
+            ),
+        );
+    }
+
+    Ok(longest)
+}
@@ -1,12 +1,30 @@
+/// Records a trace point in the search. Without the `trace` feature the
+/// macro expands to nothing, so the search does not pay for the tracer.
+macro_rules! trace {
+    ($info:expr, $method:ident($($arg:expr),*)) => {
+        #[cfg(feature = "trace")]
+        $info.tracer.$method($($arg),*);
+    };
+}
+
 pub mod search;
 pub use search::*;
 
 pub mod communication;
+pub mod engine;
 pub mod error;
+pub mod limits;
+pub mod result;
+pub mod skill;
+#[cfg(feature = "trace")]
+pub mod trace;
 
 pub(crate) mod aspiration;
 pub(crate) mod iterative;
 pub(crate) mod killers;
+pub(crate) mod mate;
 pub(crate) mod negamax;
 pub(crate) mod quiescence;
 pub(crate) mod sort;
+
+mod tests;
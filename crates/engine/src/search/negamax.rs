@@ -1,27 +1,53 @@
+use std::sync::atomic::Ordering;
+
 use base::r#move::Move;
 
 use crate::{
-    evaluation::evaluate,
+    evaluation::evaluate_cached,
     generator::{AllMoves, MoveGenerator, MAX_MOVES},
     hashtable::{
         entry::{TranspositionEntry, TranspositionFlag},
         TranspositionTable,
     },
+    tablebase::Wdl,
 };
 
 use super::{
-    communication::{Info, SearchSender},
+    communication::{ScoreBound, SearchSender},
+    iterative::score_from_eval,
     quiescence::quiescence,
-    should_stop_search,
+    send_currmove, send_stats, should_stop_search,
     sort::{pick_next_move, score_moves},
-    SearchInfo, SearchStats, StopReason, CHECKMATE, CHECKMATE_MIN, CHECK_TERMINATION, DRAW,
-    MIN_EVAL, NULL_DEPTH_REDUCTION, SEND_STATS,
+    DepthReduction, SearchInfo, SearchStats, StopReason, CHECKMATE, CHECKMATE_MIN,
+    CHECK_TERMINATION, IID_DEPTH_REDUCTION, IID_MIN_DEPTH, IIR_MIN_DEPTH, MIN_EVAL,
+    NULL_DEPTH_REDUCTION, NULL_MIN_DEPTH, NULL_VERIFICATION_DEPTH, TB_WIN,
 };
 
+#[cfg(feature = "trace")]
+use super::trace::{Cutoff, NodeKind, Pruning};
+
 pub const LATE_MOVE_PRUNING: [usize; 6] = [MAX_MOVES, 3, 6, 10, 15, 21];
+// The default margins, the search uses the ones of the evaluation parameters.
 pub const FUTILITY_MARGINS: [i32; 3] = [0, 300, 500];
 
 pub(crate) fn negamax<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    stats: &mut SearchStats,
+    alpha: i32,
+    beta: i32,
+    extended: bool,
+    do_null_move: bool,
+) -> Result<i32, StopReason> {
+    trace!(info, enter(NodeKind::Search, stats, alpha, beta));
+    let result = search_node(cache, info, stats, alpha, beta, extended, do_null_move);
+    trace!(info, exit(&result));
+
+    result
+}
+
+#[inline(always)]
+fn search_node<S: SearchSender>(
     cache: &TranspositionTable,
     info: &mut SearchInfo<S>,
     stats: &mut SearchStats,
@@ -33,41 +59,58 @@ pub(crate) fn negamax<S: SearchSender>(
     stats.nodes += 1;
     if stats.nodes & CHECK_TERMINATION == 0 {
         should_stop_search(info, stats)?;
+        send_stats(cache, info)?;
     }
 
+    // Nodes which are searched with an open window are part of the
+    // principal variation. All the others are searched with a null window.
+    let is_pv_node = beta - alpha > 1;
+
     // ~~~~~~~~~ CUT-OFF ~~~~~~~~~
     // These are tests which decide if you should stop searching based
     // on the current state of the board.
-    if info.board.is_draw() {
-        return Ok(DRAW);
+    if info.board.is_draw(stats.ply()) {
+        trace!(info, cutoff(Cutoff::Draw));
+        return Ok(info.draw_score());
     } else if stats.is_leaf() {
         // Corrects the node count, as it will be increased by one inside
         // the quiescence search.
         stats.nodes -= 1;
 
         stats.increase_ply();
-        let result = quiescence(cache, info, stats, alpha, beta);
+        let result = quiescence(cache, info, stats, alpha, beta, true);
         stats.decrease_ply();
 
         return Ok(result?);
     }
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
+    // The root moves might be restricted, so the table can neither cut off
+    // the root nor suggest a move that is not allowed.
+    let is_restricted_root = stats.ply() == 0 && !info.moves.is_empty();
+
     let mut hash_move = None;
     if let Some(entry) = cache.probe(info.board.hash()) {
-        if entry.depth() >= stats.depth() {
-            if let Some(best_move) = entry.best_move() {
-                hash_move = Some(best_move);
-            }
-
+        // Even if the entry is not deep enough to be trusted, the best move
+        // is still a good guess for the move ordering.
+        hash_move = entry
+            .best_move()
+            .filter(|mov| !is_restricted_root || info.moves.contains(mov));
+        trace!(info, tt_hit(entry.flag()));
+
+        if entry.depth() >= stats.depth() as i8 && !is_restricted_root {
             let eval = entry.eval();
             match entry.flag() {
-                TranspositionFlag::Exact => return Ok(eval),
+                TranspositionFlag::Exact => {
+                    trace!(info, cutoff(Cutoff::Transposition));
+                    return Ok(eval);
+                }
                 TranspositionFlag::LowerBound => alpha = alpha.max(eval),
                 TranspositionFlag::UpperBound => beta = beta.min(eval),
             }
 
             if alpha >= beta {
+                trace!(info, cutoff(Cutoff::Transposition));
                 return Ok(eval);
             }
         }
@@ -79,12 +122,25 @@ pub(crate) fn negamax<S: SearchSender>(
     // Source: https://www.chessprogramming.org/Terminal_Node
     let move_generator = MoveGenerator::<AllMoves>::new(&info.board);
     if move_generator.is_stalemate(&info.board) {
-        return Ok(DRAW);
+        trace!(info, cutoff(Cutoff::Stalemate));
+        return Ok(info.draw_score());
     } else if move_generator.is_checkmate(&info.board) {
+        trace!(info, cutoff(Cutoff::Checkmate));
         return Ok(-CHECKMATE + stats.ply() as i32);
     }
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
+    // ~~~~~~~~ TABLEBASE ~~~~~~~~
+    // The result of positions with few pieces is looked up in the tables.
+    // They only know the result regardless of the fifty move rule, thus
+    // they are only probed right after a capture or a pawn move.
+    // Source: https://www.chessprogramming.org/Syzygy_Bases
+    if let Some(eval) = probe_tablebase(cache, info, stats, alpha, beta) {
+        trace!(info, cutoff(Cutoff::Tablebase));
+        return Ok(eval);
+    }
+    // ~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
     // ~~~~~~~~ SELECTIVITY ~~~~~~~~
     // Source: https://www.chessprogramming.org/Selectivity
     if info.board.is_check() && extended {
@@ -93,6 +149,13 @@ pub(crate) fn negamax<S: SearchSender>(
     }
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
+    let board_eval = evaluate_cached(
+        &info.board,
+        info.board.active(),
+        &info.parameters,
+        &mut info.pawn_table,
+    );
+
     // ~~~~~~~~~ NULL MOVE PRUNING ~~~~~~~~~
     // Using this pruning technique we check if our position is so
     // good that the opponent could even make a double move without
@@ -103,78 +166,148 @@ pub(crate) fn negamax<S: SearchSender>(
     // it could lead to a wrong decision.
     //
     // Source: https://www.chessprogramming.org/Null_Move_Pruning
-    // TODO: Add zugzwang detection
-    if do_null_move && !info.board.is_check() && stats.depth() >= 5 {
+    if do_null_move && can_null_move_prune(info, stats, board_eval, beta) {
+        let reduction = null_depth_reduction(stats, board_eval, beta);
+
         info.board.make_null();
 
-        stats.make_search(NULL_DEPTH_REDUCTION);
+        trace!(info, child(None, Some(Pruning::NullMove)));
+        stats.make_search(reduction);
         let result = negamax(cache, info, stats, -beta, -beta + 1, extended, false);
-        stats.unmake_search(NULL_DEPTH_REDUCTION);
+        stats.unmake_search(reduction);
 
         info.board.unmake_null();
 
         let null_eval = -result?;
         if null_eval >= beta {
-            return Ok(beta);
+            // At a high depth a wrong null move cut-off is really expensive,
+            // thus we verify it with a reduced search of the current node
+            // where null moves are disabled. If the side to move is in
+            // zugzwang the verification will fail and we search normally.
+            //
+            // Source: https://www.chessprogramming.org/Null_Move_Pruning#Verified_Null_Move_Pruning
+            if stats.depth() < NULL_VERIFICATION_DEPTH {
+                trace!(info, cutoff(Cutoff::NullMove));
+                return Ok(beta);
+            }
+
+            trace!(info, child(None, Some(Pruning::NullMoveVerification)));
+            stats.decrease_depth(reduction);
+            let research = stats.begin_research();
+            let result = negamax(cache, info, stats, beta - 1, beta, extended, false);
+            stats.end_research(research);
+            stats.increase_depth(reduction);
+
+            let verified_eval = result?;
+            if verified_eval >= beta {
+                trace!(info, cutoff(Cutoff::NullMove));
+                return Ok(beta);
+            }
         }
     }
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
+    // ~~~~~~~~~ INTERNAL ITERATIVE DEEPENING ~~~~~~~~~
+    // If there is no hash move, the move ordering only relies on the
+    // heuristics which are way worse. For nodes of the principal variation
+    // we do a reduced search first to get a best move from the table.
+    //
+    // Other nodes are most likely not that important, thus we just reduce
+    // the depth. The next time we visit this node we will have a hash move.
+    //
+    // Source: https://www.chessprogramming.org/Internal_Iterative_Deepening
+    // Source: https://www.chessprogramming.org/Internal_Iterative_Reductions
+    let mut iir_reduction = 0;
+    if hash_move.is_none() {
+        if is_pv_node && stats.depth() >= IID_MIN_DEPTH {
+            trace!(info, child(None, Some(Pruning::InternalIterativeDeepening)));
+
+            // Corrects the node count, as the same node is entered again.
+            stats.nodes -= 1;
+
+            stats.decrease_depth(IID_DEPTH_REDUCTION);
+            let research = stats.begin_research();
+            let result = negamax(cache, info, stats, alpha, beta, extended, true);
+            stats.end_research(research);
+            stats.increase_depth(IID_DEPTH_REDUCTION);
+
+            result?;
+
+            if let Some(entry) = cache.probe(info.board.hash()) {
+                hash_move = entry
+                    .best_move()
+                    .filter(|mov| !is_restricted_root || info.moves.contains(mov));
+            }
+        } else if !is_pv_node && stats.depth() >= IIR_MIN_DEPTH {
+            iir_reduction = 1;
+        }
+    }
+
+    // The rest of the node is searched with the reduced depth, which is
+    // restored on every return.
+    let mut reduced_stats = DepthReduction::new(stats, iir_reduction);
+    let stats = &mut *reduced_stats;
+    // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
     // ~~~~~~~~~ MOVE ORDERING ~~~~~~~~~
     // Used to improve the efficiency of the alpha-beta algorithm.
     // Source: https://www.chessprogramming.org/Move_Ordering
-    let moves = move_generator.collect::<Vec<Move>>();
+    let mut moves = move_generator.collect::<Vec<Move>>();
+    if is_restricted_root {
+        moves.retain(|mov| info.moves.contains(mov));
+    }
+
     let mut scored_moves = score_moves(info, stats, moves, hash_move);
     // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 
-    // TODO: Make this better
-    if stats.nodes & SEND_STATS == 0 {
-        let elapsed = stats.start_time.elapsed();
-        let nps = (stats.nodes as f64 / elapsed.as_secs_f64()) as u64;
-        // TODO: Remove the unwrap
-        info.sender
-            .send(
-                Info::new()
-                    .time(elapsed.as_millis())
-                    .nodes(stats.nodes)
-                    .hashfull(cache.full_percentage())
-                    .nps(nps)
-                    .build(),
-            )
-            .unwrap();
-    }
-
     let mut flag = TranspositionFlag::UpperBound;
     let mut best_move = hash_move;
     let mut best_eval = MIN_EVAL;
 
-    let board_eval = evaluate(&info.board, info.board.active());
     let mut quiet_moves = 0;
 
     for move_index in 0..scored_moves.len() {
         let next_move = pick_next_move(move_index, &mut scored_moves);
         let is_pv_move = move_index == 0;
 
-        info.board.make(next_move);
-
+        // Moves giving check are never futile, as they could lead to a mate.
+        let gives_check = info.board.gives_check(next_move);
         if !is_pv_move
+            && !gives_check
             && can_futile_prune(info, stats, next_move, quiet_moves, board_eval, alpha, beta)
         {
-            info.board.unmake(next_move);
+            trace!(
+                info,
+                skip(
+                    next_move,
+                    if can_late_move_prune(stats, quiet_moves) {
+                        Pruning::LateMovePruning
+                    } else {
+                        Pruning::Futility
+                    }
+                )
+            );
             continue;
         }
 
-        // TODO: Handle moves giving check
-        if !next_move.is_capture() && !next_move.is_castling() && !next_move.is_promotion() {
+        if !next_move.is_tactical() && !next_move.is_castling() && !gives_check {
             quiet_moves += 1;
         }
 
+        if stats.is_root() {
+            send_currmove(info, next_move, move_index)?;
+        }
+
+        let nodes_before = stats.nodes;
+        info.board.make(next_move);
+
         // The evaluation of the current move.
         let mut child_eval;
 
         // As we assume that the first move is the best one, we only want to
         // search this specific move with the full window.
         if is_pv_move {
+            trace!(info, child(Some(next_move), None));
             stats.make_search(1);
             let result = negamax(cache, info, stats, -beta, -alpha, extended, true);
             stats.unmake_search(1);
@@ -186,12 +319,12 @@ pub(crate) fn negamax<S: SearchSender>(
 
             child_eval = -result.unwrap();
         } else {
-            if move_index >= 4
-                && stats.depth() >= 4
-                && !info.board.is_check()
-                && !next_move.is_tactical()
-            {
+            if move_index >= 4 && stats.depth() >= 4 && !gives_check && !next_move.is_tactical() {
                 // TODO: Calculate the depth reduction
+                trace!(
+                    info,
+                    child(Some(next_move), Some(Pruning::LateMoveReduction))
+                );
                 stats.make_search(3);
                 let result = negamax(cache, info, stats, -(alpha + 1), -alpha, extended, true);
                 stats.unmake_search(3);
@@ -209,6 +342,7 @@ pub(crate) fn negamax<S: SearchSender>(
             if child_eval > alpha {
                 // If its not the principal variation move test that
                 // it is not a better move by using the null window search.
+                trace!(info, child(Some(next_move), None));
                 stats.make_search(1);
                 let result = negamax(cache, info, stats, -alpha - 1, -alpha, extended, true);
                 stats.unmake_search(1);
@@ -223,6 +357,7 @@ pub(crate) fn negamax<S: SearchSender>(
                 // If the test failed, we need to research the move with the
                 // full window.
                 if child_eval > alpha && child_eval < beta {
+                    trace!(info, child(Some(next_move), None));
                     stats.make_search(1);
                     let result = negamax(cache, info, stats, -beta, -alpha, extended, true);
                     stats.unmake_search(1);
@@ -239,6 +374,19 @@ pub(crate) fn negamax<S: SearchSender>(
 
         info.board.unmake(next_move);
 
+        if stats.is_root() {
+            let bound = if child_eval <= alpha {
+                ScoreBound::Upper
+            } else if child_eval >= beta {
+                ScoreBound::Lower
+            } else {
+                ScoreBound::Exact
+            };
+
+            let score = score_from_eval(child_eval, bound);
+            info.update_root_move(next_move, score, stats.nodes - nodes_before);
+        }
+
         best_eval = best_eval.max(child_eval);
 
         if best_eval <= alpha {
@@ -276,18 +424,105 @@ pub(crate) fn negamax<S: SearchSender>(
             }
         }
 
+        trace!(info, cutoff(Cutoff::Beta));
         flag = TranspositionFlag::LowerBound;
         break;
     }
 
     cache.store(
         info.board.hash(),
-        TranspositionEntry::new(stats.depth(), flag, best_eval, best_move),
+        TranspositionEntry::new(stats.depth() as i8, flag, best_eval, best_move),
     );
 
     Ok(best_eval)
 }
 
+/// Probes the tablebases and returns the score if it is enough to cut off
+/// the node. Wins and losses are only bounds, as the search might still
+/// find a faster mate, while draws are exact.
+fn probe_tablebase<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    stats: &SearchStats,
+    alpha: i32,
+    beta: i32,
+) -> Option<i32> {
+    let tablebase = info.tablebase.as_ref()?;
+    if stats.ply() == 0 || info.board.halfmoves() != 0 || !tablebase.can_probe(&info.board) {
+        return None;
+    }
+
+    let wdl = tablebase.probe_wdl(&mut info.board).ok()?;
+    info.tb_hits.fetch_add(1, Ordering::Relaxed);
+
+    let (eval, flag) = match wdl {
+        Wdl::Win => (TB_WIN - stats.ply() as i32, TranspositionFlag::LowerBound),
+        Wdl::Loss => (-TB_WIN + stats.ply() as i32, TranspositionFlag::UpperBound),
+        // Cursed wins and blessed losses are drawn by the fifty move rule,
+        // but are still slightly better or worse than a real draw.
+        _ => (info.draw_score() + wdl.value(), TranspositionFlag::Exact),
+    };
+
+    let is_cutoff = match flag {
+        TranspositionFlag::Exact => true,
+        TranspositionFlag::LowerBound => eval >= beta,
+        TranspositionFlag::UpperBound => eval <= alpha,
+    };
+
+    if !is_cutoff {
+        return None;
+    }
+
+    cache.store(
+        info.board.hash(),
+        TranspositionEntry::new(stats.depth() as i8, flag, eval, None),
+    );
+
+    Some(eval)
+}
+
+fn can_null_move_prune<S: SearchSender>(
+    info: &SearchInfo<S>,
+    stats: &SearchStats,
+    eval: i32,
+    beta: i32,
+) -> bool {
+    if stats.depth() < NULL_MIN_DEPTH || info.board.is_check() {
+        return false;
+    }
+
+    // A null move cut-off can't prove a checkmate, thus we dont want to
+    // use it if we are searching for one.
+    if beta.abs() >= CHECKMATE_MIN {
+        return false;
+    }
+
+    // Zugzwang positions mostly occur if there are only pawns and the
+    // king left. In these positions passing the turn would be the best
+    // move, which makes the assumption of null move pruning wrong.
+    if !info.board.has_non_pawn_material(info.board.active()) {
+        return false;
+    }
+
+    // If we are already worse than beta, it is very unlikely that the
+    // null move will fail high.
+    eval >= beta
+}
+
+pub(crate) fn null_depth_reduction(stats: &SearchStats, eval: i32, beta: i32) -> u8 {
+    let mut reduction = NULL_DEPTH_REDUCTION;
+
+    // The deeper we are searching, the more we can reduce the search.
+    reduction += stats.depth() / 6;
+
+    // The further we are above beta, the more likely the null move will
+    // fail high. Thus we can reduce the search even more.
+    let margin = (eval - beta) / 200;
+    reduction += margin.clamp(0, 3) as u8;
+
+    reduction.min(stats.depth())
+}
+
 fn can_futile_prune<S: SearchSender>(
     info: &SearchInfo<S>,
     stats: &SearchStats,
@@ -297,9 +532,9 @@ fn can_futile_prune<S: SearchSender>(
     alpha: i32,
     beta: i32,
 ) -> bool {
-    // TODO: Check if this move is checking the opponent
+    let margins = &info.parameters.futility_margins;
     if stats.depth() == 0
-        || stats.depth() as usize >= FUTILITY_MARGINS.len()
+        || stats.depth() as usize >= margins.len()
         || info.board.is_check()
         || mov.is_capture()
         || mov.is_castling()
@@ -321,7 +556,7 @@ fn can_futile_prune<S: SearchSender>(
         return true;
     }
 
-    let delta_value = FUTILITY_MARGINS[stats.depth() as usize];
+    let delta_value = margins[stats.depth() as usize];
     eval + delta_value < alpha
 }
 
@@ -1,29 +1,33 @@
 use base::r#move::Move;
 
 use crate::{
-    evaluation::evaluate,
-    generator::{CaptureMoves, MoveGenerator},
-    hashtable::TranspositionTable,
+    evaluation::evaluate_cached,
+    generator::{AllMoves, CaptureMoves, MoveGenerator, QuietChecks},
+    hashtable::{
+        entry::{TranspositionEntry, TranspositionFlag},
+        TranspositionTable,
+    },
 };
 
 use super::{
-    communication::{Info, SearchSender},
-    should_stop_search,
+    communication::SearchSender,
+    send_stats, should_stop_search,
     sort::{pick_next_move, score_moves},
-    SearchInfo, SearchStats, StopReason, CHECKMATE_MIN, CHECK_TERMINATION, SEND_STATS,
+    SearchInfo, SearchStats, StopReason, CHECKMATE, CHECKMATE_MIN, CHECK_TERMINATION, MAX_DEPTH,
+    MIN_EVAL,
 };
 
+#[cfg(feature = "trace")]
+use super::trace::{Cutoff, NodeKind, Pruning};
+
+// The default delta margin, the search uses the one of the evaluation parameters.
 pub const QUEEN_VALUE: i32 = 1000;
 
-// By using quiescence search, we can avoid the horizon effect.
-// This describes the situation where the search horizon is reached
-// and the evaluation states that the position is equal or better,
-// even if the position is actually worse.
-//
-// For that purpose we just evaluate all the captures to reach a
-// quiet position. At this point we can evaluate the position and be
-// sure that the evaluation is accurate enough.
-//
+// The depths of the quiescence search in the table. Searching the quiet checks
+// as well is deeper, so its entries can't be replaced by the following plies.
+pub(crate) const QUIET_CHECKS_DEPTH: i8 = 0;
+pub(crate) const QUIESCENCE_DEPTH: i8 = -1;
+
 /// By using quiescence search, we can avoid the horizon effect.
 /// This describes the situation where the search horizon is reached
 /// and the evaluation states that the position is equal or better,
@@ -33,25 +37,104 @@ pub const QUEEN_VALUE: i32 = 1000;
 /// quiet position. At this point we can evaluate the position and be
 /// sure that the evaluation is accurate enough.
 ///
+/// If the side to move is in check, standing pat is not an option. Thus
+/// all the evasions are searched, which also allows us to detect mates.
+/// With `quiet_checks` the quiet moves giving check are searched as well,
+/// which should only be done at the first ply of the quiescence search.
+///
 /// Source: https://www.chessprogramming.org/Quiescence_Search
 pub(crate) fn quiescence<S: SearchSender>(
     cache: &TranspositionTable,
     info: &mut SearchInfo<S>,
     stats: &mut SearchStats,
-    mut alpha: i32,
+    alpha: i32,
     beta: i32,
+    quiet_checks: bool,
+) -> Result<i32, StopReason> {
+    trace!(info, enter(NodeKind::Quiescence, stats, alpha, beta));
+    let result = quiescence_node(cache, info, stats, alpha, beta, quiet_checks);
+    trace!(info, exit(&result));
+
+    result
+}
+
+#[inline(always)]
+fn quiescence_node<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    stats: &mut SearchStats,
+    mut alpha: i32,
+    mut beta: i32,
+    quiet_checks: bool,
 ) -> Result<i32, StopReason> {
     stats.nodes += 1;
     stats.quiescence_nodes += 1;
 
     if stats.nodes & CHECK_TERMINATION == 0 {
         should_stop_search(info, stats)?;
+        send_stats(cache, info)?;
     }
 
-    let standing_pat = evaluate(&info.board, info.board.active());
+    let in_check = info.board.is_check();
+
+    // We can't go deeper than this, as all the tables are indexed by the ply.
+    if stats.ply() >= MAX_DEPTH - 1 {
+        return Ok(evaluate_cached(
+            &info.board,
+            info.board.active(),
+            &info.parameters,
+            &mut info.pawn_table,
+        ));
+    }
+
+    let depth = match quiet_checks {
+        true => QUIET_CHECKS_DEPTH,
+        false => QUIESCENCE_DEPTH,
+    };
+
+    let mut hash_move = None;
+    if let Some(entry) = cache.probe(info.board.hash()) {
+        hash_move = entry.best_move();
+        trace!(info, tt_hit(entry.flag()));
+
+        // An entry without the quiet checks can't be trusted by a search
+        // that has to look at them.
+        if entry.depth() >= depth {
+            let eval = entry.eval();
+            match entry.flag() {
+                TranspositionFlag::Exact => {
+                    trace!(info, cutoff(Cutoff::Transposition));
+                    return Ok(eval);
+                }
+                TranspositionFlag::LowerBound => alpha = alpha.max(eval),
+                TranspositionFlag::UpperBound => beta = beta.min(eval),
+            }
+
+            if alpha >= beta {
+                trace!(info, cutoff(Cutoff::Transposition));
+                return Ok(eval);
+            }
+        }
+    }
+
+    let original_alpha = alpha;
+
+    // While being in check, the side to move is not allowed to just stand
+    // pat, as the position could be lost.
+    let standing_pat = if in_check {
+        MIN_EVAL
+    } else {
+        evaluate_cached(
+            &info.board,
+            info.board.active(),
+            &info.parameters,
+            &mut info.pawn_table,
+        )
+    };
 
     // If the evaluation exceeds the upper bound we just fail hard.
     if standing_pat >= beta {
+        trace!(info, cutoff(Cutoff::StandPat));
         return Ok(beta);
     }
 
@@ -60,50 +143,52 @@ pub(crate) fn quiescence<S: SearchSender>(
         alpha = standing_pat;
     }
 
-    let move_generator = MoveGenerator::<CaptureMoves>::new(&info.board);
-
     // ~~~~~~~~~ MOVE ORDERING ~~~~~~~~~
     // Used to improve the efficiency of the alpha-beta algorithm.
     // Source: https://www.chessprogramming.org/Move_Ordering
-    let moves = move_generator.collect::<Vec<Move>>();
-    let mut scored_moves = score_moves(info, stats, moves, None);
-    // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+    let moves = if in_check {
+        let move_generator = MoveGenerator::<AllMoves>::new(&info.board);
+        if move_generator.is_checkmate(&info.board) {
+            trace!(info, cutoff(Cutoff::Checkmate));
+            return Ok(-CHECKMATE + stats.ply() as i32);
+        }
 
-    // TODO: Make this better
-    if stats.nodes & SEND_STATS == 0 {
-        let elapsed = stats.start_time.elapsed();
-        let nps = (stats.nodes as f64 / elapsed.as_secs_f64()) as u64;
-        // TODO: Remove the unwrap
-        info.sender
-            .send(
-                Info::new()
-                    .time(elapsed.as_millis())
-                    .nodes(stats.nodes)
-                    .hashfull(cache.full_percentage())
-                    .nps(nps)
-                    .build(),
-            )
-            .unwrap();
-    }
+        move_generator.collect::<Vec<Move>>()
+    } else {
+        let mut moves = MoveGenerator::<CaptureMoves>::new(&info.board).collect::<Vec<Move>>();
+        if quiet_checks {
+            moves.extend(MoveGenerator::<QuietChecks>::new(&info.board));
+        }
 
+        moves
+    };
+    let mut scored_moves = score_moves(info, stats, moves, hash_move);
+    // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+    let mut best_move = None;
     for move_index in 0..scored_moves.len() {
         let next_move = pick_next_move(move_index, &mut scored_moves);
 
-        info.board.make(next_move);
-
-        if is_futile(info, next_move, standing_pat, alpha, beta) {
-            info.board.unmake(next_move);
+        let gives_check = info.board.gives_check(next_move);
+        if is_futile(info, next_move, gives_check, standing_pat, alpha, beta) {
+            trace!(info, skip(next_move, Pruning::Delta));
             continue;
         }
 
+        info.board.make(next_move);
+        trace!(info, child(Some(next_move), None));
+
         stats.increase_ply();
-        let result = quiescence(cache, info, stats, -beta, -alpha);
+        let result = quiescence(cache, info, stats, -beta, -alpha, false);
         stats.decrease_ply();
 
         info.board.unmake(next_move);
 
         let child_eval = -result?;
-        alpha = alpha.max(child_eval);
+        if child_eval > alpha {
+            alpha = child_eval;
+            best_move = Some(next_move);
+        }
 
         // If alpha is greater or equal to beta, we need to make
         // a beta cut-off. All other moves will be worse than the
@@ -117,10 +202,26 @@ pub(crate) fn quiescence<S: SearchSender>(
                 info.killers.store(&next_move, stats.ply());
             }
 
+            cache.store(
+                info.board.hash(),
+                TranspositionEntry::new(depth, TranspositionFlag::LowerBound, beta, best_move),
+            );
+
+            trace!(info, cutoff(Cutoff::Beta));
             return Ok(beta);
         }
     }
 
+    let flag = if alpha > original_alpha {
+        TranspositionFlag::Exact
+    } else {
+        TranspositionFlag::UpperBound
+    };
+    cache.store(
+        info.board.hash(),
+        TranspositionEntry::new(depth, flag, alpha, best_move),
+    );
+
     Ok(alpha)
 }
 
@@ -128,12 +229,14 @@ pub(crate) fn quiescence<S: SearchSender>(
 fn is_futile<S: SearchSender>(
     info: &SearchInfo<S>,
     mov: Move,
+    gives_check: bool,
     eval: i32,
     alpha: i32,
     beta: i32,
 ) -> bool {
-    // TODO: Check if this move is checking the opponent
-    if info.board.is_check() || mov.is_castling() || mov.is_promotion() {
+    // Evasions are never futile, as we would get mated otherwise. The same
+    // goes for moves giving check, as they could lead to a mate.
+    if info.board.is_check() || gives_check || mov.is_castling() || mov.is_promotion() {
         return false;
     }
 
@@ -147,6 +250,6 @@ fn is_futile<S: SearchSender>(
         .expect("There should be a piece.");
     let eval_increase = captured_piece.get_estimate_value();
 
-    let delta_value = QUEEN_VALUE;
+    let delta_value = info.parameters.delta_margin;
     eval + eval_increase + delta_value < alpha
 }
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use base::r#move::Move;
+
+use super::communication::Score;
+
+/// The outcome of a whole search, combined over all search threads.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best_move: Move,
+    /// The expected reply of the opponent, which is the second move of the
+    /// principal variation.
+    pub ponder_move: Option<Move>,
+    /// There is no score if the move was taken from the opening book or
+    /// the search was stopped before the first depth was completed.
+    pub score: Option<Score>,
+    pub depth: u8,
+    pub seldepth: u8,
+    pub pv: Vec<Move>,
+    pub nodes: usize,
+    pub time: Duration,
+    /// The share of the transposition table probes during this search that
+    /// found an entry, between 0 and 1.
+    pub tt_hit_rate: f64,
+    /// The positions that were found in the tablebases by all threads.
+    pub tb_hits: usize,
+    /// The root moves of the thread whose move was chosen, starting with the
+    /// best move and followed by the moves that needed the most nodes.
+    pub root_moves: Vec<RootMove>,
+}
+
+/// The statistics of a single move at the root. The score is the one of the
+/// last time the move was searched, which is only a bound for most moves,
+/// as they just have to be proven worse than the best move.
+#[derive(Debug, Clone, Copy)]
+pub struct RootMove {
+    pub mov: Move,
+    pub score: Score,
+    /// The nodes that were searched below this move over all depths.
+    pub nodes: usize,
+}
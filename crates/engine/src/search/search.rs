@@ -1,6 +1,9 @@
 use std::{
+    cmp::Reverse,
+    num::NonZeroU8,
+    ops::{Deref, DerefMut},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     thread,
@@ -13,49 +16,96 @@ use base::{
     polyglot::{error::PolyglotError, parser::PolyglotBook},
     r#move::Move,
 };
-use crossbeam_channel::Sender;
+use thiserror::Error;
 
-use crate::hashtable::TranspositionTable;
+use crate::{
+    evaluation::{
+        parameters::Parameters,
+        pawns::{PawnTable, PAWN_TABLE_SIZE},
+    },
+    generator::{AllMoves, MoveGenerator},
+    hashtable::TranspositionTable,
+    tablebase::Tablebase,
+};
 
 use super::{
-    communication::{
-        BestMove, CrossbeamSearchSender, NullSearchSender, SearchCommand, SearchSender,
-    },
+    communication::{BestMove, Info, NullSearchSender, Score, ScoreBound, SearchSender},
     error::SearchError,
-    iterative::iterative_deepening,
+    iterative::{iterative_deepening, score_from_eval, ThreadResult},
     killers::Killers,
+    limits::{SearchLimits, SearchOptions},
+    mate::mate_search,
+    result::{RootMove, SearchResult},
+    skill::{skill_search, Skill},
 };
 
+#[cfg(feature = "trace")]
+use super::trace::Tracer;
+
 pub const MAX_DEPTH: u8 = 64;
+// The longest mate the mate search looks for, as all of its plies have to
+// fit into the maximum depth.
+pub const MAX_MATE_MOVES: u8 = MAX_DEPTH.div_ceil(2);
+// The size in megabytes of the private table of the mate search.
+pub(crate) const MATE_CACHE_SIZE: usize = 16;
 
 pub(crate) const CHECK_TERMINATION: usize = 0x7FF;
-pub(crate) const SEND_STATS: usize = 0x7FFFF;
+
+// The time in milliseconds between two reports of the search statistics.
+pub const STATS_INTERVAL: u128 = 1000;
+// The time in milliseconds after which the root moves are reported. Before
+// that, they would just flood the GUI.
+pub const CURRMOVE_DELAY: u128 = 3000;
 
 pub(crate) const CHECKMATE: i32 = 1_000_000;
 pub(crate) const CHECKMATE_MIN: i32 = CHECKMATE - MAX_DEPTH as i32;
 pub(crate) const DRAW: i32 = 0;
 
+// The score of a position that is won according to the tablebases. It is
+// above any evaluation, but below the mates that the search proves itself.
+pub(crate) const TB_WIN: i32 = 20_000;
+
 pub(crate) const MIN_EVAL: i32 = -CHECKMATE - 1;
+pub(crate) const MAX_EVAL: i32 = CHECKMATE + 1;
 
 pub(crate) const NULL_DEPTH_REDUCTION: u8 = 3;
+pub(crate) const NULL_MIN_DEPTH: u8 = 5;
+pub(crate) const NULL_VERIFICATION_DEPTH: u8 = 10;
+
+pub(crate) const IID_MIN_DEPTH: u8 = 6;
+pub(crate) const IID_DEPTH_REDUCTION: u8 = 2;
+pub(crate) const IIR_MIN_DEPTH: u8 = 4;
+
+// Helper threads skip some depths so they do not search the exact same tree
+// as the main thread. Threads with the same size are out of phase by one
+// depth each, so the depths are spread evenly across all helpers.
+// Source: https://www.chessprogramming.org/Lazy_SMP
+pub(crate) const SKIP_SIZE: [u8; 20] = [1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4];
+pub(crate) const SKIP_PHASE: [u8; 20] =
+    [0, 1, 0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 0, 1, 2, 3, 4, 5, 6, 7];
 
 pub(crate) type History = [[[usize; Board::SIZE]; Board::SIZE]; Color::COUNT];
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum StopReason {
+    #[error("the time for the search is up")]
     TimeUp,
+    #[error("the maximum amount of nodes was searched")]
     NodesExceeded,
+    #[error("the search was stopped")]
     ForcedStop,
 }
 
 #[derive(Debug)]
 pub struct SearchStats {
     pub(crate) nodes: usize,
+    flushed_nodes: usize,
     pub(crate) quiescence_nodes: usize,
     pub(crate) start_time: Instant,
     depth: u8,
     ply: u8,
     pub(crate) max_ply: u8,
+    research: bool,
 }
 
 impl SearchStats {
@@ -64,14 +114,24 @@ impl SearchStats {
 
         Self {
             nodes: 0,
+            flushed_nodes: 0,
             quiescence_nodes: 0,
             depth,
             ply: 0,
             max_ply: 0,
+            research: false,
             start_time: Instant::now(),
         }
     }
 
+    /// Adds the nodes searched since the last flush to the counter that is
+    /// shared between all search threads.
+    pub(crate) fn flush_nodes(&mut self, shared: &AtomicUsize) {
+        let nodes = self.nodes.saturating_sub(self.flushed_nodes);
+        shared.fetch_add(nodes, Ordering::Relaxed);
+        self.flushed_nodes = self.flushed_nodes.max(self.nodes);
+    }
+
     pub fn make_search(&mut self, reduction: u8) {
         self.increase_ply();
         self.decrease_depth(reduction);
@@ -122,51 +182,234 @@ impl SearchStats {
     pub const fn ply(&self) -> u8 {
         self.ply
     }
+
+    /// Marks the following search of the current node as an internal
+    /// re-search and returns the previous state for [`Self::end_research`].
+    pub fn begin_research(&mut self) -> bool {
+        std::mem::replace(&mut self.research, true)
+    }
+
+    pub fn end_research(&mut self, previous: bool) {
+        self.research = previous;
+    }
+
+    /// Returns true if the current node is the root of the search and not an
+    /// internal re-search of it, so the root moves are only reported once.
+    #[inline(always)]
+    pub const fn is_root(&self) -> bool {
+        self.ply == 0 && !self.research
+    }
+}
+
+/// Reduces the depth of the stats until it is dropped, so the depth is also
+/// restored if the search returns early because it was stopped.
+pub(crate) struct DepthReduction<'a> {
+    stats: &'a mut SearchStats,
+    reduction: u8,
+}
+
+impl<'a> DepthReduction<'a> {
+    pub(crate) fn new(stats: &'a mut SearchStats, reduction: u8) -> Self {
+        stats.decrease_depth(reduction);
+        Self { stats, reduction }
+    }
+}
+
+impl Deref for DepthReduction<'_> {
+    type Target = SearchStats;
+
+    fn deref(&self) -> &Self::Target {
+        self.stats
+    }
+}
+
+impl DerefMut for DepthReduction<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.stats
+    }
+}
+
+impl Drop for DepthReduction<'_> {
+    fn drop(&mut self) {
+        self.stats.increase_depth(self.reduction);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SearchInfo<S: SearchSender> {
     pub(crate) board: Board,
+    pub(crate) root_color: Color,
+    pub(crate) contempt: i32,
     pub(crate) sender: S,
     pub(crate) running: Arc<AtomicBool>,
     pub(crate) time_frame: TimeFrame,
-    pub(crate) accumulated_nodes: usize,
+    pub(crate) thread: usize,
+    pub(crate) nodes: Arc<AtomicUsize>,
     pub(crate) max_nodes: Option<usize>,
     pub(crate) max_depth: u8,
-    // TODO: Use the given moves
-    pub(crate) _moves: Vec<Move>,
+    /// The root moves that are searched, all of them if it is empty.
+    pub(crate) moves: Vec<Move>,
+    /// The index of the line that is searched, if several best lines are
+    /// searched one after the other.
+    pub(crate) multipv: Option<NonZeroU8>,
     pub(crate) infinite: bool,
+    pub(crate) stats_interval: u128,
+    pub(crate) currmove_delay: u128,
+    last_stats: u128,
+    // The move ordering tables are owned by each thread, which lets the
+    // helper threads diverge from the main thread in the order they search.
     pub(crate) killers: Killers,
     pub(crate) mate_killers: Killers,
-    pub(crate) history: History,
+    // Boxed, as the table is too large to be moved around with the info.
+    pub(crate) history: Box<History>,
+    pub(crate) root_moves: Vec<RootMove>,
+    pub(crate) pawn_table: PawnTable,
+    pub(crate) parameters: Arc<Parameters>,
+    pub(crate) tablebase: Option<Arc<Tablebase>>,
+    pub(crate) tb_hits: Arc<AtomicUsize>,
+    #[cfg(feature = "trace")]
+    pub(crate) tracer: Tracer,
 }
 
 impl<S: SearchSender> SearchInfo<S> {
-    pub fn new(
-        board: Board,
-        sender: S,
-        running: Arc<AtomicBool>,
-        time_frame: TimeFrame,
-        max_nodes: Option<usize>,
-        max_depth: Option<u8>,
-        moves: Vec<Move>,
-        infinite: bool,
-    ) -> Self {
+    /// Creates the state of a search thread. The limits and the shared
+    /// counters are set with the builder methods below.
+    pub fn new(board: Board, sender: S, running: Arc<AtomicBool>, time_frame: TimeFrame) -> Self {
         SearchInfo {
+            root_color: board.active(),
+            contempt: 0,
             board,
             sender,
             running,
             time_frame,
-            accumulated_nodes: 0,
-            max_nodes,
-            max_depth: max_depth.unwrap_or(MAX_DEPTH),
-            _moves: moves,
-            infinite,
+            thread: 0,
+            nodes: Arc::default(),
+            max_nodes: None,
+            max_depth: MAX_DEPTH,
+            moves: Vec::new(),
+            multipv: None,
+            infinite: false,
+            stats_interval: STATS_INTERVAL,
+            currmove_delay: CURRMOVE_DELAY,
+            last_stats: 0,
             killers: Killers::default(),
             mate_killers: Killers::default(),
-            history: [[[0; Board::SIZE]; Board::SIZE]; Color::COUNT],
+            history: Box::new([[[0; Board::SIZE]; Board::SIZE]; Color::COUNT]),
+            root_moves: Vec::new(),
+            pawn_table: PawnTable::size(PAWN_TABLE_SIZE),
+            parameters: Arc::default(),
+            tablebase: None,
+            tb_hits: Arc::default(),
+            #[cfg(feature = "trace")]
+            tracer: Tracer::default(),
+        }
+    }
+
+    pub fn thread(mut self, thread: usize) -> Self {
+        self.thread = thread;
+        self
+    }
+
+    /// The node counter that is shared between all search threads.
+    pub fn nodes(mut self, nodes: Arc<AtomicUsize>) -> Self {
+        self.nodes = nodes;
+        self
+    }
+
+    pub fn max_nodes(mut self, max_nodes: Option<usize>) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<u8>) -> Self {
+        self.max_depth = max_depth.unwrap_or(MAX_DEPTH);
+        self
+    }
+
+    pub fn moves(mut self, moves: Vec<Move>) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    pub fn infinite(mut self, infinite: bool) -> Self {
+        self.infinite = infinite;
+        self
+    }
+
+    pub fn contempt(mut self, contempt: i32) -> Self {
+        self.contempt = contempt;
+        self
+    }
+
+    pub fn currmove_delay(mut self, currmove_delay: u128) -> Self {
+        self.currmove_delay = currmove_delay;
+        self
+    }
+
+    pub fn parameters(mut self, parameters: Arc<Parameters>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// The tablebases with the counter of their hits that is shared between
+    /// all search threads.
+    pub fn tablebase(
+        mut self,
+        tablebase: Option<Arc<Tablebase>>,
+        tb_hits: Arc<AtomicUsize>,
+    ) -> Self {
+        self.tablebase = tablebase;
+        self.tb_hits = tb_hits;
+        self
+    }
+
+    /// The score of a draw from the view of the side to move. With a
+    /// positive contempt, the side to move at the root considers a draw
+    /// worse than equal and tries to avoid it, while the opponent is
+    /// assumed to happily accept it.
+    /// Source: https://www.chessprogramming.org/Contempt_Factor
+    pub fn draw_score(&self) -> i32 {
+        if self.board.active() == self.root_color {
+            DRAW - self.contempt
+        } else {
+            DRAW + self.contempt
+        }
+    }
+
+    /// Updates the statistics of a root move after it was searched. The
+    /// nodes are added up over all depths, while the score is replaced.
+    pub(crate) fn update_root_move(&mut self, mov: Move, score: Score, nodes: usize) {
+        match self
+            .root_moves
+            .iter_mut()
+            .find(|root_move| root_move.mov == mov)
+        {
+            Some(root_move) => {
+                root_move.score = score;
+                root_move.nodes += nodes;
+            }
+            None => self.root_moves.push(RootMove { mov, score, nodes }),
         }
     }
+
+    #[inline(always)]
+    pub const fn is_main_thread(&self) -> bool {
+        self.thread == 0
+    }
+
+    /// Returns true if this thread should skip the given depth. The main
+    /// thread searches every depth, while the helpers are staggered.
+    pub fn should_skip_depth(&self, depth: u8) -> bool {
+        if self.is_main_thread() {
+            return false;
+        }
+
+        let index = (self.thread - 1) % SKIP_SIZE.len();
+        let size = SKIP_SIZE[index];
+        let phase = SKIP_PHASE[index];
+
+        ((depth + phase) / size) % 2 == 1
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -198,25 +441,40 @@ impl TimeFrame {
     }
 }
 
-pub fn search(
+/// Searches the best move of the board within the given limits. The search
+/// runs as long as `running` is set, so it can be stopped from another
+/// thread. All the info and the best move are sent to the sender, while
+/// the helper threads stay silent.
+pub fn search<S: SearchSender + Clone>(
     board: Board,
     book: Option<&PolyglotBook>,
     cache: Arc<TranspositionTable>,
-    sender: Sender<SearchCommand>,
+    sender: S,
     running: Arc<AtomicBool>,
-    time_frame: TimeFrame,
-    max_nodes: Option<usize>,
-    max_depth: Option<u8>,
-    moves: Vec<Move>,
-    infinite: bool,
-    max_threads: usize,
-) -> Result<(), SearchError> {
+    limits: SearchLimits,
+    options: SearchOptions,
+) -> Result<SearchResult, SearchError> {
+    let time_frame = limits.time_frame();
+    let infinite = limits.is_infinite();
+
     if !infinite {
         if let Some(book) = book {
             match book.get_random_move(&board) {
                 Ok(mov) => {
-                    sender.send(BestMove::new(mov))?;
-                    return Ok(());
+                    sender.send(BestMove::new(mov, None))?;
+                    return Ok(SearchResult {
+                        best_move: mov,
+                        ponder_move: None,
+                        score: None,
+                        depth: 0,
+                        seldepth: 0,
+                        pv: vec![mov],
+                        nodes: 0,
+                        time: time_frame.elapsed(),
+                        tt_hit_rate: 0.0,
+                        tb_hits: 0,
+                        root_moves: Vec::new(),
+                    });
                 }
                 Err(PolyglotError::NoEntries(_)) => {}
                 Err(err) => return Err(err.into()),
@@ -224,63 +482,336 @@ pub fn search(
         }
     }
 
-    running.store(true, Ordering::Relaxed);
     cache.increment_age();
+    let probes = CacheProbes::new(&cache);
+
+    let nodes = Arc::new(AtomicUsize::new(0));
+    let tb_hits = Arc::new(AtomicUsize::new(0));
+
+    let mut info = SearchInfo::new(
+        board.clone(),
+        sender.clone(),
+        running.clone(),
+        time_frame.clone(),
+    )
+    .nodes(nodes.clone())
+    .max_nodes(limits.nodes)
+    .max_depth(limits.depth)
+    .moves(limits.moves.clone())
+    .infinite(infinite)
+    .contempt(options.contempt)
+    .currmove_delay(options.currmove_delay)
+    .parameters(options.parameters.clone())
+    .tablebase(options.tablebase.clone(), tb_hits.clone());
+
+    // Searching for a mate is done by a dedicated search on a single thread,
+    // as it has to prove the mate and not just find a good move. It has its
+    // own table, as the entries of the regular search are no proofs.
+    if let Some(mate) = limits.mate {
+        let mate_cache = TranspositionTable::size(MATE_CACHE_SIZE);
+        let mate_probes = CacheProbes::new(&mate_cache);
+        let result = mate_search(&mate_cache, info, mate);
+        running.store(false, Ordering::Relaxed);
+
+        let result = search_result(
+            result?,
+            &mate_cache,
+            &mate_probes,
+            &nodes,
+            &tb_hits,
+            &time_frame,
+        );
+        sender.send(BestMove::new(result.best_move, result.ponder_move))?;
+        return Ok(result);
+    }
+
+    // A limited strength is only played by a single thread, as the helper
+    // threads would just make the engine stronger again.
+    if let Some(skill) = options.skill.filter(Skill::is_enabled) {
+        let result = skill_search(&cache, &mut info, skill);
+        running.store(false, Ordering::Relaxed);
 
-    let mut workers = Vec::with_capacity(max_threads);
-    for index in (0..max_threads).rev() {
+        let result = search_result(result?, &cache, &probes, &nodes, &tb_hits, &time_frame);
+        sender.send(BestMove::new(result.best_move, result.ponder_move))?;
+        return Ok(result);
+    }
+
+    // If the root is covered by the tablebases, only the moves that keep the
+    // best result are searched. The search then just has to find the fastest
+    // way to convert it.
+    if let Some(tablebase) = &options.tablebase {
+        if let Some(moves) = tablebase_root_moves(tablebase, &mut info.board, &limits.moves) {
+            info.moves = moves;
+        }
+    }
+
+    let threads = options.threads.max(1);
+    let mut workers = Vec::with_capacity(threads - 1);
+    for index in 1..threads {
         let cache = cache.clone();
+        let mut info = SearchInfo::new(
+            board.clone(),
+            NullSearchSender,
+            running.clone(),
+            time_frame.clone(),
+        )
+        .thread(index)
+        .nodes(nodes.clone())
+        .max_nodes(limits.nodes)
+        .max_depth(limits.depth)
+        .moves(info.moves.clone())
+        .infinite(infinite)
+        .contempt(options.contempt)
+        .parameters(options.parameters.clone())
+        .tablebase(options.tablebase.clone(), tb_hits.clone());
+
+        workers.push(thread::spawn(move || {
+            iterative_deepening(&cache, &mut info)
+        }));
+    }
 
-        let handle = if index == 0 {
-            let info = SearchInfo::new(
-                board.clone(),
-                CrossbeamSearchSender::new(sender.clone()),
-                running.clone(),
-                time_frame.clone(),
-                max_nodes,
-                max_depth,
-                moves.clone(),
-                infinite,
-            );
-
-            thread::spawn(move || iterative_deepening(&cache, info))
-        } else {
-            let info = SearchInfo::new(
-                board.clone(),
-                NullSearchSender,
-                running.clone(),
-                time_frame.clone(),
-                max_nodes,
-                max_depth,
-                moves.clone(),
-                infinite,
-            );
+    // The main thread searches on the calling thread. As soon as it is done,
+    // the helper threads are stopped as well.
+    let main_result = iterative_deepening(&cache, &mut info);
+    running.store(false, Ordering::Relaxed);
 
-            thread::spawn(move || iterative_deepening(&cache, info))
-        };
+    // A helper thread that failed or panicked just loses its vote, the
+    // remaining threads still decide on the best move.
+    let helper_results = workers
+        .into_iter()
+        .filter_map(|worker| worker.join().ok()?.ok())
+        .collect::<Vec<ThreadResult>>();
+
+    let mut results = vec![main_result?];
+    results.extend(helper_results);
+
+    let best_index = select_best_thread(&results);
+    let best_result = results.swap_remove(best_index);
+
+    // If a helper thread won the vote, its line is reported so that the
+    // best move matches the last principal variation.
+    if best_index != 0 {
+        sender.send(
+            Info::new()
+                .depth(best_result.depth)
+                .score(score_from_eval(best_result.eval, ScoreBound::Exact))
+                .nodes(nodes.load(Ordering::Relaxed))
+                .pv(best_result.pv.clone())
+                .build(),
+        )?;
+    }
+
+    let result = search_result(best_result, &cache, &probes, &nodes, &tb_hits, &time_frame);
+    sender.send(BestMove::new(result.best_move, result.ponder_move))?;
+
+    Ok(result)
+}
 
-        workers.push(handle);
+/// The probe counters of the transposition table at the start of a search,
+/// so that the hit rate only covers the probes of this search.
+struct CacheProbes {
+    hits: usize,
+    probes: usize,
+}
+
+impl CacheProbes {
+    fn new(cache: &TranspositionTable) -> Self {
+        Self {
+            hits: cache.hits(),
+            probes: cache.probes(),
+        }
     }
 
-    let first_worker = workers.remove(0);
-    let best_move = first_worker.join().unwrap()?;
+    fn hit_rate(&self, cache: &TranspositionTable) -> f64 {
+        let hits = cache.hits().saturating_sub(self.hits);
+        let probes = cache.probes().saturating_sub(self.probes);
 
-    for worker in workers {
-        worker.join().unwrap()?;
+        match probes {
+            0 => 0.0,
+            _ => hits as f64 / probes as f64,
+        }
     }
+}
 
-    running.store(false, Ordering::Relaxed);
-    sender.send(BestMove::new(best_move))?;
+fn search_result(
+    result: ThreadResult,
+    cache: &TranspositionTable,
+    probes: &CacheProbes,
+    nodes: &AtomicUsize,
+    tb_hits: &AtomicUsize,
+    time_frame: &TimeFrame,
+) -> SearchResult {
+    let score = match result.depth {
+        0 => None,
+        _ => Some(score_from_eval(result.eval, ScoreBound::Exact)),
+    };
+
+    // The best move comes first, the others are sorted by the effort it took
+    // to refute them.
+    let mut root_moves = result.root_moves;
+    root_moves
+        .sort_by_key(|root_move| (root_move.mov != result.best_move, Reverse(root_move.nodes)));
+
+    SearchResult {
+        best_move: result.best_move,
+        ponder_move: result.pv.get(1).copied(),
+        score,
+        depth: result.depth,
+        seldepth: result.seldepth,
+        pv: result.pv,
+        nodes: nodes.load(Ordering::Relaxed),
+        time: time_frame.elapsed(),
+        tt_hit_rate: probes.hit_rate(cache),
+        tb_hits: tb_hits.load(Ordering::Relaxed),
+        root_moves,
+    }
+}
 
-    Ok(())
+/// Filters the root moves by the tablebases. Returns nothing if the root is
+/// not covered by them, so the search falls back to all the given moves.
+fn tablebase_root_moves(
+    tablebase: &Tablebase,
+    board: &mut Board,
+    moves: &[Move],
+) -> Option<Vec<Move>> {
+    if !tablebase.can_probe(board) {
+        return None;
+    }
+
+    let moves = match moves.is_empty() {
+        true => MoveGenerator::<AllMoves>::new(board).collect::<Vec<Move>>(),
+        false => moves.to_vec(),
+    };
+
+    tablebase
+        .best_root_moves(board, &moves)
+        .ok()
+        .filter(|moves| !moves.is_empty())
+}
+
+/// Selects the thread whose best move should be played. Every thread votes
+/// for its best move, weighted by the completed depth and how much better
+/// its evaluation is compared to the worst thread. Proven mates always
+/// win the vote, preferring the shortest one.
+/// Source: https://www.chessprogramming.org/Lazy_SMP
+pub(crate) fn select_best_thread(results: &[ThreadResult]) -> usize {
+    let min_eval = results
+        .iter()
+        .map(|result| result.eval)
+        .min()
+        .unwrap_or_default();
+
+    let mut votes: Vec<(Move, i64)> = Vec::with_capacity(results.len());
+    for result in results {
+        let weight = (result.eval - min_eval + 14) as i64 * result.depth as i64;
+
+        match votes.iter_mut().find(|(mov, _)| *mov == result.best_move) {
+            Some((_, votes)) => *votes += weight,
+            None => votes.push((result.best_move, weight)),
+        }
+    }
+
+    let votes_for = |mov: Move| {
+        votes
+            .iter()
+            .find(|(other, _)| *other == mov)
+            .map(|(_, votes)| *votes)
+            .unwrap_or_default()
+    };
+
+    let mut best_index = 0;
+    for (index, result) in results.iter().enumerate().skip(1) {
+        let best = &results[best_index];
+        // Threads that did not complete a single depth have no opinion.
+        if result.depth == 0 {
+            continue;
+        }
+
+        if best.eval >= CHECKMATE_MIN {
+            // Prefer the shortest mate that was found.
+            if result.eval > best.eval {
+                best_index = index;
+            }
+        } else if result.eval >= CHECKMATE_MIN
+            || votes_for(result.best_move) > votes_for(best.best_move)
+        {
+            best_index = index;
+        }
+    }
+
+    best_index
+}
+
+/// Reports the statistics of all threads combined, every time the stats
+/// interval has passed. Only the main thread reports them.
+pub(crate) fn send_stats<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+) -> Result<(), StopReason> {
+    if !info.is_main_thread() {
+        return Ok(());
+    }
+
+    let elapsed = info.time_frame.elapsed();
+    if elapsed.as_millis() < info.last_stats + info.stats_interval {
+        return Ok(());
+    }
+
+    info.last_stats = elapsed.as_millis();
+
+    let nodes = info.nodes.load(Ordering::Relaxed);
+    let nodes_per_second = (nodes as f64 / elapsed.as_secs_f64()) as u64;
+
+    let mut stats = Info::new()
+        .time(elapsed.as_millis())
+        .nodes(nodes)
+        .hashfull(cache.full_percentage())
+        .nps(nodes_per_second);
+    if info.tablebase.is_some() {
+        stats = stats.tbhits(info.tb_hits.load(Ordering::Relaxed));
+    }
+
+    // If nobody is listening anymore, there is no reason to keep searching.
+    info.sender
+        .send(stats.build())
+        .map_err(|_| StopReason::ForcedStop)
+}
+
+/// Reports the root move that is currently searched, once the search took
+/// longer than the currmove delay. Only the main thread reports them.
+pub(crate) fn send_currmove<S: SearchSender>(
+    info: &SearchInfo<S>,
+    mov: Move,
+    move_index: usize,
+) -> Result<(), StopReason> {
+    if !info.is_main_thread() {
+        return Ok(());
+    }
+
+    if info.time_frame.elapsed().as_millis() < info.currmove_delay {
+        return Ok(());
+    }
+
+    let move_number = NonZeroU8::new((move_index + 1) as u8).unwrap();
+
+    info.sender
+        .send(
+            Info::new()
+                .currmove(mov)
+                .currmovenumber(move_number)
+                .build(),
+        )
+        .map_err(|_| StopReason::ForcedStop)
 }
 
 pub fn should_stop_search<S: SearchSender>(
     info: &SearchInfo<S>,
-    stats: &SearchStats,
+    stats: &mut SearchStats,
 ) -> Result<(), StopReason> {
+    stats.flush_nodes(&info.nodes);
+
     if let Some(max_nodes) = info.max_nodes {
-        let nodes = info.accumulated_nodes + stats.nodes;
+        let nodes = info.nodes.load(Ordering::Relaxed);
         if nodes >= max_nodes {
             return Err(StopReason::NodesExceeded);
         }
@@ -0,0 +1,178 @@
+use std::{cmp::Reverse, num::NonZeroU8, sync::atomic::Ordering};
+
+use base::r#move::Move;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    generator::{AllMoves, MoveGenerator},
+    hashtable::TranspositionTable,
+};
+
+use super::{
+    communication::SearchSender,
+    error::SearchError,
+    iterative::{iterative_deepening, ThreadResult},
+    SearchInfo, MIN_EVAL,
+};
+
+pub const MIN_SKILL_LEVEL: u8 = 0;
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+// The range of the UCI_Elo option. The elo is mapped linearly onto the
+// skill levels and was never calibrated against rated opponents, so these
+// are just rough guesses of the weakest and strongest limited play.
+pub const MIN_ELO: u16 = 1000;
+pub const MAX_ELO: u16 = 2500;
+
+// The amount of best root moves that are considered when picking a move.
+pub const SKILL_MULTI_PV: usize = 4;
+// The nodes that are searched at the lowest skill level. Every two levels
+// this amount is doubled.
+pub const SKILL_BASE_NODES: usize = 1_000;
+// The maximum evaluation difference that is added as random noise.
+pub const SKILL_MAX_DELTA: i32 = 100;
+
+/// Weakens the play of the engine. A lower level searches shallower and
+/// picks a worse root move more often. The seed makes the choice of the
+/// move reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skill {
+    level: u8,
+    seed: u64,
+}
+
+impl Skill {
+    pub fn new(level: u8, seed: u64) -> Self {
+        let level = level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL);
+        Self { level, seed }
+    }
+
+    /// Maps the elo linearly onto the skill levels. The mapping is not
+    /// calibrated, so the resulting strength only roughly follows the elo.
+    pub fn from_elo(elo: u16, seed: u64) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+
+        let range = (MAX_ELO - MIN_ELO) as u32;
+        let level = (elo - MIN_ELO) as u32 * MAX_SKILL_LEVEL as u32 / range;
+
+        Self::new(level as u8, seed)
+    }
+
+    #[inline(always)]
+    pub const fn level(&self) -> u8 {
+        self.level
+    }
+
+    #[inline(always)]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The highest level plays with the full strength of the engine.
+    #[inline(always)]
+    pub const fn is_enabled(&self) -> bool {
+        self.level < MAX_SKILL_LEVEL
+    }
+
+    pub const fn max_depth(&self) -> u8 {
+        self.level + 1
+    }
+
+    pub const fn max_nodes(&self) -> usize {
+        SKILL_BASE_NODES << (self.level / 2)
+    }
+
+    /// Picks one of the best root moves, which need to be sorted from the
+    /// best to the worst. Each move gets a random bonus which is bigger the
+    /// lower the skill level is, so that weaker levels choose worse moves.
+    /// Source: https://github.com/official-stockfish/Stockfish/blob/master/src/search.cpp
+    pub fn pick_move(&self, root_moves: &[(Move, i32)]) -> Option<Move> {
+        let candidates = &root_moves[..root_moves.len().min(SKILL_MULTI_PV)];
+        let (top_move, top_eval) = *candidates.first()?;
+        let (_, last_eval) = *candidates.last()?;
+
+        // The highest level plays with the full strength of the engine.
+        if !self.is_enabled() {
+            return Some(top_move);
+        }
+
+        let mut rand = StdRng::seed_from_u64(self.seed);
+
+        let weakness = 120 - 2 * self.level as i32;
+        let delta = (top_eval - last_eval).min(SKILL_MAX_DELTA);
+
+        let mut best_move = None;
+        let mut best_eval = MIN_EVAL;
+        for (mov, eval) in candidates {
+            let noise = delta * rand.gen_range(0..weakness);
+            let push = (weakness * (top_eval - eval) + noise) / 128;
+
+            if eval + push >= best_eval {
+                best_eval = eval + push;
+                best_move = Some(*mov);
+            }
+        }
+
+        best_move
+    }
+}
+
+/// Searches the best lines of the position one after the other, like a
+/// MultiPV search, within the limits of the skill level. Every line excludes
+/// the best moves of the lines before it. Then one of the lines is picked
+/// with the skill noise.
+pub(crate) fn skill_search<S: SearchSender>(
+    cache: &TranspositionTable,
+    info: &mut SearchInfo<S>,
+    skill: Skill,
+) -> Result<ThreadResult, SearchError> {
+    info.max_depth = info.max_depth.min(skill.max_depth());
+    let max_nodes = match info.max_nodes {
+        Some(nodes) => nodes.min(skill.max_nodes()),
+        None => skill.max_nodes(),
+    };
+
+    // Every line gets the same share of the nodes, so the later lines are
+    // searched about as deep as the first one.
+    let line_nodes = (max_nodes / SKILL_MULTI_PV).max(1);
+
+    let mut moves = match info.moves.is_empty() {
+        true => MoveGenerator::<AllMoves>::new(&info.board).collect::<Vec<Move>>(),
+        false => info.moves.clone(),
+    };
+
+    let mut lines: Vec<ThreadResult> = Vec::with_capacity(SKILL_MULTI_PV);
+    while lines.len() < SKILL_MULTI_PV && !moves.is_empty() {
+        info.moves = moves.clone();
+        info.multipv = NonZeroU8::new(lines.len() as u8 + 1);
+        info.max_nodes = Some(info.nodes.load(Ordering::Relaxed) + line_nodes);
+
+        // A line that failed or did not complete a single depth leaves the
+        // choice to the lines that were already searched.
+        let line = match iterative_deepening(cache, info) {
+            Ok(line) if line.depth > 0 => line,
+            Ok(line) if lines.is_empty() => return Ok(line),
+            Err(error) if lines.is_empty() => return Err(error),
+            _ => break,
+        };
+
+        moves.retain(|mov| *mov != line.best_move);
+        lines.push(line);
+    }
+
+    lines.sort_by_key(|line| Reverse(line.eval));
+    let root_moves = lines
+        .iter()
+        .map(|line| (line.best_move, line.eval))
+        .collect::<Vec<(Move, i32)>>();
+
+    let index = skill
+        .pick_move(&root_moves)
+        .and_then(|mov| lines.iter().position(|line| line.best_move == mov))
+        .unwrap_or_default();
+
+    let mut result = lines.swap_remove(index);
+    result.root_moves = info.root_moves.clone();
+
+    Ok(result)
+}
@@ -0,0 +1,1189 @@
+#[cfg(test)]
+use std::sync::{atomic::AtomicBool, Arc};
+
+#[cfg(test)]
+use base::testing::board;
+
+#[cfg(test)]
+use super::{communication::SearchSender, SearchInfo, TimeFrame};
+
+/// The info of a search of the board without a time limit, which keeps
+/// running until it is done.
+#[cfg(test)]
+fn search_info<S: SearchSender>(fen: &str, sender: S) -> SearchInfo<S> {
+    SearchInfo::new(
+        board(fen),
+        sender,
+        Arc::new(AtomicBool::new(true)),
+        TimeFrame::new(u128::MAX),
+    )
+}
+
+#[cfg(test)]
+mod threads {
+    use base::{board::Board, r#move::Move};
+
+    use crate::search::{
+        communication::NullSearchSender, iterative::ThreadResult, select_best_thread, SearchInfo,
+        CHECKMATE,
+    };
+
+    use super::search_info;
+
+    fn thread_info(thread: usize) -> SearchInfo<NullSearchSender> {
+        search_info(Board::STARTPOS_FEN, NullSearchSender).thread(thread)
+    }
+
+    fn result(board: &Board, mov: &str, depth: u8, eval: i32) -> ThreadResult {
+        let best_move = Move::parse(board, mov).unwrap();
+        ThreadResult {
+            best_move,
+            depth,
+            seldepth: depth,
+            eval,
+            pv: vec![best_move],
+            root_moves: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn main_thread_searches_every_depth() {
+        let info = thread_info(0);
+        assert!((1..=20).all(|depth| !info.should_skip_depth(depth)));
+    }
+
+    #[test]
+    fn helpers_are_staggered() {
+        let first = thread_info(1);
+        let second = thread_info(2);
+
+        for depth in 1..=20 {
+            assert!(!first.should_skip_depth(depth) || !second.should_skip_depth(depth));
+        }
+
+        let skipped = (1..=20)
+            .filter(|depth| first.should_skip_depth(*depth))
+            .count();
+        assert_eq!(skipped, 10);
+    }
+
+    #[test]
+    fn vote_by_depth_and_score() {
+        let board = thread_info(0).board;
+
+        let results = [
+            result(&board, "e2e4", 8, 30),
+            result(&board, "d2d4", 9, 35),
+            result(&board, "d2d4", 8, 30),
+        ];
+        assert_eq!(select_best_thread(&results), 1);
+
+        let results = [
+            result(&board, "e2e4", 10, 30),
+            result(&board, "d2d4", 6, 20),
+        ];
+        assert_eq!(select_best_thread(&results), 0);
+    }
+
+    #[test]
+    fn vote_prefers_shortest_mate() {
+        let board = thread_info(0).board;
+
+        let results = [
+            result(&board, "e2e4", 12, 50),
+            result(&board, "d2d4", 9, CHECKMATE - 7),
+            result(&board, "g1f3", 7, CHECKMATE - 3),
+            result(&board, "c2c4", 0, 0),
+        ];
+        assert_eq!(select_best_thread(&results), 2);
+    }
+}
+
+#[cfg(test)]
+mod mate {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::{
+        hashtable::{entry::TranspositionFlag, TranspositionTable},
+        search::{
+            communication::NullSearchSender, iterative::ThreadResult, mate::mate_search, CHECKMATE,
+        },
+    };
+
+    use super::search_info;
+
+    fn search(fen: &str, moves: u8, search_moves: &[&str]) -> (TranspositionTable, ThreadResult) {
+        let info = search_info(fen, NullSearchSender);
+        let search_moves = search_moves
+            .iter()
+            .map(|mov| Move::parse(&info.board, *mov).unwrap())
+            .collect();
+        let info = info.moves(search_moves);
+
+        let cache = TranspositionTable::size(1);
+        let result = mate_search(&cache, info, moves).unwrap();
+        (cache, result)
+    }
+
+    fn search_mate(fen: &str, moves: u8) -> ThreadResult {
+        search(fen, moves, &[]).1
+    }
+
+    fn pv(result: &ThreadResult) -> Vec<String> {
+        result.pv.iter().map(|mov| mov.to_string()).collect()
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let result = search_mate(fen, 1);
+
+        assert_eq!(pv(&result), ["h5f7"]);
+        assert_eq!(result.eval, CHECKMATE - 1);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let fen = "1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+        let result = search_mate(fen, 3);
+
+        assert_eq!(pv(&result), ["d2d8", "b8d8", "d1d8"]);
+        assert_eq!(result.eval, CHECKMATE - 3);
+    }
+
+    #[test]
+    fn mate_refuted() {
+        let fen = "1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+        let result = search_mate(fen, 1);
+        assert!(result.pv.is_empty());
+        assert_eq!(result.depth, 0);
+
+        let result = search_mate(Board::STARTPOS_FEN, 2);
+        assert!(result.pv.is_empty());
+    }
+
+    #[test]
+    fn mate_length_is_limited() {
+        // Bare kings are a draw, so every mate length is refuted at once.
+        let result = search_mate("8/8/3k4/8/8/3K4/8/8 w - - 0 1", u8::MAX);
+        assert!(result.pv.is_empty());
+        assert_eq!(result.depth, 0);
+    }
+
+    #[test]
+    fn search_moves() {
+        let fen = "1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+
+        let (_, result) = search(fen, 3, &["g2g3", "d2d8"]);
+        assert_eq!(pv(&result), ["d2d8", "b8d8", "d1d8"]);
+
+        let (_, result) = search(fen, 3, &["g2g3"]);
+        assert!(result.pv.is_empty());
+        assert_eq!(result.best_move.to_string(), "g2g3");
+    }
+
+    #[test]
+    fn stores_proven_nodes() {
+        let fen = "1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+        let (cache, result) = search(fen, 3, &[]);
+
+        let mut board = board(fen);
+        board.make(result.pv[0]);
+
+        let entry = cache.probe(board.hash()).unwrap();
+        assert_eq!(entry.flag(), TranspositionFlag::Exact);
+        assert_eq!(entry.eval(), -CHECKMATE + 2);
+        assert_eq!(entry.best_move(), Some(result.pv[1]));
+    }
+
+    #[test]
+    fn reuses_proven_nodes() {
+        let fen = "1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+        let cache = TranspositionTable::size(1);
+
+        for _ in 0..2 {
+            let result = mate_search(&cache, search_info(fen, NullSearchSender), 2).unwrap();
+            assert_eq!(pv(&result), ["d2d8", "b8d8", "d1d8"]);
+            assert_eq!(result.eval, CHECKMATE - 3);
+        }
+    }
+
+    #[test]
+    fn reuses_transposed_nodes() {
+        // The forced recapture leads to the second position, so its mate is
+        // first proven two plies deeper than in its own search.
+        let fen = "r5k1/5ppp/8/8/8/8/3R1PPP/2RR2K1 w - - 0 1";
+        let (cache, result) = search(fen, 3, &["c1c8"]);
+        assert_eq!(pv(&result), ["c1c8", "a8c8", "d2d8", "c8d8", "d1d8"]);
+
+        let fen = "2r3k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1";
+        let result = mate_search(&cache, search_info(fen, NullSearchSender), 2).unwrap();
+        assert_eq!(pv(&result), ["d2d8", "c8d8", "d1d8"]);
+        assert_eq!(result.eval, CHECKMATE - 3);
+    }
+}
+
+#[cfg(test)]
+mod aspiration {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    use base::r#move::Move;
+    use crossbeam_channel::SendError;
+
+    use crate::{
+        hashtable::TranspositionTable,
+        search::{
+            communication::{CrossbeamSearchSender, ScoreBound, SearchCommand, SearchSender},
+            iterative::iterative_deepening,
+        },
+    };
+
+    use super::search_info;
+
+    #[test]
+    fn reports_bounds_on_fail() {
+        // The first iteration starts with a window around zero, while white
+        // is a queen up. This has to fail high first.
+        let cache = TranspositionTable::size(1);
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let mut info = search_info(
+            "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1",
+            CrossbeamSearchSender::new(sender),
+        )
+        .max_depth(Some(2))
+        .infinite(true);
+        iterative_deepening(&cache, &mut info).unwrap();
+
+        let bounds = receiver
+            .try_iter()
+            .filter_map(|command| match command {
+                SearchCommand::Info(info) => info.score.map(|score| score.bound()),
+                _ => None,
+            })
+            .collect::<Vec<ScoreBound>>();
+
+        assert_eq!(bounds.first(), Some(&ScoreBound::Lower));
+        assert_eq!(bounds.last(), Some(&ScoreBound::Exact));
+        assert_eq!(
+            bounds
+                .iter()
+                .filter(|bound| **bound == ScoreBound::Exact)
+                .count(),
+            2
+        );
+    }
+
+    /// The depth and line of every exact score that was reported.
+    type Lines = Arc<Mutex<Vec<(u8, Vec<Move>)>>>;
+
+    /// Stops the search as soon as a failed aspiration search is reported,
+    /// while keeping the depth of every reported score.
+    struct StopOnBound {
+        running: Arc<AtomicBool>,
+        exact: Lines,
+        bounds: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl SearchSender for StopOnBound {
+        fn send(&self, command: SearchCommand) -> Result<(), Box<SendError<SearchCommand>>> {
+            if let SearchCommand::Info(info) = command {
+                let (Some(depth), Some(score)) = (info.depth, info.score) else {
+                    return Ok(());
+                };
+
+                match score.bound() {
+                    ScoreBound::Exact => {
+                        let pv = info.pv.unwrap_or_default();
+                        self.exact.lock().unwrap().push((depth, pv));
+                    }
+                    _ => {
+                        self.bounds.lock().unwrap().push(depth);
+                        self.running.store(false, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stopped_research_keeps_bound() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let cache = TranspositionTable::size(1);
+        let running = Arc::new(AtomicBool::new(true));
+        let exact = Arc::new(Mutex::new(Vec::new()));
+        let bounds = Arc::new(Mutex::new(Vec::new()));
+
+        let sender = StopOnBound {
+            running: running.clone(),
+            exact: exact.clone(),
+            bounds: bounds.clone(),
+        };
+        let mut info = search_info(fen, sender).infinite(true);
+        info.running = running;
+
+        // The re-search after the first failed search is stopped, so that
+        // depth is only reported as a bound and never counts as completed.
+        let result = iterative_deepening(&cache, &mut info).unwrap();
+        let exact = exact.lock().unwrap();
+        let bounds = bounds.lock().unwrap();
+
+        let (depth, pv) = exact.last().unwrap();
+        assert_eq!(bounds.last(), Some(&(depth + 1)));
+        assert_eq!(result.depth, *depth);
+        assert_eq!(&result.pv, pv);
+        assert_eq!(result.best_move, pv[0]);
+    }
+}
+
+#[cfg(test)]
+mod reporting {
+    use base::{board::Board, r#move::Move};
+
+    use crate::{
+        generator::{AllMoves, MoveGenerator},
+        hashtable::TranspositionTable,
+        search::{
+            communication::{CrossbeamSearchSender, Info, SearchCommand},
+            iterative::iterative_deepening,
+            negamax::negamax,
+            SearchStats, MAX_EVAL, MIN_EVAL,
+        },
+    };
+
+    use super::search_info;
+
+    fn search_infos(stats_interval: u128, currmove_delay: u128) -> (Board, Vec<Info>) {
+        let cache = TranspositionTable::size(1);
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let mut info = search_info(Board::STARTPOS_FEN, CrossbeamSearchSender::new(sender))
+            .max_depth(Some(7))
+            .infinite(true);
+        info.stats_interval = stats_interval;
+        info.currmove_delay = currmove_delay;
+        let board = info.board.clone();
+        iterative_deepening(&cache, &mut info).unwrap();
+
+        let infos = receiver
+            .try_iter()
+            .filter_map(|command| match command {
+                SearchCommand::Info(info) => Some(info),
+                _ => None,
+            })
+            .collect();
+
+        (board, infos)
+    }
+
+    #[test]
+    fn currmove_after_delay() {
+        let (_, infos) = search_infos(u128::MAX, u128::MAX);
+        assert!(infos.iter().all(|info| info.currmove.is_none()));
+
+        let (board, infos) = search_infos(u128::MAX, 0);
+        let root_moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+
+        let currmoves = infos
+            .iter()
+            .filter(|info| info.currmove.is_some())
+            .collect::<Vec<&Info>>();
+        assert!(!currmoves.is_empty());
+
+        for info in currmoves {
+            assert!(root_moves.contains(&info.currmove.unwrap()));
+
+            let number = info.currmovenumber.unwrap().get() as usize;
+            assert!(number <= root_moves.len());
+        }
+    }
+
+    #[test]
+    fn stats_are_cumulative() {
+        let (_, infos) = search_infos(0, u128::MAX);
+
+        let nodes = infos
+            .iter()
+            .filter(|info| info.depth.is_none())
+            .map(|info| info.nodes.unwrap())
+            .collect::<Vec<usize>>();
+
+        assert!(!nodes.is_empty());
+        assert!(nodes.windows(2).all(|window| window[0] <= window[1]));
+    }
+
+    #[test]
+    fn research_is_not_reported() {
+        let cache = TranspositionTable::size(1);
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let mut info =
+            search_info(Board::STARTPOS_FEN, CrossbeamSearchSender::new(sender)).infinite(true);
+        info.currmove_delay = 0;
+
+        let mut stats = SearchStats::new(3);
+        let research = stats.begin_research();
+        negamax(
+            &cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false, false,
+        )
+        .unwrap();
+        stats.end_research(research);
+
+        assert!(info.root_moves.is_empty());
+        assert_eq!(receiver.try_iter().count(), 0);
+
+        // A fresh table, as the root was already stored by the re-search.
+        let cache = TranspositionTable::size(1);
+        negamax(
+            &cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false, false,
+        )
+        .unwrap();
+        assert!(!info.root_moves.is_empty());
+        assert!(receiver.try_iter().count() > 0);
+    }
+}
+
+#[cfg(test)]
+mod skill {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::{
+        hashtable::TranspositionTable,
+        search::{
+            communication::NullSearchSender,
+            iterative::ThreadResult,
+            skill::{skill_search, Skill, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO},
+        },
+    };
+
+    use super::search_info;
+
+    fn root_moves(board: &Board) -> Vec<(Move, i32)> {
+        ["e2e4", "d2d4", "g1f3", "c2c4", "a2a3"]
+            .iter()
+            .zip([40, 35, 30, 0, -50])
+            .map(|(mov, eval)| (Move::parse(board, *mov).unwrap(), eval))
+            .collect()
+    }
+
+    fn search_result(fen: &str, skill: Skill, moves: &[&str]) -> ThreadResult {
+        let info = search_info(fen, NullSearchSender);
+        let moves = moves
+            .iter()
+            .map(|mov| Move::parse(&info.board, *mov).unwrap())
+            .collect();
+
+        let cache = TranspositionTable::size(1);
+        let mut info = info.moves(moves).infinite(true);
+
+        skill_search(&cache, &mut info, skill).unwrap()
+    }
+
+    fn search(fen: &str, skill: Skill) -> Move {
+        search_result(fen, skill, &[]).best_move
+    }
+
+    #[test]
+    fn elo_to_level() {
+        assert_eq!(Skill::from_elo(0, 0).level(), 0);
+        assert_eq!(Skill::from_elo(MIN_ELO, 0).level(), 0);
+        assert_eq!(Skill::from_elo(MAX_ELO, 0).level(), MAX_SKILL_LEVEL);
+        assert!(!Skill::from_elo(MAX_ELO, 0).is_enabled());
+
+        let levels = (MIN_ELO..=MAX_ELO)
+            .step_by(100)
+            .map(|elo| Skill::from_elo(elo, 0).level())
+            .collect::<Vec<u8>>();
+        assert!(levels.windows(2).all(|window| window[0] <= window[1]));
+    }
+
+    #[test]
+    fn pick_is_reproducible() {
+        let board = board(Board::STARTPOS_FEN);
+        let root_moves = root_moves(&board);
+
+        for seed in 0..32 {
+            let skill = Skill::new(0, seed);
+            assert_eq!(skill.pick_move(&root_moves), skill.pick_move(&root_moves));
+        }
+    }
+
+    #[test]
+    fn pick_by_level() {
+        let board = board(Board::STARTPOS_FEN);
+        let root_moves = root_moves(&board);
+
+        // The ranks of the picked moves summed up over many seeds.
+        let ranks = |level| {
+            (0..256)
+                .map(|seed| {
+                    let mov = Skill::new(level, seed).pick_move(&root_moves).unwrap();
+                    root_moves
+                        .iter()
+                        .position(|(root_move, _)| *root_move == mov)
+                        .unwrap()
+                })
+                .sum::<usize>()
+        };
+
+        // Weaker levels pick the lower ranked moves more often.
+        let level_ranks = [0, 5, 10, 15, 19].map(ranks);
+        assert!(level_ranks.windows(2).all(|window| window[0] > window[1]));
+
+        // The highest level always picks the best move.
+        assert_eq!(ranks(MAX_SKILL_LEVEL), 0);
+
+        assert_eq!(Skill::new(0, 0).pick_move(&[]), None);
+    }
+
+    #[test]
+    fn skill_search_is_reproducible() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        for seed in 0..4 {
+            let skill = Skill::new(3, seed);
+            assert_eq!(search(fen, skill), search(fen, skill));
+        }
+    }
+
+    #[test]
+    fn skill_still_mates() {
+        // Even the weakest level should not miss a mate in one, as the mate is
+        // way better than any other move.
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        for seed in 0..8 {
+            assert_eq!(search(fen, Skill::new(0, seed)).to_string(), "h5f7");
+        }
+    }
+
+    #[test]
+    fn result_of_picked_move() {
+        // The line and score that are reported belong to the picked move and
+        // not to the best line of the search.
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        for seed in 0..8 {
+            let result = search_result(fen, Skill::new(0, seed), &[]);
+            assert!(result.depth > 0);
+            assert_eq!(result.pv.first(), Some(&result.best_move));
+        }
+    }
+
+    #[test]
+    fn skill_search_moves() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let moves = ["a2a3", "h2h3"];
+        for seed in 0..8 {
+            let result = search_result(fen, Skill::new(0, seed), &moves);
+            assert!(moves.contains(&result.best_move.to_string().as_str()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod quiescence {
+    use base::board::Board;
+
+    use crate::{
+        hashtable::{
+            entry::{TranspositionEntry, TranspositionFlag},
+            TranspositionTable,
+        },
+        search::{
+            communication::NullSearchSender,
+            quiescence::{quiescence, QUIESCENCE_DEPTH, QUIET_CHECKS_DEPTH},
+            SearchStats, MAX_EVAL, MIN_EVAL,
+        },
+    };
+
+    use super::search_info;
+
+    #[test]
+    fn quiet_checks_are_stored_deeper() {
+        let cache = TranspositionTable::size(1);
+        let mut info = search_info(Board::STARTPOS_FEN, NullSearchSender);
+        let mut stats = SearchStats::new(0);
+        let hash = info.board.hash();
+
+        // The entry of a search without the quiet checks is only used by
+        // the plies that don't search them either.
+        let entry = TranspositionEntry::new(QUIESCENCE_DEPTH, TranspositionFlag::Exact, 777, None);
+        cache.store(hash, entry.clone());
+
+        let eval = quiescence(&cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false);
+        assert_eq!(eval.unwrap(), 777);
+
+        let eval = quiescence(&cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, true);
+        let eval = eval.unwrap();
+        assert_ne!(eval, 777);
+        assert_eq!(cache.probe(hash).unwrap().depth(), QUIET_CHECKS_DEPTH);
+
+        // The search with the quiet checks can't be replaced by one without.
+        cache.store(hash, entry);
+        assert_eq!(cache.probe(hash).unwrap().depth(), QUIET_CHECKS_DEPTH);
+
+        let plain = quiescence(&cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false);
+        assert_eq!(plain.unwrap(), eval);
+    }
+}
+
+#[cfg(test)]
+mod reductions {
+    use std::sync::atomic::Ordering;
+
+    use base::{board::Board, r#move::Move};
+
+    use crate::{
+        hashtable::{
+            entry::{TranspositionEntry, TranspositionFlag},
+            TranspositionTable,
+        },
+        search::{
+            communication::NullSearchSender, negamax::negamax, SearchStats, CHECK_TERMINATION,
+            IID_MIN_DEPTH, IIR_MIN_DEPTH, MAX_EVAL, MIN_EVAL,
+        },
+    };
+
+    use super::search_info;
+
+    #[test]
+    fn stopped_search_restores_depth() {
+        let cache = TranspositionTable::size(1);
+        let mut info = search_info(Board::STARTPOS_FEN, NullSearchSender);
+        info.running.store(false, Ordering::Relaxed);
+
+        // The node has no hash move, so its depth is reduced. The search is
+        // stopped at the first child, which is the next node to check for it.
+        let mut stats = SearchStats::new(IIR_MIN_DEPTH);
+        stats.nodes = CHECK_TERMINATION - 1;
+
+        let result = negamax(&cache, &mut info, &mut stats, -1, 0, false, false);
+        assert!(result.is_err());
+        assert_eq!(stats.depth(), IIR_MIN_DEPTH);
+    }
+
+    #[test]
+    fn pv_node_deepens_internally() {
+        // The table is empty, so the node first gets its best move from a
+        // reduced search of itself.
+        let cache = TranspositionTable::size(1);
+        let mut info = search_info(Board::STARTPOS_FEN, NullSearchSender);
+        let mut stats = SearchStats::new(IID_MIN_DEPTH);
+
+        negamax(
+            &cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false, false,
+        )
+        .unwrap();
+
+        let entry = cache.probe(info.board.hash()).unwrap();
+        assert_eq!(entry.depth(), IID_MIN_DEPTH as i8);
+        assert!(entry.best_move().is_some());
+    }
+
+    #[test]
+    fn non_pv_node_is_reduced() {
+        // Without a hash move the node is searched one ply shallower, which
+        // shows in the depth of its entry.
+        let cache = TranspositionTable::size(1);
+        let mut info = search_info(Board::STARTPOS_FEN, NullSearchSender);
+        let mut stats = SearchStats::new(IIR_MIN_DEPTH);
+
+        negamax(&cache, &mut info, &mut stats, -1, 0, false, false).unwrap();
+
+        let entry = cache.probe(info.board.hash()).unwrap();
+        assert_eq!(entry.depth(), IIR_MIN_DEPTH as i8 - 1);
+        assert_eq!(stats.depth(), IIR_MIN_DEPTH);
+
+        // With a hash move the node keeps its full depth.
+        let cache = TranspositionTable::size(1);
+        let hash_move = Move::parse(&info.board, "e2e4").unwrap();
+        cache.store(
+            info.board.hash(),
+            TranspositionEntry::new(0, TranspositionFlag::Exact, 0, Some(hash_move)),
+        );
+
+        negamax(&cache, &mut info, &mut stats, -1, 0, false, false).unwrap();
+
+        let entry = cache.probe(info.board.hash()).unwrap();
+        assert_eq!(entry.depth(), IIR_MIN_DEPTH as i8);
+    }
+}
+
+#[cfg(test)]
+mod null_move {
+    use base::testing::board;
+
+    use crate::{
+        evaluation::evaluate,
+        hashtable::TranspositionTable,
+        search::{
+            communication::NullSearchSender,
+            engine::Engine,
+            limits::SearchLimits,
+            negamax::{negamax, null_depth_reduction},
+            SearchStats, MAX_EVAL, MIN_EVAL, NULL_VERIFICATION_DEPTH,
+        },
+    };
+
+    use super::search_info;
+
+    #[test]
+    fn depth_reduction() {
+        let reduction = |depth, margin| null_depth_reduction(&SearchStats::new(depth), margin, 0);
+
+        assert_eq!(reduction(6, 0), 4);
+        assert_eq!(reduction(12, 199), 5);
+        assert_eq!(reduction(12, 450), 7);
+        assert_eq!(reduction(18, 2000), 9);
+
+        // The null move search never gets a negative depth.
+        assert_eq!(reduction(5, 1000), 5);
+    }
+
+    #[test]
+    fn pawn_ending_zugzwang() {
+        // Only Kc1 stops the a-pawn in time, which null moves in the pawn
+        // ending would hide behind a false cut-off.
+        let engine = Engine::new(16);
+        let board = board("8/3p4/8/1P6/8/p7/3K2k1/8 w - - 0 1");
+
+        let result = engine
+            .search(&board, SearchLimits::new().depth(10))
+            .unwrap();
+        assert_eq!(result.best_move.to_string(), "d2c1");
+    }
+
+    #[test]
+    fn verification_refutes_cutoff() {
+        // The black king has to give way, while the locked in bishop still
+        // allows null moves.
+        let fen = "b6k/1p6/1P3KP1/6P1/8/8/8/8 b - - 0 1";
+        let depth = NULL_VERIFICATION_DEPTH;
+
+        let cache = TranspositionTable::size(1);
+        let mut info = search_info(fen, NullSearchSender);
+        let mut stats = SearchStats::new(depth);
+        let eval = negamax(
+            &cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false, false,
+        );
+        let beta = eval.unwrap() + 1;
+
+        // Passing the turn fails high, so the null move alone would cut off.
+        let board_eval = evaluate(&info.board, info.board.active());
+        let reduction = null_depth_reduction(&stats, board_eval, beta);
+
+        let cache = TranspositionTable::size(1);
+        let mut stats = SearchStats::new(depth);
+        info.board.make_null();
+        stats.make_search(reduction);
+        let null_eval = negamax(
+            &cache,
+            &mut info,
+            &mut stats,
+            -beta,
+            -beta + 1,
+            false,
+            false,
+        );
+        stats.unmake_search(reduction);
+        info.board.unmake_null();
+        assert!(-null_eval.unwrap() >= beta);
+
+        let cache = TranspositionTable::size(1);
+        let eval = negamax(&cache, &mut info, &mut stats, beta - 1, beta, false, true);
+        assert!(eval.unwrap() < beta);
+    }
+}
+
+#[cfg(test)]
+mod contempt {
+    use base::{board::Board, r#move::Move};
+
+    use crate::{
+        hashtable::TranspositionTable,
+        search::{
+            communication::NullSearchSender, negamax::negamax, SearchInfo, SearchStats, MAX_EVAL,
+            MIN_EVAL,
+        },
+    };
+
+    fn search_info(fen: &str, contempt: i32) -> SearchInfo<NullSearchSender> {
+        super::search_info(fen, NullSearchSender)
+            .infinite(true)
+            .contempt(contempt)
+    }
+
+    #[test]
+    fn draw_score_relative_to_root() {
+        let mut info = search_info(Board::STARTPOS_FEN, 25);
+        assert_eq!(info.draw_score(), -25);
+
+        let mov = Move::parse(&info.board, "e2e4").unwrap();
+        info.board.make(mov);
+        assert_eq!(info.draw_score(), 25);
+
+        let info = search_info(Board::STARTPOS_FEN, 0);
+        assert_eq!(info.draw_score(), 0);
+    }
+
+    #[test]
+    fn stalemate_uses_contempt() {
+        let cache = TranspositionTable::size(1);
+
+        let fen = "k7/8/1Q6/8/8/8/8/7K b - - 0 1";
+        for contempt in [-30, 0, 30] {
+            let mut info = search_info(fen, contempt);
+            let mut stats = SearchStats::new(1);
+
+            let eval = negamax(
+                &cache, &mut info, &mut stats, MIN_EVAL, MAX_EVAL, false, false,
+            );
+            assert_eq!(eval.unwrap(), -contempt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod engine {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::{
+        generator::{AllMoves, MoveGenerator},
+        search::{
+            communication::{Score, ScoreBound},
+            engine::Engine,
+            limits::{SearchLimits, SearchOptions},
+        },
+    };
+
+    #[test]
+    fn limits_time_frame() {
+        // Only an explicit infinite search is infinite, while the other limits
+        // just run without a time limit.
+        let limits = SearchLimits::new().depth(5);
+        assert!(!limits.is_infinite());
+        assert_eq!(limits.time_frame().move_time, u128::MAX);
+
+        let limits = SearchLimits::new().move_time(1000);
+        assert!(!limits.is_infinite());
+        assert_eq!(limits.time_frame().move_time, 1000);
+
+        let limits = SearchLimits::new().clock(40_000, 0);
+        assert!(!limits.is_infinite());
+        assert_eq!(limits.time_frame().move_time, 1000);
+
+        let limits = SearchLimits::new().move_time(1000).infinite();
+        assert!(limits.is_infinite());
+    }
+
+    #[test]
+    fn blocking_search() {
+        let engine = Engine::new(1);
+        let board = board("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 0 1");
+
+        let mut depths = Vec::new();
+        let limits = SearchLimits::new().depth(4);
+        let result = engine
+            .search_with(&board, limits, |info| depths.extend(info.depth))
+            .unwrap();
+
+        assert_eq!(result.best_move.to_string(), "f3f7");
+        assert_eq!(result.pv[0], result.best_move);
+        assert!(matches!(result.score, Some(Score::Mate(1, _))));
+        assert!(result.nodes > 0);
+
+        // The mate is found at the first depth, so the search does not go on
+        // to the depth limit.
+        assert_eq!(result.depth, 1);
+        assert_eq!(depths.last(), Some(&1));
+    }
+
+    #[test]
+    fn repeated_mate_search() {
+        // The mate search keeps its entries apart from the regular search, so
+        // neither a regular search nor an earlier mate search gets in its way.
+        let engine = Engine::new(16);
+        let board = board("1r4k1/5ppp/8/8/8/8/3R1PPP/3R2K1 w - - 0 1");
+        engine.search(&board, SearchLimits::new().depth(4)).unwrap();
+
+        for _ in 0..2 {
+            let result = engine.search(&board, SearchLimits::new().mate(2)).unwrap();
+            let pv = result.pv.iter().map(Move::to_string).collect::<Vec<_>>();
+            assert_eq!(pv, ["d2d8", "b8d8", "d1d8"]);
+            assert!(matches!(
+                result.score,
+                Some(Score::Mate(2, ScoreBound::Exact))
+            ));
+        }
+    }
+
+    #[test]
+    fn depth_limit() {
+        let engine = Engine::new(1);
+        let board = board(Board::STARTPOS_FEN);
+
+        let result = engine.search(&board, SearchLimits::new().depth(3)).unwrap();
+        assert_eq!(result.depth, 3);
+        assert!(matches!(result.score, Some(Score::Centipawns(_, _))));
+    }
+
+    #[test]
+    fn currmove_delay_option() {
+        let board = board(Board::STARTPOS_FEN);
+        let currmoves = |engine: &Engine| {
+            let mut currmoves = 0;
+            engine
+                .search_with(&board, SearchLimits::new().depth(3), |info| {
+                    currmoves += info.currmove.is_some() as usize
+                })
+                .unwrap();
+            currmoves
+        };
+
+        // The short search is over long before the default delay.
+        let engine = Engine::new(1);
+        assert_eq!(currmoves(&engine), 0);
+
+        let mut engine = Engine::new(1);
+        engine.set_currmove_delay(0);
+        assert!(currmoves(&engine) > 0);
+    }
+
+    #[test]
+    fn result_statistics() {
+        let engine = Engine::new(1);
+        let board = board(Board::STARTPOS_FEN);
+
+        let result = engine.search(&board, SearchLimits::new().depth(5)).unwrap();
+        assert_eq!(result.ponder_move, Some(result.pv[1]));
+        assert_eq!(result.score.unwrap().bound(), ScoreBound::Exact);
+        assert!(result.seldepth >= result.depth);
+        assert!((0.0..=1.0).contains(&result.tt_hit_rate));
+
+        let legal_moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+        assert_eq!(result.root_moves.len(), legal_moves.len());
+        assert_eq!(result.root_moves[0].mov, result.best_move);
+
+        let root_nodes = result.root_moves.iter().map(|root_move| root_move.nodes);
+        assert!(root_nodes.clone().all(|nodes| nodes > 0));
+        assert!(root_nodes.sum::<usize>() <= result.nodes);
+    }
+
+    #[test]
+    fn stop_infinite_search() {
+        let mut engine = Engine::new(1);
+        engine.set_threads(2);
+        assert_eq!(
+            engine.options().threads,
+            SearchOptions::default().threads + 1
+        );
+
+        let board = board(Board::STARTPOS_FEN);
+        let handle = engine.start(&board, SearchLimits::new().infinite());
+
+        // The first line is only sent once a depth was completed, so there is
+        // a move to return when the search is stopped.
+        let first_line = handle.infos().find(|info| info.pv.is_some());
+        assert!(first_line.is_some());
+        assert!(!handle.is_finished());
+
+        handle.stop();
+        let result = handle.wait().unwrap();
+        assert!(result.depth > 0);
+    }
+
+    #[test]
+    fn search_moves() {
+        let engine = Engine::new(1);
+        let board = board("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 0 1");
+
+        // The mate is not part of the searched moves.
+        let mov = Move::parse(&board, "b1c3").unwrap();
+        let limits = SearchLimits::new().depth(3).search_moves(vec![mov]);
+        let result = engine.search(&board, limits).unwrap();
+
+        assert_eq!(result.best_move, mov);
+        assert_eq!(result.root_moves.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod tablebase {
+    use base::testing::board;
+
+    use crate::{
+        search::{communication::Score, engine::Engine, limits::SearchLimits},
+        tablebase::{Tablebase, Wdl},
+    };
+
+    const TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tablebases");
+
+    fn tablebase() -> Tablebase {
+        let mut tablebase = Tablebase::new();
+        tablebase.add_directory(TABLES).unwrap();
+        tablebase
+    }
+
+    #[test]
+    fn converts_win() {
+        let mut engine = Engine::new(1);
+        engine.set_tablebase(Some(tablebase()));
+
+        let mut board = board("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1");
+        let result = engine.search(&board, SearchLimits::new().depth(4)).unwrap();
+
+        // The move has to get closer to the mate, not just keep the win.
+        let tablebase = tablebase();
+        board.make(result.best_move);
+        assert_eq!(tablebase.probe_dtz(&mut board).unwrap(), -20);
+    }
+
+    #[test]
+    fn probes_in_search() {
+        let mut engine = Engine::new(1);
+        engine.set_tablebase(Some(tablebase()));
+
+        let mut board = board("8/5K2/3k1r2/8/8/8/2Q5/8 w - - 0 1");
+        let result = engine.search(&board, SearchLimits::new().depth(5)).unwrap();
+
+        assert!(result.tb_hits > 0);
+        assert!(matches!(result.score, Some(Score::Centipawns(eval, _)) if eval > 10_000));
+
+        let tablebase = tablebase();
+        board.make(result.best_move);
+        assert_eq!(tablebase.probe_wdl(&mut board).unwrap(), Wdl::Loss);
+    }
+
+    #[test]
+    fn without_tablebase() {
+        let engine = Engine::new(1);
+        let board = board("8/5K2/3k1r2/8/8/8/2Q5/8 w - - 0 1");
+
+        let result = engine.search(&board, SearchLimits::new().depth(3)).unwrap();
+        assert_eq!(result.tb_hits, 0);
+    }
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod trace {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::{
+        generator::{AllMoves, MoveGenerator},
+        search::trace::{graphviz_escape, trace_search, NodeKind, Pruning, Trace, TraceNode},
+    };
+
+    fn trace(fen: &str, depth: u8) -> (Board, Trace) {
+        let board = board(fen);
+        let trace = trace_search(board.clone(), depth).unwrap();
+
+        (board, trace)
+    }
+
+    fn nodes(node: &TraceNode) -> Vec<&TraceNode> {
+        let mut nodes = vec![node];
+        for child in &node.children {
+            nodes.extend(self::nodes(child));
+        }
+
+        nodes
+    }
+
+    #[test]
+    fn records_root_moves() {
+        let (board, trace) = trace(Board::STARTPOS_FEN, 3);
+        assert!(!trace.roots.is_empty());
+
+        let legal_moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+        for root in &trace.roots {
+            assert_eq!(root.kind, NodeKind::Search);
+            assert_eq!(root.ply, 0);
+            assert_eq!(root.depth, 3);
+            assert!(root.score.is_some());
+
+            for child in &root.children {
+                assert!(legal_moves.contains(&child.mov.unwrap()));
+            }
+        }
+
+        // The last root search is the one that succeeded.
+        let last = trace.roots.last().unwrap();
+        let score = last.score.unwrap();
+        assert!(last.alpha < score && score < last.beta);
+    }
+
+    #[test]
+    fn records_pruning() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let (_, trace) = trace(fen, 7);
+
+        let all_nodes = trace
+            .roots
+            .iter()
+            .flat_map(nodes)
+            .collect::<Vec<&TraceNode>>();
+        assert!(all_nodes
+            .iter()
+            .any(|node| node.kind == NodeKind::Quiescence));
+        assert!(all_nodes.iter().any(|node| node.tt_flag.is_some()));
+
+        let pruned = all_nodes.iter().filter_map(|node| node.pruning);
+        assert!(pruned
+            .clone()
+            .any(|pruning| pruning == Pruning::LateMoveReduction));
+        assert!(pruned.clone().any(|pruning| pruning == Pruning::NullMove));
+
+        // Skipped moves are never searched.
+        for node in all_nodes {
+            if matches!(
+                node.pruning,
+                Some(Pruning::Futility | Pruning::LateMovePruning | Pruning::Delta)
+            ) {
+                assert!(node.score.is_none());
+                assert!(node.children.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn writes_json_and_graphviz() {
+        let (_, trace) = trace(Board::STARTPOS_FEN, 2);
+
+        let mut json = Vec::new();
+        trace.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("[{\"kind\":\"Search\",\"move\":null"));
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json.matches('[').count(), json.matches(']').count());
+
+        let mut graphviz = Vec::new();
+        trace.write_graphviz(&mut graphviz).unwrap();
+        let graphviz = String::from_utf8(graphviz).unwrap();
+        assert!(graphviz.starts_with("digraph search {"));
+        assert!(graphviz.contains("n0 -> n1;"));
+        assert!(graphviz.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn json_scores_are_numbers() {
+        let (_, trace) = trace(Board::STARTPOS_FEN, 2);
+
+        let mut json = Vec::new();
+        trace.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+
+        let score = trace.roots.last().unwrap().score.unwrap();
+        assert!(json.contains(&format!("\"score\":{},\"children\"", score)));
+        assert!(!json.contains("\"score\":\""));
+    }
+
+    #[test]
+    fn graphviz_labels_are_escaped() {
+        assert_eq!(graphviz_escape("e2e4 d1 [-10, 10]"), "e2e4 d1 [-10, 10]");
+        assert_eq!(graphviz_escape("a \"b\""), "a \\\"b\\\"");
+        assert_eq!(graphviz_escape("a\\b"), "a\\\\b");
+    }
+}
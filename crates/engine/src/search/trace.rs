@@ -0,0 +1,338 @@
+use std::{
+    io::{self, Write},
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use base::{board::Board, r#move::Move};
+
+use crate::hashtable::{entry::TranspositionFlag, TranspositionTable};
+
+use super::{
+    aspiration::aspiration, communication::NullSearchSender, error::SearchError,
+    iterative::iterative_deepening, SearchInfo, SearchStats, StopReason, TimeFrame,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Search,
+    Quiescence,
+}
+
+/// Why a move was searched differently than usual, or not at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pruning {
+    /// The side to move passed, to prove that the node fails high anyway.
+    NullMove,
+    /// The reduced search that verifies a null move cut-off.
+    NullMoveVerification,
+    /// The reduced search to find a hash move for the move ordering.
+    InternalIterativeDeepening,
+    /// The move was searched with a reduced depth.
+    LateMoveReduction,
+    /// The move was skipped, as it can't raise alpha by the futility margin.
+    Futility,
+    /// The move was skipped, as enough quiet moves were already searched.
+    LateMovePruning,
+    /// The capture was skipped in the quiescence search, as it can't raise alpha.
+    Delta,
+}
+
+/// Why a node returned without searching all of its moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cutoff {
+    Draw,
+    Checkmate,
+    Stalemate,
+    Transposition,
+    Tablebase,
+    NullMove,
+    StandPat,
+    Beta,
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceNode {
+    pub kind: NodeKind,
+    /// The move that lead to this node. There is none for the root and the
+    /// searches of the same position, like the null move.
+    pub mov: Option<Move>,
+    pub pruning: Option<Pruning>,
+    pub ply: u8,
+    pub depth: u8,
+    pub alpha: i32,
+    pub beta: i32,
+    pub tt_flag: Option<TranspositionFlag>,
+    pub cutoff: Option<Cutoff>,
+    /// There is no score if the move was pruned or the search was stopped.
+    pub score: Option<i32>,
+    pub children: Vec<TraceNode>,
+}
+
+impl TraceNode {
+    fn skipped(mov: Move, pruning: Pruning) -> Self {
+        Self {
+            kind: NodeKind::Search,
+            mov: Some(mov),
+            pruning: Some(pruning),
+            ply: 0,
+            depth: 0,
+            alpha: 0,
+            beta: 0,
+            tt_flag: None,
+            cutoff: None,
+            score: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.mov {
+            Some(mov) => mov.to_string(),
+            None if self.ply == 0 => "root".to_string(),
+            None => "-".to_string(),
+        }
+    }
+
+    fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{{\"kind\":\"{:?}\"", self.kind)?;
+        write!(writer, ",\"move\":{}", json_string(self.mov))?;
+        write!(writer, ",\"pruning\":{}", json_debug(self.pruning))?;
+        write!(writer, ",\"ply\":{},\"depth\":{}", self.ply, self.depth)?;
+        write!(writer, ",\"alpha\":{},\"beta\":{}", self.alpha, self.beta)?;
+        write!(writer, ",\"tt_flag\":{}", json_debug(self.tt_flag))?;
+        write!(writer, ",\"cutoff\":{}", json_debug(self.cutoff))?;
+        write!(writer, ",\"score\":{}", json_number(self.score))?;
+
+        write!(writer, ",\"children\":[")?;
+        for (index, child) in self.children.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+
+            child.write_json(writer)?;
+        }
+
+        write!(writer, "]}}")
+    }
+
+    fn write_graphviz<W: Write>(&self, writer: &mut W, id: &mut usize) -> io::Result<usize> {
+        let node_id = *id;
+        *id += 1;
+
+        let mut lines = vec![format!(
+            "{} d{} [{}, {}]",
+            self.label(),
+            self.depth,
+            self.alpha,
+            self.beta
+        )];
+        if let Some(pruning) = self.pruning {
+            lines.push(format!("{:?}", pruning));
+        }
+        if let Some(flag) = self.tt_flag {
+            lines.push(format!("tt {:?}", flag));
+        }
+        if let Some(cutoff) = self.cutoff {
+            lines.push(format!("cutoff {:?}", cutoff));
+        }
+        if let Some(score) = self.score {
+            lines.push(format!("score {}", score));
+        }
+
+        // The lines are joined with the escaped line break of Graphviz.
+        let label = lines
+            .iter()
+            .map(|line| graphviz_escape(line))
+            .collect::<Vec<String>>()
+            .join("\\n");
+
+        let shape = match self.kind {
+            NodeKind::Search => "box",
+            NodeKind::Quiescence => "ellipse",
+        };
+        let style = match self.score {
+            Some(_) => "solid",
+            None => "dashed",
+        };
+        writeln!(
+            writer,
+            "  n{} [label=\"{}\", shape={}, style={}];",
+            node_id, label, shape, style
+        )?;
+
+        for child in &self.children {
+            let child_id = child.write_graphviz(writer, id)?;
+            writeln!(writer, "  n{} -> n{};", node_id, child_id)?;
+        }
+
+        Ok(node_id)
+    }
+}
+
+fn json_string<T: ToString>(value: Option<T>) -> String {
+    match value {
+        Some(value) => format!("\"{}\"", value.to_string()),
+        None => "null".to_string(),
+    }
+}
+
+fn json_number(value: Option<i32>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+fn json_debug<T: std::fmt::Debug>(value: Option<T>) -> String {
+    match value {
+        Some(value) => format!("\"{:?}\"", value),
+        None => "null".to_string(),
+    }
+}
+
+/// Escapes the characters that would end or break a quoted Graphviz label.
+pub(crate) fn graphviz_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The recorded search trees. Every search of the root is a separate tree,
+/// so a failed aspiration window shows up as multiple trees.
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    pub roots: Vec<TraceNode>,
+}
+
+impl Trace {
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "[")?;
+        for (index, root) in self.roots.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+
+            root.write_json(writer)?;
+        }
+
+        writeln!(writer, "]")
+    }
+
+    pub fn write_graphviz<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "digraph search {{")?;
+
+        let mut id = 0;
+        for root in &self.roots {
+            root.write_graphviz(writer, &mut id)?;
+        }
+
+        writeln!(writer, "}}")
+    }
+}
+
+/// Builds the search tree while the search walks it. The tracer stays
+/// idle until it is enabled, so only the interesting part is recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+    enabled: bool,
+    stack: Vec<TraceNode>,
+    roots: Vec<TraceNode>,
+    next_move: Option<Move>,
+    next_pruning: Option<Pruning>,
+}
+
+impl Tracer {
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn take(&mut self) -> Trace {
+        self.enabled = false;
+        self.stack.clear();
+
+        Trace {
+            roots: std::mem::take(&mut self.roots),
+        }
+    }
+
+    pub(crate) fn enter(&mut self, kind: NodeKind, stats: &SearchStats, alpha: i32, beta: i32) {
+        if !self.enabled {
+            return;
+        }
+
+        self.stack.push(TraceNode {
+            kind,
+            mov: self.next_move.take(),
+            pruning: self.next_pruning.take(),
+            ply: stats.ply(),
+            depth: stats.depth(),
+            alpha,
+            beta,
+            tt_flag: None,
+            cutoff: None,
+            score: None,
+            children: Vec::new(),
+        });
+    }
+
+    pub(crate) fn exit(&mut self, result: &Result<i32, StopReason>) {
+        let Some(mut node) = self.stack.pop() else {
+            return;
+        };
+
+        node.score = result.as_ref().ok().copied();
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.roots.push(node),
+        }
+    }
+
+    /// Describes the next node that is entered.
+    pub(crate) fn child(&mut self, mov: Option<Move>, pruning: Option<Pruning>) {
+        if self.enabled {
+            self.next_move = mov;
+            self.next_pruning = pruning;
+        }
+    }
+
+    /// Records a move that was not searched at all.
+    pub(crate) fn skip(&mut self, mov: Move, pruning: Pruning) {
+        if let Some(node) = self.stack.last_mut() {
+            node.children.push(TraceNode::skipped(mov, pruning));
+        }
+    }
+
+    pub(crate) fn tt_hit(&mut self, flag: TranspositionFlag) {
+        if let Some(node) = self.stack.last_mut() {
+            node.tt_flag = Some(flag);
+        }
+    }
+
+    pub(crate) fn cutoff(&mut self, cutoff: Cutoff) {
+        if let Some(node) = self.stack.last_mut() {
+            node.cutoff = Some(cutoff);
+        }
+    }
+}
+
+/// Searches the board up to the given depth and records the tree of the
+/// last depth. The depths before are searched without the tracer, so that
+/// the move ordering and the transposition table are the same as in a
+/// regular search.
+pub fn trace_search(board: Board, depth: u8) -> Result<Trace, SearchError> {
+    let cache = TranspositionTable::size(16);
+    let mut info = SearchInfo::new(
+        board,
+        NullSearchSender,
+        Arc::new(AtomicBool::new(true)),
+        TimeFrame::new(u128::MAX),
+    )
+    .max_depth(Some(depth.saturating_sub(1)))
+    .infinite(true);
+
+    let result = iterative_deepening(&cache, &mut info)?;
+
+    info.tracer.enable();
+    let mut stats = SearchStats::new(depth);
+    aspiration(&cache, &mut info, &mut stats, result.eval)?;
+
+    Ok(info.tracer.take())
+}
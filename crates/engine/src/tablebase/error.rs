@@ -0,0 +1,58 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub enum TablebaseError {
+    MissingTable(#[from] MissingTable),
+    InvalidTable(#[from] InvalidTable),
+    TooManyPieces(#[from] TooManyPieces),
+    CastlingRights(#[from] CastlingRights),
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("the table for '{material}' is not available")]
+pub struct MissingTable {
+    material: String,
+}
+
+impl MissingTable {
+    pub fn new(material: impl Into<String>) -> Self {
+        Self {
+            material: material.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the table '{name}' is invalid: {reason}")]
+pub struct InvalidTable {
+    name: String,
+    reason: String,
+}
+
+impl InvalidTable {
+    pub fn new(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the position has {given} pieces, but the tables only cover {max}")]
+pub struct TooManyPieces {
+    given: usize,
+    max: usize,
+}
+
+impl TooManyPieces {
+    pub fn new(given: usize, max: usize) -> Self {
+        Self { given, max }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("positions with castling rights are not covered by the tables")]
+pub struct CastlingRights;
@@ -0,0 +1,7 @@
+pub mod tablebase;
+pub use tablebase::*;
+
+pub mod error;
+pub mod table;
+
+mod tests;
@@ -0,0 +1,1016 @@
+use base::{
+    bitboard::Bitboard,
+    board::{color::Color, piece::Piece, Board},
+};
+
+use crate::evaluation::endgame::MaterialSignature;
+
+use super::{error::InvalidTable, tablebase::Wdl};
+
+pub const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+pub const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+pub const WDL_EXTENSION: &str = "rtbw";
+pub const DTZ_EXTENSION: &str = "rtbz";
+
+/// The most pieces a Syzygy table can contain, including both kings.
+pub const MAX_PIECES: usize = 7;
+
+// The layout of the whole table, stored right after the magic number.
+const LAYOUT_SPLIT: u8 = 1;
+const LAYOUT_HAS_PAWNS: u8 = 2;
+
+// The flags of a single side of the table.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// The amount of positions of the leading group without pawns, either three
+// unique pieces or just the two kings.
+const UNIQUE_PIECES_SIZE: u64 = 31_332;
+const KINGS_SIZE: u64 = 462;
+
+// Maps the result of a position to the DTZ map that is used for it, indexed
+// by the WDL value plus two.
+const WDL_TO_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+const BINOMIAL: [[u64; 64]; MAX_PIECES] = binomial();
+const MAP_B1H1H7: [u64; 64] = map_b1h1h7();
+const MAP_A1D1D4: [u64; 64] = map_a1d1d4();
+const MAP_KK: [[u64; 64]; 10] = map_kk();
+const MAP_PAWNS: [u64; 64] = map_pawns();
+const LEAD_PAWN_INDEX: [[u64; 64]; MAX_PIECES] = lead_pawns().0;
+const LEAD_PAWNS_SIZE: [[u64; 4]; MAX_PIECES] = lead_pawns().1;
+
+/// Returns how far the square is above the a1-h8 diagonal. Squares below
+/// the diagonal are negative.
+const fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+/// Mirrors the square on the a1-h8 diagonal.
+const fn flip_diagonal(square: usize) -> usize {
+    ((square >> 3) | (square << 3)) & 63
+}
+
+const fn is_adjacent(first: usize, second: usize) -> bool {
+    (first / 8).abs_diff(second / 8) <= 1 && (first % 8).abs_diff(second % 8) <= 1
+}
+
+/// The binomial coefficients, indexed by the amount of pieces and then the
+/// amount of squares they can be placed on.
+const fn binomial() -> [[u64; 64]; MAX_PIECES] {
+    let mut table = [[0; 64]; MAX_PIECES];
+
+    let mut squares = 0;
+    while squares < 64 {
+        let mut pieces = 0;
+        while pieces < MAX_PIECES && pieces <= squares {
+            table[pieces][squares] = if pieces == 0 || pieces == squares {
+                1
+            } else {
+                table[pieces - 1][squares - 1] + table[pieces][squares - 1]
+            };
+
+            pieces += 1;
+        }
+
+        squares += 1;
+    }
+
+    table
+}
+
+/// Encodes the squares of the b1-h1-h7 triangle to 0..28.
+const fn map_b1h1h7() -> [u64; 64] {
+    let mut table = [0; 64];
+
+    let mut code = 0;
+    let mut square = 0;
+    while square < 64 {
+        if off_diagonal(square) < 0 {
+            table[square] = code;
+            code += 1;
+        }
+
+        square += 1;
+    }
+
+    table
+}
+
+/// Encodes the squares of the a1-d1-d4 triangle to 0..10, where the ones
+/// on the diagonal come last.
+const fn map_a1d1d4() -> [u64; 64] {
+    let mut table = [0; 64];
+
+    let mut code = 0;
+    let mut diagonal = 0;
+    while diagonal < 2 {
+        let mut square = 0;
+        while square < 32 {
+            let offset = off_diagonal(square);
+            if square % 8 < 4 && offset <= 0 && (offset == 0) == (diagonal == 1) {
+                table[square] = code;
+                code += 1;
+            }
+
+            square += 1;
+        }
+
+        diagonal += 1;
+    }
+
+    table
+}
+
+/// Encodes the 462 legal positions of two kings, where the first one is in
+/// the a1-d1-d4 triangle. If the first king is on the diagonal, the other
+/// one is not above it. Both kings on the diagonal are encoded last.
+const fn map_kk() -> [[u64; 64]; 10] {
+    let mut table = [[0; 64]; 10];
+
+    let mut code = 0;
+    let mut diagonal = 0;
+    while diagonal < 2 {
+        let mut index = 0;
+        while index < 10 {
+            let mut first = 0;
+            while first < 32 {
+                // Every square outside of the triangle maps to zero as well,
+                // so the zero is only taken from b1.
+                if MAP_A1D1D4[first] != index as u64 || (index == 0 && first != 1) {
+                    first += 1;
+                    continue;
+                }
+
+                let mut second = 0;
+                while second < 64 {
+                    let on_diagonal = off_diagonal(first) == 0;
+                    let both_on_diagonal = on_diagonal && off_diagonal(second) == 0;
+                    // The second king is never adjacent, and never above the
+                    // diagonal if the first one is on it.
+                    let is_skipped =
+                        is_adjacent(first, second) || (on_diagonal && off_diagonal(second) > 0);
+
+                    if !is_skipped && both_on_diagonal == (diagonal == 1) {
+                        table[index][second] = code;
+                        code += 1;
+                    }
+
+                    second += 1;
+                }
+
+                first += 1;
+            }
+
+            index += 1;
+        }
+
+        diagonal += 1;
+    }
+
+    table
+}
+
+/// Encodes the squares of the pawns, so that the leading pawn is the one
+/// with the highest value. Pawns closer to the a-file and h-file and on
+/// lower ranks come first.
+const fn map_pawns() -> [u64; 64] {
+    let mut table = [0; 64];
+
+    let mut available = 48;
+    let mut file = 0;
+    while file < 4 {
+        let mut rank = 1;
+        while rank < 7 {
+            let square = rank * 8 + file;
+
+            available -= 1;
+            table[square] = available;
+            available -= 1;
+            table[square ^ 7] = available;
+
+            rank += 1;
+        }
+
+        file += 1;
+    }
+
+    table
+}
+
+/// The index of the leading pawn and the amount of positions of all leading
+/// pawns on each file, indexed by the amount of leading pawns.
+const fn lead_pawns() -> ([[u64; 64]; MAX_PIECES], [[u64; 4]; MAX_PIECES]) {
+    let mut indices = [[0; 64]; MAX_PIECES];
+    let mut sizes = [[0; 4]; MAX_PIECES];
+
+    let mut count = 1;
+    while count < MAX_PIECES {
+        let mut file = 0;
+        while file < 4 {
+            let mut index = 0;
+
+            let mut rank = 1;
+            while rank < 7 {
+                let square = rank * 8 + file;
+                indices[count][square] = index;
+                index += BINOMIAL[count - 1][MAP_PAWNS[square] as usize];
+
+                rank += 1;
+            }
+
+            sizes[count][file] = index;
+            file += 1;
+        }
+
+        count += 1;
+    }
+
+    (indices, sizes)
+}
+
+/// The pieces are stored as a nibble, the color is the fourth bit.
+const fn encode_piece(color: Color, piece: Piece) -> u8 {
+    match color {
+        Color::White => piece.index() as u8,
+        Color::Black => piece.index() as u8 | 8,
+    }
+}
+
+const fn decode_piece(code: u8) -> (Color, Piece) {
+    let color = match code & 8 {
+        0 => Color::White,
+        _ => Color::Black,
+    };
+
+    (color, Piece::from_index((code & 7) as usize))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+impl TableKind {
+    pub const fn magic(&self) -> [u8; 4] {
+        match self {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        }
+    }
+
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            TableKind::Wdl => WDL_EXTENSION,
+            TableKind::Dtz => DTZ_EXTENSION,
+        }
+    }
+}
+
+/// How the positions of one side to move on one file are indexed and how
+/// their values are compressed.
+/// Source: https://github.com/syzygy1/tb
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    /// The order in which the pieces are encoded.
+    pieces: [u8; MAX_PIECES],
+    /// The lengths of the groups of pieces that are encoded together,
+    /// terminated by a zero.
+    group_len: [usize; MAX_PIECES + 1],
+    /// The factor of each group in the index, the one after the last group
+    /// is the size of the whole table.
+    group_index: [u64; MAX_PIECES + 1],
+    block_size: usize,
+    span: u64,
+    blocks: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    block_lengths: usize,
+    block_lengths_size: usize,
+    data: usize,
+    /// The shortest Huffman code, or the value of all positions if the
+    /// table only stores a single one.
+    min_symbol_length: u8,
+    lowest_symbol: usize,
+    /// The lowest Huffman code of every length, padded to 64 bits.
+    base: Vec<u64>,
+    /// The amount of values every symbol expands to, minus one.
+    symbol_length: Vec<u8>,
+    btree: usize,
+    /// The offsets into the DTZ map for each result.
+    map_index: [u16; 4],
+}
+
+/// A single WDL or DTZ table, which is completely read into memory.
+pub struct Table {
+    name: String,
+    kind: TableKind,
+    /// The material with the pieces of white in the order of the file name.
+    key: MaterialSignature,
+    data: Vec<u8>,
+    symmetric: bool,
+    has_pawns: bool,
+    both_pawns: bool,
+    has_unique_pieces: bool,
+    piece_count: usize,
+    map: usize,
+    /// The encoding of each side to move and each file of the leading pawn.
+    sides: Vec<Vec<PairsData>>,
+}
+
+impl Table {
+    pub fn parse(name: &str, kind: TableKind, data: Vec<u8>) -> Result<Table, InvalidTable> {
+        let invalid = |reason: &str| InvalidTable::new(name, reason);
+
+        let key = MaterialSignature::from_name(name)
+            .filter(|key| key.piece_count() <= MAX_PIECES)
+            .ok_or_else(|| invalid("the name is not a supported material"))?;
+
+        if data.len() < 5 || data[..4] != kind.magic() {
+            return Err(invalid("the magic number does not match"));
+        }
+
+        let has_pawns =
+            key.count(Color::White, Piece::Pawn) + key.count(Color::Black, Piece::Pawn) > 0;
+        let symmetric = key == key.flipped();
+        let layout = data[4];
+        if (layout & LAYOUT_HAS_PAWNS != 0) != has_pawns
+            || (layout & LAYOUT_SPLIT != 0) == symmetric
+        {
+            return Err(invalid("the layout does not match the material"));
+        }
+
+        let has_unique_pieces = [Color::White, Color::Black].into_iter().any(|color| {
+            [
+                Piece::Pawn,
+                Piece::Knight,
+                Piece::Bishop,
+                Piece::Rook,
+                Piece::Queen,
+            ]
+            .into_iter()
+            .any(|piece| key.count(color, piece) == 1)
+        });
+
+        let sides = if kind == TableKind::Wdl && !symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if has_pawns { 4 } else { 1 };
+
+        let mut table = Table {
+            name: name.to_string(),
+            kind,
+            key,
+            data,
+            symmetric,
+            has_pawns,
+            both_pawns: key.count(Color::White, Piece::Pawn) > 0
+                && key.count(Color::Black, Piece::Pawn) > 0,
+            has_unique_pieces,
+            piece_count: key.piece_count(),
+            map: 0,
+            sides: vec![vec![PairsData::default(); files]; sides],
+        };
+
+        table
+            .parse_layout(files)
+            .ok_or_else(|| invalid("the file is truncated or corrupted"))?;
+
+        Ok(table)
+    }
+
+    /// Reads the encoding and compression of every side and file, and where
+    /// their data is stored.
+    fn parse_layout(&mut self, files: usize) -> Option<()> {
+        let mut offset = 5;
+        for file in 0..files {
+            let order = self.read_u8(offset)?;
+            let pawn_order = match self.both_pawns {
+                true => self.read_u8(offset + 1)?,
+                false => 0xFF,
+            };
+            offset += 1 + self.both_pawns as usize;
+
+            for index in 0..self.piece_count {
+                let pieces = self.read_u8(offset + index)?;
+                for (side, data) in self.sides.iter_mut().enumerate() {
+                    data[file].pieces[index] = match side {
+                        0 => pieces & 0xF,
+                        _ => pieces >> 4,
+                    };
+                }
+            }
+            offset += self.piece_count;
+
+            for side in 0..self.sides.len() {
+                let order = match side {
+                    0 => [order & 0xF, pawn_order & 0xF],
+                    _ => [order >> 4, pawn_order >> 4],
+                };
+                self.set_groups(side, file, order)?;
+            }
+        }
+
+        offset += offset & 1;
+
+        for file in 0..files {
+            for side in 0..self.sides.len() {
+                offset = self.set_sizes(side, file, offset)?;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            offset = self.set_dtz_map(offset, files)?;
+        }
+
+        for file in 0..files {
+            for data in self.sides.iter_mut() {
+                data[file].sparse_index = offset;
+                offset += data[file].sparse_index_size * 6;
+            }
+        }
+
+        for file in 0..files {
+            for data in self.sides.iter_mut() {
+                data[file].block_lengths = offset;
+                offset += data[file].block_lengths_size * 2;
+            }
+        }
+
+        for file in 0..files {
+            for data in self.sides.iter_mut() {
+                offset = (offset + 0x3F) & !0x3F;
+                data[file].data = offset;
+                offset += data[file].blocks * data[file].block_size;
+            }
+        }
+
+        (offset <= self.data.len()).then_some(())
+    }
+
+    /// Splits the pieces into the groups that are encoded together and
+    /// computes the factor of each group in the index. The leading group is
+    /// either the leading pawns, three unique pieces or the kings.
+    fn set_groups(&mut self, side: usize, file: usize, order: [u8; 2]) -> Option<()> {
+        let has_pawns = self.has_pawns;
+        let both_pawns = self.both_pawns;
+        let has_unique_pieces = self.has_unique_pieces;
+        let piece_count = self.piece_count;
+        let data = &mut self.sides[side][file];
+
+        let mut first_len: i32 = match (has_pawns, has_unique_pieces) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 2,
+        };
+
+        let mut groups = 0;
+        data.group_len[0] = 1;
+        for index in 1..piece_count {
+            first_len -= 1;
+            if first_len > 0 || data.pieces[index] == data.pieces[index - 1] {
+                data.group_len[groups] += 1;
+            } else {
+                groups += 1;
+                data.group_len[groups] = 1;
+            }
+        }
+        groups += 1;
+        data.group_len[groups] = 0;
+
+        // The groups are not encoded in their natural order, the table tells
+        // at which position the leading group and the remaining pawns are.
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - data.group_len[0];
+        if both_pawns {
+            free_squares -= data.group_len[1];
+        }
+
+        let mut index = 1u64;
+        let mut position = 0;
+        while next < groups || position == order[0] || position == order[1] {
+            let factor = if position == order[0] {
+                data.group_index[0] = index;
+                match (has_pawns, has_unique_pieces) {
+                    (true, _) => LEAD_PAWNS_SIZE.get(data.group_len[0])?[file],
+                    (false, true) => UNIQUE_PIECES_SIZE,
+                    (false, false) => KINGS_SIZE,
+                }
+            } else if position == order[1] {
+                data.group_index[1] = index;
+                BINOMIAL.get(data.group_len[1])?[48 - data.group_len[0]]
+            } else {
+                let len = *data.group_len.get(next)?;
+                *data.group_index.get_mut(next)? = index;
+                free_squares = free_squares.checked_sub(len)?;
+                next += 1;
+                BINOMIAL.get(len)?[free_squares + len]
+            };
+
+            index = index.checked_mul(factor)?;
+            position += 1;
+        }
+
+        data.group_index[groups] = index;
+
+        Some(())
+    }
+
+    /// Reads the sizes of the compressed data and the canonical Huffman
+    /// code of one side and file.
+    fn set_sizes(&mut self, side: usize, file: usize, mut offset: usize) -> Option<usize> {
+        let bytes = &self.data;
+        let data = &mut self.sides[side][file];
+
+        data.flags = read_u8(bytes, offset)?;
+        if data.flags & FLAG_SINGLE_VALUE != 0 {
+            data.min_symbol_length = read_u8(bytes, offset + 1)?;
+            return Some(offset + 2);
+        }
+
+        let groups = data.group_len.iter().position(|&len| len == 0)?;
+        let size = data.group_index[groups];
+
+        data.block_size = 1 << read_u8(bytes, offset + 1)?;
+        data.span = 1 << read_u8(bytes, offset + 2)?;
+        data.sparse_index_size = size.div_ceil(data.span) as usize;
+        let padding = read_u8(bytes, offset + 3)? as usize;
+        data.blocks = read_u32(bytes, offset + 4)? as usize;
+        data.block_lengths_size = data.blocks + padding;
+
+        let max_symbol_length = read_u8(bytes, offset + 8)?;
+        data.min_symbol_length = read_u8(bytes, offset + 9)?;
+        if data.min_symbol_length == 0 || max_symbol_length < data.min_symbol_length {
+            return None;
+        }
+
+        offset += 10;
+        data.lowest_symbol = offset;
+
+        // The longer codes have lower values, so the base of every length is
+        // computed from the one of the next length.
+        // Source: https://en.wikipedia.org/wiki/Canonical_Huffman_code
+        let lengths = (max_symbol_length - data.min_symbol_length + 1) as usize;
+        data.base = vec![0; lengths];
+        for length in (0..lengths - 1).rev() {
+            let lowest = read_u16(bytes, offset + 2 * length)? as u64;
+            let next_lowest = read_u16(bytes, offset + 2 * (length + 1))? as u64;
+            data.base[length] = data.base[length + 1]
+                .wrapping_add(lowest)
+                .wrapping_sub(next_lowest)
+                / 2;
+        }
+
+        for (length, base) in data.base.iter_mut().enumerate() {
+            let shift = 64u32.checked_sub((length + data.min_symbol_length as usize) as u32)?;
+            *base = base.checked_shl(shift).unwrap_or(0);
+        }
+
+        offset += lengths * 2;
+        let symbols = read_u16(bytes, offset)? as usize;
+        offset += 2;
+
+        data.btree = offset;
+        data.symbol_length = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                data.symbol_length[symbol] = symbol_length(
+                    bytes,
+                    data.btree,
+                    &mut data.symbol_length,
+                    &mut visited,
+                    symbol,
+                )?;
+            }
+        }
+
+        Some(offset + symbols * 3 + (symbols & 1))
+    }
+
+    /// Reads the maps of the DTZ values, which are stored per result.
+    fn set_dtz_map(&mut self, mut offset: usize, files: usize) -> Option<usize> {
+        self.map = offset;
+
+        for file in 0..files {
+            let flags = self.sides[0][file].flags;
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+
+            if flags & FLAG_WIDE != 0 {
+                offset += offset & 1;
+                for index in 0..4 {
+                    self.sides[0][file].map_index[index] = ((offset - self.map) / 2 + 1) as u16;
+                    offset += 2 * read_u16(&self.data, offset)? as usize + 2;
+                }
+            } else {
+                for index in 0..4 {
+                    self.sides[0][file].map_index[index] = (offset - self.map + 1) as u16;
+                    offset += read_u8(&self.data, offset)? as usize + 1;
+                }
+            }
+        }
+
+        Some(offset + (offset & 1))
+    }
+
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Looks up the result of the board, which has to have the material of
+    /// the table with either color.
+    pub fn probe_wdl(&self, board: &Board) -> Result<Wdl, InvalidTable> {
+        let (_, value) = self
+            .lookup(board)?
+            .ok_or_else(|| self.invalid("the side to move is missing"))?;
+
+        Wdl::from_value(value as i32 - 2).ok_or_else(|| self.invalid("the result is out of range"))
+    }
+
+    /// Looks up the distance to the next capture or pawn move of the board
+    /// with the given result, in plies. As most DTZ tables only store one
+    /// side to move, there is no value for the other side.
+    pub fn probe_dtz(&self, board: &Board, wdl: Wdl) -> Result<Option<i32>, InvalidTable> {
+        let (file, value) = match self.lookup(board)? {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        let data = &self.sides[0][file];
+        let mut value = value as usize;
+        if data.flags & FLAG_MAPPED != 0 {
+            let index = data.map_index[WDL_TO_MAP[(wdl.value() + 2) as usize]] as usize + value;
+            let mapped = match data.flags & FLAG_WIDE {
+                0 => read_u8(&self.data, self.map + index).map(usize::from),
+                _ => read_u16(&self.data, self.map + 2 * index).map(usize::from),
+            };
+
+            value = mapped.ok_or_else(|| self.invalid("the DTZ map is truncated"))?;
+        }
+
+        // The tables store moves instead of plies where it does not matter
+        // for the fifty move rule.
+        let stores_moves = match wdl {
+            Wdl::Win => data.flags & FLAG_WIN_PLIES == 0,
+            Wdl::Loss => data.flags & FLAG_LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+
+        if stores_moves {
+            value *= 2;
+        }
+
+        Ok(Some(value as i32 + 1))
+    }
+
+    /// Computes the index of the board in the table and decompresses its
+    /// value. Returns the file of the leading pawn together with the value,
+    /// or nothing if the DTZ table does not store the side to move.
+    fn lookup(&self, board: &Board) -> Result<Option<(usize, u16)>, InvalidTable> {
+        let signature = MaterialSignature::new(board);
+        debug_assert!(signature == self.key || signature == self.key.flipped());
+
+        // The tables are stored with the stronger side as white, and for
+        // symmetric material only with white to move.
+        let flip = match self.symmetric {
+            true => board.active() == Color::Black,
+            false => signature != self.key,
+        };
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let side = (flip != (board.active() == Color::Black)) as usize;
+
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+
+        let mut lead_pawns = Bitboard::default();
+        let mut file = 0;
+        if self.has_pawns {
+            let code = self.sides[0][0].pieces[0];
+            let (color, piece) = decode_piece(code ^ flip_color);
+            lead_pawns = board.get_piece_board(color, piece);
+
+            for square in lead_pawns {
+                squares[size] = square.index() as usize ^ flip_squares;
+                pieces[size] = code;
+                size += 1;
+            }
+
+            let lead = (0..size).max_by_key(|&index| MAP_PAWNS[squares[index]]);
+            squares.swap(0, lead.unwrap_or_default());
+
+            let lead_file = squares[0] % 8;
+            file = lead_file.min(7 - lead_file);
+        }
+        let lead_pawns_count = size;
+
+        if self.kind == TableKind::Dtz && !self.stores_side(side, file) {
+            return Ok(None);
+        }
+
+        let data = &self.sides[side % self.sides.len()][file];
+
+        for square in board.get_all_occupied() & !lead_pawns {
+            let tile = board
+                .get_tile(square)
+                .ok_or_else(|| self.invalid("the board is broken"))?;
+            if size >= self.piece_count {
+                return Err(self.invalid("the board does not match the material"));
+            }
+
+            squares[size] = square.index() as usize ^ flip_squares;
+            pieces[size] = encode_piece(tile.color, tile.piece) ^ flip_color;
+            size += 1;
+        }
+
+        // The pieces are sorted in the order of the table, which is chosen
+        // for the best compression.
+        for index in lead_pawns_count..size.saturating_sub(1) {
+            for other in index + 1..size {
+                if data.pieces[index] == pieces[other] {
+                    pieces.swap(index, other);
+                    squares.swap(index, other);
+                    break;
+                }
+            }
+        }
+
+        // The leading piece is always mirrored to the a-d files.
+        if squares[0] % 8 > 3 {
+            squares[..size].iter_mut().for_each(|square| *square ^= 7);
+        }
+
+        let mut index = if self.has_pawns {
+            let mut index = LEAD_PAWN_INDEX[lead_pawns_count][squares[0]];
+
+            squares[1..lead_pawns_count].sort_by_key(|&square| MAP_PAWNS[square]);
+            for (count, &square) in squares[1..lead_pawns_count].iter().enumerate() {
+                index += BINOMIAL[count + 1][MAP_PAWNS[square] as usize];
+            }
+
+            index
+        } else {
+            self.encode_leading_pieces(&mut squares[..size], data)
+        };
+
+        index *= data.group_index[0];
+
+        // The remaining groups are encoded in ascending order of their
+        // squares, skipping the squares that are taken by previous groups.
+        let mut remaining_pawns = self.both_pawns;
+        let mut group_start = data.group_len[0];
+        let mut group = 1;
+        while data.group_len[group] != 0 {
+            let group_end = group_start + data.group_len[group];
+            squares[group_start..group_end].sort_unstable();
+
+            let mut group_index = 0;
+            for (count, &square) in squares[group_start..group_end].iter().enumerate() {
+                let taken = squares[..group_start]
+                    .iter()
+                    .filter(|&&other| square > other)
+                    .count();
+                let pawn_offset = if remaining_pawns { 8 } else { 0 };
+
+                group_index += BINOMIAL[count + 1][square - taken - pawn_offset];
+            }
+
+            remaining_pawns = false;
+            index += group_index * data.group_index[group];
+            group_start = group_end;
+            group += 1;
+        }
+
+        let value = self.decompress(data, index)?;
+        Ok(Some((file, value)))
+    }
+
+    /// Encodes the leading group of a table without pawns. The board is
+    /// mirrored so that the first piece is in the a1-d1-d4 triangle and the
+    /// first piece that is not on the diagonal is below it.
+    fn encode_leading_pieces(&self, squares: &mut [usize], data: &PairsData) -> u64 {
+        if squares[0] / 8 > 3 {
+            squares.iter_mut().for_each(|square| *square ^= 56);
+        }
+
+        for index in 0..data.group_len[0] {
+            let offset = off_diagonal(squares[index]);
+            if offset == 0 {
+                continue;
+            }
+
+            if offset > 0 {
+                squares[index..]
+                    .iter_mut()
+                    .for_each(|square| *square = flip_diagonal(*square));
+            }
+
+            break;
+        }
+
+        if !self.has_unique_pieces {
+            return MAP_KK[MAP_A1D1D4[squares[0]] as usize][squares[1]];
+        }
+
+        let rank = |square: usize| (square / 8) as u64;
+        let adjust1 = (squares[1] > squares[0]) as usize;
+        let adjust2 = (squares[2] > squares[0]) as usize + (squares[2] > squares[1]) as usize;
+
+        if off_diagonal(squares[0]) != 0 {
+            (MAP_A1D1D4[squares[0]] * 63 + (squares[1] - adjust1) as u64) * 62
+                + (squares[2] - adjust2) as u64
+        } else if off_diagonal(squares[1]) != 0 {
+            (6 * 63 + rank(squares[0]) * 28 + MAP_B1H1H7[squares[1]]) * 62
+                + (squares[2] - adjust2) as u64
+        } else if off_diagonal(squares[2]) != 0 {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + rank(squares[0]) * 7 * 28
+                + (rank(squares[1]) - adjust1 as u64) * 28
+                + MAP_B1H1H7[squares[2]]
+        } else {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + 4 * 7 * 28
+                + rank(squares[0]) * 7 * 6
+                + (rank(squares[1]) - adjust1 as u64) * 6
+                + (rank(squares[2]) - adjust2 as u64)
+        }
+    }
+
+    /// Returns true if the DTZ table stores the given side to move. Tables
+    /// of symmetric material without pawns store both.
+    fn stores_side(&self, side: usize, file: usize) -> bool {
+        (self.sides[0][file].flags & FLAG_STM) as usize == side
+            || (self.symmetric && !self.has_pawns)
+    }
+
+    fn decompress(&self, data: &PairsData, index: u64) -> Result<u16, InvalidTable> {
+        if data.flags & FLAG_SINGLE_VALUE != 0 {
+            return Ok(data.min_symbol_length as u16);
+        }
+
+        self.decompress_pairs(data, index)
+            .ok_or_else(|| self.invalid("the compressed data is corrupted"))
+    }
+
+    /// Finds the value at the index in the compressed data. The sparse index
+    /// points close to the block of the value, which is then decoded symbol
+    /// by symbol. Each symbol expands to a pair of symbols recursively.
+    /// Source: http://www.larsson.dogma.net/dcc99.pdf
+    fn decompress_pairs(&self, data: &PairsData, index: u64) -> Option<u16> {
+        let bytes = &self.data;
+
+        let entry = data.sparse_index + 6 * (index / data.span) as usize;
+        let mut block = read_u32(bytes, entry)? as usize;
+        let mut offset = read_u16(bytes, entry + 4)? as i64;
+        offset += (index % data.span) as i64 - (data.span / 2) as i64;
+
+        let block_length =
+            |block: usize| read_u16(bytes, data.block_lengths + 2 * block).map(i64::from);
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        let mut pointer = data.data + block * data.block_size;
+        let mut buffer = read_u64_be(bytes, pointer)?;
+        let mut buffer_size = 64;
+        pointer += 8;
+
+        let min_symbol_length = data.min_symbol_length as usize;
+        let mut symbol;
+        loop {
+            let mut length = 0;
+            while buffer < *data.base.get(length)? {
+                length += 1;
+            }
+
+            symbol = ((buffer - data.base[length]) >> (64 - length - min_symbol_length)) as usize;
+            symbol += read_u16(bytes, data.lowest_symbol + 2 * length)? as usize;
+
+            let values = *data.symbol_length.get(symbol)? as i64 + 1;
+            if offset < values {
+                break;
+            }
+
+            offset -= values;
+            length += min_symbol_length;
+            buffer = buffer.checked_shl(length as u32).unwrap_or(0);
+            buffer_size = usize::checked_sub(buffer_size, length)?;
+
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= (read_u32_be(bytes, pointer)? as u64) << (64 - buffer_size);
+                pointer += 4;
+            }
+        }
+
+        while data.symbol_length[symbol] != 0 {
+            let (left, right) = children(bytes, data.btree, symbol)?;
+
+            let values = *data.symbol_length.get(left)? as i64 + 1;
+            if offset < values {
+                symbol = left;
+            } else {
+                offset -= values;
+                symbol = right;
+            }
+
+            data.symbol_length.get(symbol)?;
+        }
+
+        children(bytes, data.btree, symbol).map(|(left, _)| left as u16)
+    }
+
+    #[inline(always)]
+    fn read_u8(&self, offset: usize) -> Option<u8> {
+        read_u8(&self.data, offset)
+    }
+
+    fn invalid(&self, reason: &str) -> InvalidTable {
+        InvalidTable::new(&self.name, reason)
+    }
+}
+
+/// Computes how many values the symbol expands to, minus one. Symbols
+/// without a right child are leaves that stand for a single value.
+fn symbol_length(
+    bytes: &[u8],
+    btree: usize,
+    lengths: &mut [u8],
+    visited: &mut [bool],
+    symbol: usize,
+) -> Option<u8> {
+    visited[symbol] = true;
+
+    let (left, right) = children(bytes, btree, symbol)?;
+    if right == 0xFFF {
+        return Some(0);
+    }
+
+    for child in [left, right] {
+        if !*visited.get(child)? {
+            lengths[child] = symbol_length(bytes, btree, lengths, visited, child)?;
+        }
+    }
+
+    Some(lengths[left].wrapping_add(lengths[right]).wrapping_add(1))
+}
+
+/// Reads the two children of a symbol, which are packed into 12 bits each.
+fn children(bytes: &[u8], btree: usize, symbol: usize) -> Option<(usize, usize)> {
+    let node = bytes.get(btree + 3 * symbol..btree + 3 * symbol + 3)?;
+    let left = ((node[1] as usize & 0xF) << 8) | node[0] as usize;
+    let right = ((node[2] as usize) << 4) | (node[1] as usize >> 4);
+
+    Some((left, right))
+}
+
+#[inline(always)]
+fn read_u8(bytes: &[u8], offset: usize) -> Option<u8> {
+    bytes.get(offset).copied()
+}
+
+#[inline(always)]
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+#[inline(always)]
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[inline(always)]
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+#[inline(always)]
+fn read_u64_be(bytes: &[u8], offset: usize) -> Option<u64> {
+    let bytes = bytes.get(offset..offset + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
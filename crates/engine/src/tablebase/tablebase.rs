@@ -0,0 +1,450 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    ops::Neg,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use base::{
+    board::{piece::Piece, Board},
+    r#move::Move,
+};
+
+use crate::{
+    evaluation::endgame::MaterialSignature,
+    generator::{AllMoves, MoveGenerator},
+};
+
+use super::{
+    error::{CastlingRights, InvalidTable, MissingTable, TablebaseError, TooManyPieces},
+    table::{Table, TableKind, MAX_PIECES},
+};
+
+// The rank of the best root move, which is far above any distance to zero.
+const MAX_DTZ: i32 = 1 << 18;
+
+/// The result of a position with perfect play. Cursed wins and blessed
+/// losses are won or lost, but drawn by the fifty move rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    pub const fn from_value(value: i32) -> Option<Self> {
+        match value {
+            -2 => Some(Wdl::Loss),
+            -1 => Some(Wdl::BlessedLoss),
+            0 => Some(Wdl::Draw),
+            1 => Some(Wdl::CursedWin),
+            2 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+
+    pub const fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    /// The distance to zero of a position in which the best move resets the
+    /// fifty move counter.
+    pub const fn dtz_before_zeroing(&self) -> i32 {
+        match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        }
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeState {
+    Normal,
+    /// The best move resets the fifty move counter, so the stored value of
+    /// the position itself might not be meaningful.
+    ZeroingBestMove,
+}
+
+/// A table file that is only read once it is probed for the first time.
+#[derive(Default)]
+struct LazyTable {
+    path: Option<PathBuf>,
+    table: OnceLock<Result<Table, String>>,
+}
+
+impl LazyTable {
+    fn get(&self, name: &str, kind: TableKind) -> Result<&Table, TablebaseError> {
+        let path = self.path.as_ref().ok_or_else(|| MissingTable::new(name))?;
+
+        let table = self.table.get_or_init(|| {
+            let data = fs::read(path).map_err(|err| err.to_string())?;
+            Table::parse(name, kind, data).map_err(|err| err.to_string())
+        });
+
+        table
+            .as_ref()
+            .map_err(|reason| InvalidTable::new(name, reason.as_str()).into())
+    }
+}
+
+#[derive(Default)]
+struct TableEntry {
+    name: String,
+    wdl: LazyTable,
+    dtz: LazyTable,
+}
+
+/// The Syzygy tablebases, which store the result (WDL) and the distance to
+/// the next capture or pawn move (DTZ) of every endgame up to seven pieces.
+/// Source: https://www.chessprogramming.org/Syzygy_Bases
+#[derive(Default)]
+pub struct Tablebase {
+    entries: Vec<TableEntry>,
+    /// Both colorings of every endgame point to the same entry.
+    indices: HashMap<MaterialSignature, usize>,
+    max_pieces: usize,
+}
+
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("tables", &self.entries.len())
+            .field("max_pieces", &self.max_pieces)
+            .finish()
+    }
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds all tables of the directory and returns how many files were
+    /// found. The files are only read once they are probed.
+    pub fn add_directory(&mut self, path: impl AsRef<Path>) -> Result<usize, TablebaseError> {
+        let mut added = 0;
+
+        for file in fs::read_dir(path)? {
+            let path = file?.path();
+            let kind = match path.extension().and_then(|extension| extension.to_str()) {
+                Some(extension) if extension == TableKind::Wdl.extension() => TableKind::Wdl,
+                Some(extension) if extension == TableKind::Dtz.extension() => TableKind::Dtz,
+                _ => continue,
+            };
+
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            let signature = match MaterialSignature::from_name(&name) {
+                Some(signature) if signature.piece_count() <= MAX_PIECES => signature,
+                _ => continue,
+            };
+
+            let index = *self.indices.entry(signature).or_insert_with(|| {
+                self.entries.push(TableEntry {
+                    name: name.clone(),
+                    ..Default::default()
+                });
+                self.entries.len() - 1
+            });
+            self.indices.insert(signature.flipped(), index);
+
+            let entry = &mut self.entries[index];
+            match kind {
+                TableKind::Wdl => entry.wdl.path = Some(path),
+                TableKind::Dtz => entry.dtz.path = Some(path),
+            }
+
+            self.max_pieces = self.max_pieces.max(signature.piece_count());
+            added += 1;
+        }
+
+        Ok(added)
+    }
+
+    /// The most pieces of any available table.
+    #[inline(always)]
+    pub const fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    #[inline(always)]
+    pub fn table_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the board might be covered by the tables. The tables
+    /// do not store positions with castling rights.
+    pub fn can_probe(&self, board: &Board) -> bool {
+        !has_castling_rights(board) && board.get_all_occupied().count_ones() <= self.max_pieces
+    }
+
+    /// Probes the result of the board for the side to move.
+    pub fn probe_wdl(&self, board: &mut Board) -> Result<Wdl, TablebaseError> {
+        self.check_board(board)?;
+
+        let (wdl, _) = self.search(board, false)?;
+        Ok(wdl)
+    }
+
+    /// Probes the distance to the next capture or pawn move in plies, which
+    /// is positive if the side to move wins and negative if it loses. The
+    /// distance of cursed wins and blessed losses is offset by 100.
+    pub fn probe_dtz(&self, board: &mut Board) -> Result<i32, TablebaseError> {
+        self.check_board(board)?;
+
+        self.dtz(board)
+    }
+
+    /// Filters the moves down to the ones which keep the best result and
+    /// reach it the fastest, so that the search cannot spoil a won endgame
+    /// by running into the fifty move rule.
+    pub fn best_root_moves(
+        &self,
+        board: &mut Board,
+        moves: &[Move],
+    ) -> Result<Vec<Move>, TablebaseError> {
+        self.check_board(board)?;
+
+        let halfmoves = board.halfmoves() as i32;
+
+        let mut ranked = Vec::with_capacity(moves.len());
+        for &mov in moves {
+            board.make(mov);
+            let dtz = self.root_move_dtz(board);
+            board.unmake(mov);
+            let dtz = dtz?;
+
+            // Wins within the fifty move rule are ranked by their distance,
+            // the same goes for losses that cannot be saved by it. Otherwise
+            // the side that is losing wants to get closer to the draw.
+            let rank = match dtz {
+                dtz if dtz > 0 && dtz + halfmoves <= 99 => MAX_DTZ - dtz,
+                dtz if dtz > 0 => MAX_DTZ / 2 - (dtz + halfmoves),
+                dtz if dtz < 0 && -dtz * 2 + halfmoves < 100 => -MAX_DTZ - dtz,
+                dtz if dtz < 0 => -MAX_DTZ / 2 + (-dtz + halfmoves),
+                _ => 0,
+            };
+
+            ranked.push((mov, rank));
+        }
+
+        let best = ranked.iter().map(|&(_, rank)| rank).max();
+        let moves = ranked
+            .into_iter()
+            .filter(|&(_, rank)| Some(rank) == best)
+            .map(|(mov, _)| mov)
+            .collect();
+
+        Ok(moves)
+    }
+
+    /// The distance to zero of the position after a root move, seen from
+    /// the side that made the move.
+    fn root_move_dtz(&self, board: &mut Board) -> Result<i32, TablebaseError> {
+        let dtz = if board.halfmoves() == 0 {
+            let (wdl, _) = self.search(board, false)?;
+            (-wdl).dtz_before_zeroing()
+        } else if board.is_draw(1) {
+            0
+        } else {
+            let dtz = -self.dtz(board)?;
+            dtz + dtz.signum()
+        };
+
+        // A mate is always the fastest way to win.
+        if dtz == 2 && is_checkmate(board) {
+            return Ok(1);
+        }
+
+        Ok(dtz)
+    }
+
+    /// Searches the captures, and the pawn moves if the zeroing moves are
+    /// checked, before probing the table. The tables do not store positions
+    /// with en passant and store a wrong value if the best move is a capture
+    /// or a pawn move.
+    fn search(
+        &self,
+        board: &mut Board,
+        zeroing_moves: bool,
+    ) -> Result<(Wdl, ProbeState), TablebaseError> {
+        let moves = MoveGenerator::<AllMoves>::new(board);
+        let total = moves.len();
+
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for mov in moves {
+            let is_pawn_move =
+                board.get_tile(mov.from()).map(|tile| tile.piece) == Some(Piece::Pawn);
+            let is_searched = mov.is_capture() || (zeroing_moves && is_pawn_move);
+            if !is_searched {
+                continue;
+            }
+
+            searched += 1;
+
+            board.make(mov);
+            let result = self.search(board, false);
+            board.unmake(mov);
+
+            let (wdl, _) = result?;
+            let wdl = -wdl;
+            if wdl > best {
+                best = wdl;
+                if wdl == Wdl::Win {
+                    return Ok((wdl, ProbeState::ZeroingBestMove));
+                }
+            }
+        }
+
+        // If all moves have been searched, the stored value is not needed.
+        let no_more_moves = searched > 0 && searched == total;
+        let wdl = match no_more_moves {
+            true => best,
+            false => self.probe_table_wdl(board)?,
+        };
+
+        if best >= wdl {
+            let state = match best > Wdl::Draw || no_more_moves {
+                true => ProbeState::ZeroingBestMove,
+                false => ProbeState::Normal,
+            };
+
+            return Ok((best, state));
+        }
+
+        Ok((wdl, ProbeState::Normal))
+    }
+
+    fn dtz(&self, board: &mut Board) -> Result<i32, TablebaseError> {
+        let (wdl, state) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+
+        if state == ProbeState::ZeroingBestMove {
+            return Ok(wdl.dtz_before_zeroing());
+        }
+
+        let signature = MaterialSignature::new(board);
+        let table = self.entry(&signature)?;
+        let dtz = table
+            .dtz
+            .get(&table.name, TableKind::Dtz)?
+            .probe_dtz(board, wdl)?;
+
+        if let Some(dtz) = dtz {
+            let offset = match wdl {
+                Wdl::BlessedLoss | Wdl::CursedWin => 100,
+                _ => 0,
+            };
+
+            return Ok((dtz + offset) * wdl.value().signum());
+        }
+
+        // The table only stores the other side to move, so the best move
+        // has to be found by searching one ply.
+        let mut best = None;
+        for mov in MoveGenerator::<AllMoves>::new(board) {
+            let is_zeroing = mov.is_capture()
+                || board.get_tile(mov.from()).map(|tile| tile.piece) == Some(Piece::Pawn);
+
+            board.make(mov);
+            let result = match is_zeroing {
+                true => self
+                    .search(board, false)
+                    .map(|(wdl, _)| -wdl.dtz_before_zeroing()),
+                false => self.dtz(board).map(|dtz| -dtz),
+            };
+            let is_mate = is_checkmate(board);
+            board.unmake(mov);
+
+            let mut dtz = result?;
+            if dtz == 1 && is_mate {
+                best = Some(1);
+            }
+
+            if !is_zeroing {
+                dtz += dtz.signum();
+            }
+
+            if dtz.signum() == wdl.value().signum() && best.is_none_or(|best| dtz < best) {
+                best = Some(dtz);
+            }
+        }
+
+        // Without any legal moves the side to move is mated.
+        Ok(best.unwrap_or(-1))
+    }
+
+    fn probe_table_wdl(&self, board: &Board) -> Result<Wdl, TablebaseError> {
+        // The tables of the bare kings are not stored.
+        if board.get_all_occupied().count_ones() == 2 {
+            return Ok(Wdl::Draw);
+        }
+
+        let signature = MaterialSignature::new(board);
+        let table = self.entry(&signature)?;
+        Ok(table
+            .wdl
+            .get(&table.name, TableKind::Wdl)?
+            .probe_wdl(board)?)
+    }
+
+    fn entry(&self, signature: &MaterialSignature) -> Result<&TableEntry, TablebaseError> {
+        let index = self
+            .indices
+            .get(signature)
+            .ok_or_else(|| MissingTable::new(signature.to_string()))?;
+
+        Ok(&self.entries[*index])
+    }
+
+    fn check_board(&self, board: &Board) -> Result<(), TablebaseError> {
+        if has_castling_rights(board) {
+            return Err(CastlingRights.into());
+        }
+
+        let pieces = board.get_all_occupied().count_ones();
+        if pieces > self.max_pieces {
+            return Err(TooManyPieces::new(pieces, self.max_pieces).into());
+        }
+
+        Ok(())
+    }
+}
+
+fn has_castling_rights(board: &Board) -> bool {
+    board.can_white_kingside()
+        || board.can_white_queenside()
+        || board.can_black_kingside()
+        || board.can_black_queenside()
+}
+
+fn is_checkmate(board: &Board) -> bool {
+    board.is_check() && !board.has_legal_moves()
+}
@@ -0,0 +1,216 @@
+#[cfg(test)]
+mod table {
+    use std::fs;
+
+    use base::testing::board;
+
+    use crate::tablebase::{
+        table::{Table, TableKind},
+        Wdl,
+    };
+
+    const TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tablebases");
+
+    fn read(name: &str, kind: TableKind) -> Vec<u8> {
+        fs::read(format!("{TABLES}/{name}.{}", kind.extension())).unwrap()
+    }
+
+    #[test]
+    fn rejects_wrong_magic() {
+        let data = read("KRvK", TableKind::Wdl);
+        assert!(Table::parse("KRvK", TableKind::Dtz, data).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_material() {
+        // The layout of a table with pawns does not match one without.
+        let data = read("KRvK", TableKind::Wdl);
+        assert!(Table::parse("KPvK", TableKind::Wdl, data.clone()).is_err());
+        assert!(Table::parse("KRvKR", TableKind::Wdl, data.clone()).is_err());
+        assert!(Table::parse("KRRRRRRvK", TableKind::Wdl, data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let mut data = read("KQvKR", TableKind::Wdl);
+        data.truncate(data.len() / 2);
+        assert!(Table::parse("KQvKR", TableKind::Wdl, data).is_err());
+    }
+
+    #[test]
+    fn probes_single_table() {
+        let table = Table::parse("KRvK", TableKind::Wdl, read("KRvK", TableKind::Wdl)).unwrap();
+        assert_eq!(table.name(), "KRvK");
+
+        let white = board("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1");
+        assert_eq!(table.probe_wdl(&white).unwrap(), Wdl::Win);
+
+        // The same table covers the colors swapped.
+        let black = board("8/5K2/8/1k6/2r5/8/8/8 w - - 0 1");
+        assert_eq!(table.probe_wdl(&black).unwrap(), Wdl::Loss);
+    }
+}
+
+#[cfg(test)]
+mod probe {
+    use base::{board::Board, r#move::Move, testing::board};
+
+    use crate::{
+        generator::{AllMoves, MoveGenerator},
+        tablebase::{error::TablebaseError, Tablebase, Wdl},
+    };
+
+    const TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tablebases");
+
+    // Positions with their result and distance to zero for the side to move, taken from
+    // tests/regular.csv of shakmaty-syzygy and cross-checked with shakmaty-syzygy 0.28.1.
+    const POSITIONS: [(&str, Wdl, i32); 14] = [
+        ("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1", Wdl::Win, 21),
+        ("6q1/8/6K1/4R3/1k6/8/8/8 w - - 0 1", Wdl::Loss, -46),
+        ("K7/3q4/5R2/5k2/8/8/8/8 b - - 0 1", Wdl::Win, 1),
+        ("7K/6p1/8/8/k1R5/8/8/8 b - - 0 1", Wdl::Loss, -4),
+        ("8/1p3K2/4R3/8/8/5k2/8/8 w - - 0 1", Wdl::Win, 3),
+        ("8/8/3P1K1p/1k6/8/8/8/8 b - - 0 1", Wdl::Loss, -3),
+        ("8/5p2/6k1/K7/8/8/8/8 w - - 0 1", Wdl::Loss, -2),
+        ("8/3p4/5p2/2K5/8/8/8/2k5 b - - 0 1", Wdl::Win, 1),
+        ("8/3k4/8/8/8/8/4P3/3K4 w - - 0 1", Wdl::Draw, 0),
+        ("R7/8/6k1/8/8/8/2K4r/8 w - - 0 1", Wdl::Draw, 0),
+        ("8/8/3k4/8/8/6KN/3N4/8 b - - 0 1", Wdl::Draw, 0),
+        ("8/8/K4k2/7q/8/8/7Q/8 b - - 0 1", Wdl::Win, 1),
+        ("8/8/5k2/8/2K5/3N4/2B5/8 w - - 0 1", Wdl::Win, 48),
+        ("3B4/K7/8/k3N3/8/8/8/8 b - - 0 1", Wdl::Loss, -53),
+    ];
+
+    fn tablebase() -> Tablebase {
+        let mut tablebase = Tablebase::new();
+        tablebase.add_directory(TABLES).unwrap();
+        tablebase
+    }
+
+    #[test]
+    fn add_directory() {
+        let tablebase = tablebase();
+        assert_eq!(tablebase.max_pieces(), 4);
+        assert!(tablebase.table_count() > 0);
+
+        assert!(tablebase.can_probe(&board("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1")));
+        assert!(!tablebase.can_probe(&board(Board::STARTPOS_FEN)));
+        assert!(!tablebase.can_probe(&board("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1")));
+    }
+
+    #[test]
+    fn missing_directory() {
+        let mut tablebase = Tablebase::new();
+        assert!(tablebase.add_directory("/does/not/exist").is_err());
+        assert_eq!(tablebase.max_pieces(), 0);
+    }
+
+    #[test]
+    fn wdl() {
+        let tablebase = tablebase();
+        for (fen, wdl, _) in POSITIONS {
+            let mut board = board(fen);
+            assert_eq!(tablebase.probe_wdl(&mut board).unwrap(), wdl, "{fen}");
+        }
+    }
+
+    #[test]
+    fn dtz() {
+        let tablebase = tablebase();
+        for (fen, _, dtz) in POSITIONS {
+            let mut board = board(fen);
+            assert_eq!(tablebase.probe_dtz(&mut board).unwrap(), dtz, "{fen}");
+        }
+    }
+
+    #[test]
+    fn probing_keeps_board() {
+        let tablebase = tablebase();
+        let mut board = board("8/1p3K2/4R3/8/8/5k2/8/8 w - - 0 1");
+        let fen = board.to_fen();
+
+        tablebase.probe_dtz(&mut board).unwrap();
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn bare_kings() {
+        let tablebase = tablebase();
+        let mut board = board("8/8/3k4/8/8/3K4/8/8 w - - 0 1");
+        assert_eq!(tablebase.probe_wdl(&mut board).unwrap(), Wdl::Draw);
+        assert_eq!(tablebase.probe_dtz(&mut board).unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_positions() {
+        let tablebase = tablebase();
+
+        let mut castling = board("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        assert!(matches!(
+            tablebase.probe_wdl(&mut castling),
+            Err(TablebaseError::CastlingRights(_))
+        ));
+
+        let mut too_many = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+        assert!(matches!(
+            tablebase.probe_wdl(&mut too_many),
+            Err(TablebaseError::TooManyPieces(_))
+        ));
+
+        let mut missing = board("4k3/8/8/8/8/8/8/QQ2K3 w - - 0 1");
+        assert!(matches!(
+            tablebase.probe_wdl(&mut missing),
+            Err(TablebaseError::MissingTable(_))
+        ));
+    }
+
+    #[test]
+    fn root_moves_convert_win() {
+        let tablebase = tablebase();
+        let mut board = board("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1");
+        let moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+
+        let best = tablebase.best_root_moves(&mut board, &moves).unwrap();
+        assert!(!best.is_empty());
+        assert!(best.len() < moves.len());
+
+        // Every remaining move keeps the win and gets closer to the mate.
+        for mov in best {
+            board.make(mov);
+            assert_eq!(tablebase.probe_wdl(&mut board).unwrap(), Wdl::Loss);
+            assert_eq!(tablebase.probe_dtz(&mut board).unwrap(), -20);
+            board.unmake(mov);
+        }
+    }
+
+    #[test]
+    fn root_moves_prefer_mate() {
+        let tablebase = tablebase();
+        let mut board = board("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1");
+        let moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+
+        let best = tablebase.best_root_moves(&mut board, &moves).unwrap();
+        assert!(!best.is_empty());
+
+        for mov in best {
+            board.make(mov);
+            assert!(board.is_check() && !board.has_legal_moves());
+            board.unmake(mov);
+        }
+    }
+
+    #[test]
+    fn root_moves_avoid_draw() {
+        // Not every move of the rook keeps the win against the pawn.
+        let tablebase = tablebase();
+        let mut board = board("8/1p3K2/4R3/8/8/5k2/8/8 w - - 0 1");
+        let moves = MoveGenerator::<AllMoves>::new(&board).collect::<Vec<Move>>();
+
+        let best = tablebase.best_root_moves(&mut board, &moves).unwrap();
+        for mov in best {
+            board.make(mov);
+            assert_eq!(tablebase.probe_wdl(&mut board).unwrap(), Wdl::Loss);
+            board.unmake(mov);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    generators::moves::{generate_king_moves, generate_pawn_attacks},
+    utils::{
+        kpk::{kpk_index, KPK_POSITIONS, KPK_SIZE},
+        utils::{index, rank_file},
+    },
+    BOARD_SIZE,
+};
+
+const WHITE: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KpkResult {
+    Unknown,
+    Invalid,
+    Draw,
+    Win,
+}
+
+/// The squares the king and the pawn can attack, from the view of white.
+struct Attacks {
+    king: [u64; BOARD_SIZE],
+    pawn: [u64; BOARD_SIZE],
+}
+
+/// Generates the KPK bitbase by retrograde analysis. Positions that are
+/// won or drawn right away are classified first, then the results are
+/// propagated to the positions that lead to them until nothing changes.
+/// Positions that are never decided are draws.
+/// Source: https://www.chessprogramming.org/Retrograde_Analysis
+pub fn generate_kpk_bitbase() -> [u64; KPK_SIZE] {
+    let attacks = Attacks {
+        king: generate_king_moves(),
+        pawn: generate_pawn_attacks()[WHITE],
+    };
+
+    let mut results = vec![KpkResult::Unknown; KPK_POSITIONS];
+    for_each_position(|white_to_move, white_king, black_king, pawn| {
+        let index = kpk_index(white_to_move, white_king, black_king, pawn);
+        results[index] = classify(&attacks, white_to_move, white_king, black_king, pawn);
+    });
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for_each_position(|white_to_move, white_king, black_king, pawn| {
+            let index = kpk_index(white_to_move, white_king, black_king, pawn);
+            if results[index] != KpkResult::Unknown {
+                return;
+            }
+
+            let result = match white_to_move {
+                true => white_successors(&attacks, &results, white_king, black_king, pawn),
+                false => black_successors(&attacks, &results, white_king, black_king, pawn),
+            };
+
+            if result != KpkResult::Unknown {
+                results[index] = result;
+                changed = true;
+            }
+        });
+    }
+
+    let mut bitbase = [0; KPK_SIZE];
+    for (index, result) in results.iter().enumerate() {
+        if *result == KpkResult::Win {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    bitbase
+}
+
+fn for_each_position(mut function: impl FnMut(bool, usize, usize, usize)) {
+    for file in 0..4 {
+        for rank in 1..7 {
+            let pawn = index(rank, file);
+            for white_king in 0..BOARD_SIZE {
+                for black_king in 0..BOARD_SIZE {
+                    function(true, white_king, black_king, pawn);
+                    function(false, white_king, black_king, pawn);
+                }
+            }
+        }
+    }
+}
+
+/// The result of the position if it is known without looking at the moves.
+fn classify(
+    attacks: &Attacks,
+    white_to_move: bool,
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+) -> KpkResult {
+    if white_king == black_king
+        || white_king == pawn
+        || black_king == pawn
+        || attacks.king[white_king] & (1 << black_king) != 0
+    {
+        return KpkResult::Invalid;
+    }
+
+    let in_check = attacks.pawn[pawn] & (1 << black_king) != 0;
+    if white_to_move {
+        if in_check {
+            return KpkResult::Invalid;
+        }
+
+        // The pawn promotes and the queen can't be taken right away.
+        let (rank, _) = rank_file(pawn);
+        let promotion = pawn + 8;
+        if rank == 6
+            && promotion != white_king
+            && promotion != black_king
+            && (attacks.king[black_king] & (1 << promotion) == 0
+                || attacks.king[white_king] & (1 << promotion) != 0)
+        {
+            return KpkResult::Win;
+        }
+
+        return KpkResult::Unknown;
+    }
+
+    let moves = attacks.king[black_king] & !(attacks.king[white_king] | attacks.pawn[pawn]);
+    if moves & (1 << pawn) != 0 {
+        return KpkResult::Draw;
+    }
+
+    if moves == 0 {
+        return match in_check {
+            true => KpkResult::Win,
+            false => KpkResult::Draw,
+        };
+    }
+
+    KpkResult::Unknown
+}
+
+/// White wins if any move wins and draws if every move draws.
+fn white_successors(
+    attacks: &Attacks,
+    results: &[KpkResult],
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+) -> KpkResult {
+    let mut successors = Vec::new();
+
+    let mut king_moves = attacks.king[white_king];
+    while king_moves != 0 {
+        let to = king_moves.trailing_zeros() as usize;
+        king_moves &= king_moves - 1;
+        successors.push(results[kpk_index(false, to, black_king, pawn)]);
+    }
+
+    // The promotion is already classified, so only the pushes on the board
+    // are left.
+    let (rank, _) = rank_file(pawn);
+    let push = pawn + 8;
+    if rank < 6 && push != white_king && push != black_king {
+        successors.push(results[kpk_index(false, white_king, black_king, push)]);
+
+        let double_push = push + 8;
+        if rank == 1 && double_push != white_king && double_push != black_king {
+            successors.push(results[kpk_index(false, white_king, black_king, double_push)]);
+        }
+    }
+
+    combine(successors, KpkResult::Win, KpkResult::Draw)
+}
+
+/// Black draws if any move draws and loses if every move loses.
+fn black_successors(
+    attacks: &Attacks,
+    results: &[KpkResult],
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+) -> KpkResult {
+    let mut successors = Vec::new();
+
+    let mut king_moves = attacks.king[black_king];
+    while king_moves != 0 {
+        let to = king_moves.trailing_zeros() as usize;
+        king_moves &= king_moves - 1;
+        successors.push(results[kpk_index(true, white_king, to, pawn)]);
+    }
+
+    combine(successors, KpkResult::Draw, KpkResult::Win)
+}
+
+/// The result of the side to move is `good` if one of the legal moves leads
+/// to it, and `bad` if all of them do.
+fn combine(successors: Vec<KpkResult>, good: KpkResult, bad: KpkResult) -> KpkResult {
+    let legal = successors
+        .into_iter()
+        .filter(|result| *result != KpkResult::Invalid)
+        .collect::<Vec<KpkResult>>();
+
+    if legal.contains(&good) {
+        good
+    } else if legal.iter().all(|result| *result == bad) {
+        bad
+    } else {
+        KpkResult::Unknown
+    }
+}
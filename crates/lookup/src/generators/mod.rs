@@ -1,3 +1,4 @@
 pub(crate) mod generic;
+pub(crate) mod kpk;
 pub(crate) mod magic;
 pub(crate) mod moves;
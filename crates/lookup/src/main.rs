@@ -10,6 +10,7 @@ use generators::moves::generate_pawn_attacks;
 
 use crate::generators::{
     generic::{generate_adjacent_files, generate_between, generate_lines, generate_rays},
+    kpk::generate_kpk_bitbase,
     magic::{
         generate_bishop_attacks, generate_bishop_magics, generate_bishop_mask_ones,
         generate_bishop_masks, generate_rook_attacks, generate_rook_magics,
@@ -113,6 +114,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         .collect::<Vec<Vec<u64>>>();
     write_two_dimensional("BISHOP_MAGIC_ATTACKS", b_magic_attacks_as_vec, &mut output)?;
 
+    let kpk_bitbase = generate_kpk_bitbase();
+    write_one_dimensional("KPK_BITBASE", kpk_bitbase.to_vec(), &mut output)?;
+
     let path = Path::new("./src/tables/generated.rs");
     std::fs::write(path, output)?;
 
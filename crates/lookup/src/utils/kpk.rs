@@ -0,0 +1,22 @@
+use super::utils::rank_file;
+
+// The bitbase only stores positions where white has the pawn on the files a
+// to d, the others are mirrored. The pawn can stand on the ranks 2 to 7.
+pub const KPK_PAWN_SQUARES: usize = 4 * 6;
+pub const KPK_POSITIONS: usize = KPK_PAWN_SQUARES * 2 * 64 * 64;
+pub const KPK_SIZE: usize = KPK_POSITIONS / 64;
+
+/// The index of the position in the KPK bitbase, in which the bit is set if
+/// white wins. The pawn has to be on the files a to d and the ranks 2 to 7.
+#[inline(always)]
+pub const fn kpk_index(
+    white_to_move: bool,
+    white_king: usize,
+    black_king: usize,
+    pawn: usize,
+) -> usize {
+    let (rank, file) = rank_file(pawn);
+    let pawn_index = file * 6 + (rank - 1);
+
+    ((pawn_index * 2 + white_to_move as usize) * 64 + white_king) * 64 + black_king
+}
@@ -1,2 +1,3 @@
 pub mod direction;
+pub mod kpk;
 pub(crate) mod utils;
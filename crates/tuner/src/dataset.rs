@@ -0,0 +1,218 @@
+use std::{fs, path::Path};
+
+use base::{board::Board, zobrist::ZobristHasher};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    error::{EmptyDataset, InvalidResult, TunerError},
+    san::parse_san,
+};
+
+/// A position labelled with the result of the game it was played in, from
+/// the view of white.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// Loads the positions of an EPD file, or of all the games of a PGN file if
+/// the file ends with `.pgn`. Positions where the side to move is in check
+/// are skipped, as they can't be evaluated statically.
+pub fn load(path: impl AsRef<Path>, skip_plies: usize) -> Result<Vec<Entry>, TunerError> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)?;
+
+    let is_pgn = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+    let entries = if is_pgn {
+        parse_pgn(&content, skip_plies)?
+    } else {
+        parse_epd(&content)?
+    };
+
+    if entries.is_empty() {
+        return Err(EmptyDataset.into());
+    }
+
+    Ok(entries)
+}
+
+/// Parses lines like `<fen> c9 "1-0";`, `<fen> [0.5]`, `<fen> 1/2-1/2` or
+/// `<fen> | <score> | <result>` of the data generator. The move counters of
+/// the FEN are optional, just like in EPD.
+pub fn parse_epd(content: &str) -> Result<Vec<Entry>, TunerError> {
+    let hasher = hasher();
+
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        if tokens.len() < 4 {
+            return Err(InvalidResult::new(line).into());
+        }
+
+        let mut fen = tokens[..4].join(" ");
+        let has_counters = tokens.len() >= 6
+            && tokens[4].parse::<u16>().is_ok()
+            && tokens[5].parse::<u16>().is_ok();
+        match has_counters {
+            true => fen = format!("{} {} {}", fen, tokens[4], tokens[5]),
+            false => fen.push_str(" 0 1"),
+        }
+
+        let result = parse_epd_result(line).ok_or_else(|| InvalidResult::new(line))?;
+        let board = Board::from_str(&fen, hasher.clone())?;
+        if !board.is_check() {
+            entries.push(Entry { board, result });
+        }
+    }
+
+    Ok(entries)
+}
+
+fn parse_epd_result(line: &str) -> Option<f64> {
+    if let Some((_, result)) = line.rsplit_once('|') {
+        return result.trim().parse::<f64>().ok();
+    }
+
+    if let Some(start) = line.find('[') {
+        let end = line[start..].find(']')? + start;
+        return line[start + 1..end].trim().parse::<f64>().ok();
+    }
+
+    if let Some(start) = line.find("c9") {
+        let result = line[start + 2..].trim().trim_end_matches(';');
+        return parse_result(result.trim_matches('"'));
+    }
+
+    line.split_whitespace().rev().find_map(parse_result)
+}
+
+fn parse_result(result: &str) -> Option<f64> {
+    match result {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => None,
+    }
+}
+
+/// Parses all the games and labels every position after the first plies
+/// with the result of the game. Games without a result are skipped.
+pub fn parse_pgn(content: &str, skip_plies: usize) -> Result<Vec<Entry>, TunerError> {
+    let hasher = hasher();
+
+    let mut entries = Vec::new();
+    let mut headers = Vec::new();
+    let mut movetext = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            // A header after the moves starts the next game.
+            if !movetext.is_empty() {
+                parse_game(&headers, &movetext, skip_plies, &hasher, &mut entries)?;
+                headers.clear();
+                movetext.clear();
+            }
+
+            headers.push(line.to_string());
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    if !movetext.trim().is_empty() {
+        parse_game(&headers, &movetext, skip_plies, &hasher, &mut entries)?;
+    }
+
+    Ok(entries)
+}
+
+fn parse_game(
+    headers: &[String],
+    movetext: &str,
+    skip_plies: usize,
+    hasher: &ZobristHasher,
+    entries: &mut Vec<Entry>,
+) -> Result<(), TunerError> {
+    let Some(result) = header(headers, "Result").and_then(parse_result) else {
+        return Ok(());
+    };
+
+    let mut board = match header(headers, "FEN") {
+        Some(fen) => Board::from_str(fen, hasher.clone())?,
+        None => Board::default(hasher.clone()),
+    };
+
+    for (ply, token) in movetext_tokens(movetext).into_iter().enumerate() {
+        // A move that can't be parsed ends the game, as all the following
+        // positions would be wrong.
+        let Some(mov) = parse_san(&board, &token) else {
+            break;
+        };
+
+        board.make(mov);
+        if ply + 1 >= skip_plies && !board.is_check() {
+            entries.push(Entry {
+                board: board.clone(),
+                result,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn header<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    headers.iter().find_map(|header| {
+        let header = header.trim_start_matches('[').trim_end_matches(']');
+        let (key, value) = header.split_once(' ')?;
+        (key == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Returns only the moves of the movetext, without the comments, variations,
+/// move numbers, annotations and the result.
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut cleaned = String::with_capacity(movetext.len());
+    let mut comment = false;
+    let mut line_comment = false;
+    let mut variations = 0;
+
+    for char in movetext.chars() {
+        match char {
+            '{' if !line_comment => comment = true,
+            '}' if comment => comment = false,
+            ';' if !comment => line_comment = true,
+            '\n' if line_comment => line_comment = false,
+            '(' if !comment && !line_comment => variations += 1,
+            ')' if !comment && !line_comment => variations -= 1,
+            _ if !comment && !line_comment && variations == 0 => cleaned.push(char),
+            _ => {}
+        }
+    }
+
+    cleaned
+        .split_whitespace()
+        .filter(|token| parse_result(token).is_none() && *token != "*")
+        .map(|token| match token.starts_with("0-0") {
+            true => token,
+            false => token.trim_start_matches(|char: char| char.is_ascii_digit() || char == '.'),
+        })
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn hasher() -> ZobristHasher {
+    let mut rand = StdRng::seed_from_u64(42);
+    ZobristHasher::random(&mut rand)
+}
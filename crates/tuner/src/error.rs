@@ -0,0 +1,46 @@
+use thiserror::Error;
+
+use base::board::error::BoardError;
+use engine::evaluation::error::ParameterError;
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub enum TunerError {
+    IOError(#[from] std::io::Error),
+    BoardError(#[from] BoardError),
+    ParameterError(#[from] ParameterError),
+    ParseIntError(#[from] std::num::ParseIntError),
+    InvalidResult(#[from] InvalidResult),
+    InvalidArgument(#[from] InvalidArgument),
+    EmptyDataset(#[from] EmptyDataset),
+}
+
+#[derive(Debug, Error)]
+#[error("the line '{line}' has no valid game result")]
+pub struct InvalidResult {
+    line: String,
+}
+
+impl InvalidResult {
+    pub fn new(line: impl Into<String>) -> Self {
+        Self { line: line.into() }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("passed an invalid argument '{argument}'")]
+pub struct InvalidArgument {
+    argument: String,
+}
+
+impl InvalidArgument {
+    pub fn new(argument: impl Into<String>) -> Self {
+        Self {
+            argument: argument.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("the dataset does not contain any positions")]
+pub struct EmptyDataset;
@@ -0,0 +1,159 @@
+use std::{env, thread, time::Instant};
+
+use engine::evaluation::parameters::Parameters;
+
+use crate::{
+    error::{InvalidArgument, TunerError},
+    tuner::{Tuner, SEARCH_PARAMETERS},
+};
+
+mod dataset;
+mod error;
+mod output;
+mod san;
+mod tuner;
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: tuner <dataset> [--params <file>] [--output <prefix>] [--quiescence] \
+[--threads <count>] [--iterations <count>] [--tune <name,...>] [--skip-plies <count>]";
+
+struct Arguments {
+    dataset: String,
+    parameters: Option<String>,
+    output: String,
+    quiescence: bool,
+    threads: usize,
+    iterations: usize,
+    tune: Vec<&'static str>,
+    skip_plies: usize,
+}
+
+impl Arguments {
+    fn parse(mut arguments: impl Iterator<Item = String>) -> Result<Self, TunerError> {
+        let mut dataset = None;
+        let mut parameters = None;
+        let mut output = "tuned".to_string();
+        let mut quiescence = false;
+        let mut threads = thread::available_parallelism().map_or(1, |count| count.get());
+        let mut iterations = usize::MAX;
+        let mut tune = None;
+        let mut skip_plies = 8;
+
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| InvalidArgument::new(&argument))
+            };
+
+            match argument.as_str() {
+                "--params" => parameters = Some(value()?),
+                "--output" => output = value()?,
+                "--quiescence" => quiescence = true,
+                "--threads" => threads = value()?.parse()?,
+                "--iterations" => iterations = value()?.parse()?,
+                "--tune" => tune = Some(parse_names(&value()?)?),
+                "--skip-plies" => skip_plies = value()?.parse()?,
+                _ if dataset.is_none() && !argument.starts_with("--") => dataset = Some(argument),
+                _ => return Err(InvalidArgument::new(argument).into()),
+            }
+        }
+
+        // The search parameters don't change the evaluation, so tuning them
+        // against the game results would only waste time.
+        let tune = tune.unwrap_or_else(|| {
+            Parameters::names()
+                .into_iter()
+                .filter(|name| !SEARCH_PARAMETERS.contains(name))
+                .collect()
+        });
+
+        Ok(Self {
+            dataset: dataset.ok_or_else(|| InvalidArgument::new(USAGE))?,
+            parameters,
+            output,
+            quiescence,
+            threads,
+            iterations,
+            tune,
+            skip_plies,
+        })
+    }
+}
+
+fn parse_names(input: &str) -> Result<Vec<&'static str>, TunerError> {
+    let names = Parameters::names();
+
+    input
+        .split(',')
+        .map(|name| {
+            let name = name.trim();
+            let found = names.iter().find(|known| **known == name);
+            found
+                .copied()
+                .ok_or_else(|| InvalidArgument::new(name).into())
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let arguments = Arguments::parse(env::args().skip(1))?;
+
+    let parameters = match &arguments.parameters {
+        Some(path) => Parameters::load(path)?,
+        None => Parameters::default(),
+    };
+
+    let start = Instant::now();
+    let entries = dataset::load(&arguments.dataset, arguments.skip_plies)?;
+    println!(
+        "Loaded {} positions in {:?}",
+        entries.len(),
+        start.elapsed()
+    );
+
+    let mut tuner = Tuner::new(
+        entries,
+        parameters,
+        &arguments.tune,
+        arguments.quiescence,
+        arguments.threads,
+    );
+
+    let k = tuner.optimize_k();
+    let mut error = tuner.error();
+    println!(
+        "Tuning {} weights with K = {:.4}, starting error {:.8}",
+        tuner.weights().len(),
+        k,
+        error
+    );
+
+    for iteration in 1..=arguments.iterations {
+        let start = Instant::now();
+        let new_error = tuner.local_search_pass(error)?;
+        output::write(tuner.parameters(), &arguments.output)?;
+
+        println!(
+            "Iteration {}: error {:.8} in {:?}",
+            iteration,
+            new_error,
+            start.elapsed()
+        );
+
+        if new_error >= error {
+            break;
+        }
+
+        error = new_error;
+    }
+
+    println!(
+        "Wrote the parameters to {0}.txt and {0}.rs",
+        arguments.output
+    );
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::{fmt::Write, fs, path::Path};
+
+use engine::evaluation::parameters::Parameters;
+
+use crate::error::TunerError;
+
+/// Writes the parameters as Rust constants, in the same format as the
+/// tables of `lookup::tables::pesto`, so they can be pasted over them.
+pub fn rust_source(parameters: &Parameters) -> String {
+    let mut source = String::new();
+
+    for name in Parameters::names() {
+        let Some((rust_type, value)) = parameters.rust_constant(name) else {
+            continue;
+        };
+
+        let values = parameters.get(name).unwrap_or_default();
+        let constant = constant_name(name);
+
+        source.push_str("#[rustfmt::skip]\n");
+        // The tables are written with eight values per row, just like the
+        // board, everything else fits on a single line.
+        if rust_type.starts_with("[i32;") && values.len() > 16 {
+            writeln!(source, "pub const {constant}: {rust_type} = [").unwrap();
+            for row in values.chunks(8) {
+                let row = row
+                    .iter()
+                    .map(|value| format!("{value:>4}"))
+                    .collect::<Vec<String>>();
+                writeln!(source, "    {},", row.join(", ")).unwrap();
+            }
+            source.push_str("];\n\n");
+        } else {
+            writeln!(source, "pub const {constant}: {rust_type} = {value};\n").unwrap();
+        }
+    }
+
+    source
+}
+
+pub fn write(parameters: &Parameters, prefix: &str) -> Result<(), TunerError> {
+    parameters.save(format!("{prefix}.txt"))?;
+    fs::write(Path::new(&format!("{prefix}.rs")), rust_source(parameters))?;
+    Ok(())
+}
+
+fn constant_name(name: &str) -> String {
+    match name {
+        "midgame_piece_values" => "MIDGAME_PIECE_VALUE".to_string(),
+        "endgame_piece_values" => "ENDGAME_PIECE_VALUE".to_string(),
+        _ => name.to_uppercase(),
+    }
+}
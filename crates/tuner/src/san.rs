@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use base::{
+    board::{piece::Piece, Board},
+    r#move::{flag::MoveFlag, Move},
+    square::Square,
+};
+use engine::generator::{AllMoves, MoveGenerator};
+
+/// Finds the legal move of the board that is described by the move in
+/// standard algebraic notation, like `Nbd7`, `exd5`, `e8=Q+` or `O-O`.
+pub fn parse_san(board: &Board, input: &str) -> Option<Move> {
+    let input = input.trim_end_matches(['+', '#', '!', '?']);
+
+    let castling = match input {
+        "O-O" | "0-0" => Some(MoveFlag::KingCastle),
+        "O-O-O" | "0-0-0" => Some(MoveFlag::QueenCastle),
+        _ => None,
+    };
+    if let Some(flag) = castling {
+        return MoveGenerator::<AllMoves>::new(board).find(|mov| mov.flag() == flag);
+    }
+
+    let (piece, rest) = match input.chars().next()? {
+        'K' => (Piece::King, &input[1..]),
+        'Q' => (Piece::Queen, &input[1..]),
+        'R' => (Piece::Rook, &input[1..]),
+        'B' => (Piece::Bishop, &input[1..]),
+        'N' => (Piece::Knight, &input[1..]),
+        _ => (Piece::Pawn, input),
+    };
+
+    let (rest, promotion) = split_promotion(rest, piece)?;
+    let rest = rest.replace('x', "");
+    if rest.len() < 2 {
+        return None;
+    }
+
+    let (hints, to) = rest.split_at(rest.len() - 2);
+    let to = Square::from_str(to).ok()?;
+
+    // The hints are the file and/or the rank of the moving piece, if the
+    // move would be ambiguous otherwise.
+    let mut from_file = None;
+    let mut from_rank = None;
+    for hint in hints.bytes() {
+        match hint {
+            b'a'..=b'h' => from_file = Some(hint - b'a'),
+            b'1'..=b'8' => from_rank = Some(hint - b'1'),
+            _ => return None,
+        }
+    }
+
+    let mut candidates = MoveGenerator::<AllMoves>::new(board).filter(|mov| {
+        let moving = board.get_tile(mov.from()).map(|tile| tile.piece);
+        let promoted = mov.is_promotion().then(|| mov.flag().get_promotion_piece());
+
+        moving == Some(piece)
+            && mov.to() == to
+            && !mov.is_castling()
+            && promoted == promotion
+            && from_file.is_none_or(|file| mov.from().file() == file)
+            && from_rank.is_none_or(|rank| mov.from().rank() == rank)
+    });
+
+    let mov = candidates.next()?;
+    match candidates.next() {
+        Some(_) => None,
+        None => Some(mov),
+    }
+}
+
+/// Splits the promotion piece from the move, which is written as `e8=Q` or
+/// just `e8Q`.
+fn split_promotion(input: &str, piece: Piece) -> Option<(&str, Option<Piece>)> {
+    if piece != Piece::Pawn {
+        return Some((input, None));
+    }
+
+    let promotion = match input.chars().last()? {
+        'Q' => Piece::Queen,
+        'R' => Piece::Rook,
+        'B' => Piece::Bishop,
+        'N' => Piece::Knight,
+        _ => return Some((input, None)),
+    };
+
+    let input = input[..input.len() - 1].trim_end_matches('=');
+    Some((input, Some(promotion)))
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod san {
+    use base::{board::Board, testing::board};
+
+    use crate::san::parse_san;
+
+    fn parse(fen: &str, input: &str) -> Option<String> {
+        parse_san(&board(fen), input).map(|mov| mov.to_string())
+    }
+
+    #[test]
+    fn pawn_and_piece_moves() {
+        assert_eq!(parse(Board::STARTPOS_FEN, "e4"), Some("e2e4".to_string()));
+        assert_eq!(parse(Board::STARTPOS_FEN, "Nf3"), Some("g1f3".to_string()));
+        assert_eq!(parse(Board::STARTPOS_FEN, "Ke2"), None);
+    }
+
+    #[test]
+    fn captures_and_disambiguation() {
+        let fen = "r3k3/8/8/3p4/4P3/8/8/1N1NK3 w - - 0 1";
+        assert_eq!(parse(fen, "exd5"), Some("e4d5".to_string()));
+        assert_eq!(parse(fen, "Nc3"), None);
+        assert_eq!(parse(fen, "Nbc3"), Some("b1c3".to_string()));
+        assert_eq!(parse(fen, "Ndc3+"), Some("d1c3".to_string()));
+    }
+
+    #[test]
+    fn promotions_and_castling() {
+        let fen = "4k3/P7/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(parse(fen, "a8=Q"), Some("a7a8q".to_string()));
+        assert_eq!(parse(fen, "a8N"), Some("a7a8n".to_string()));
+        assert_eq!(parse(fen, "O-O"), Some("e1g1".to_string()));
+        assert_eq!(parse(fen, "O-O-O"), None);
+    }
+}
+
+#[cfg(test)]
+mod dataset {
+    use crate::dataset::{parse_epd, parse_pgn};
+
+    #[test]
+    fn epd_results() {
+        let content = "\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 \"1-0\";\n\
+            # A comment\n\
+            4k3/8/8/8/8/8/8/4K3 w - - 0 1 [0.5]\n\
+            4k3/8/8/8/8/8/4q3/4K3 w - - 0-1\n\
+            4k3/8/8/8/8/q7/8/4K3 w - - 0-1\n";
+
+        // The position where white is in check is skipped.
+        let entries = parse_epd(content).unwrap();
+        let results = entries
+            .iter()
+            .map(|entry| entry.result)
+            .collect::<Vec<f64>>();
+        assert_eq!(results, vec![1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn datagen_results() {
+        let content = "\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 | -35 | 0.5\n\
+            4k3/8/8/8/8/8/8/4K3 w - - 12 40 | 0 | 1.0\n";
+
+        let entries = parse_epd(content).unwrap();
+        let results = entries
+            .iter()
+            .map(|entry| entry.result)
+            .collect::<Vec<f64>>();
+        assert_eq!(results, vec![0.5, 1.0]);
+        assert_eq!(entries[1].board.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 12 40");
+    }
+
+    #[test]
+    fn epd_without_result() {
+        assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn pgn_games() {
+        let content = r#"[Event "First"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3. Bb5 a6 1-0
+
+[Event "Second"]
+[Result "*"]
+
+1. d4 d5 *
+
+[Event "Third"]
+[FEN "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"]
+[Result "1/2-1/2"]
+
+1. O-O-O Kf7 1/2-1/2
+"#;
+
+        // The unfinished game is skipped and the first two plies of every
+        // game are not used.
+        let entries = parse_pgn(content, 2).unwrap();
+        let results = entries
+            .iter()
+            .map(|entry| entry.result)
+            .collect::<Vec<f64>>();
+        assert_eq!(results, vec![1.0, 1.0, 1.0, 1.0, 1.0, 0.5]);
+        assert_eq!(
+            entries[4].board.to_fen(),
+            "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tuner {
+    use engine::evaluation::parameters::Parameters;
+
+    use crate::{
+        dataset::parse_epd,
+        tuner::{sigmoid, Tuner, Weight},
+    };
+
+    #[test]
+    fn sigmoid_is_symmetric() {
+        assert_eq!(sigmoid(0, 1.0), 0.5);
+        assert!((sigmoid(400, 1.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!((sigmoid(150, 1.3) + sigmoid(-150, 1.3) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skips_unused_weights() {
+        let names = [
+            "midgame_piece_values",
+            "passed_endgame",
+            "midgame_pawn_table",
+            "bishop_pair",
+        ];
+        let tuner = Tuner::new(Vec::new(), Parameters::default(), &names, false, 1);
+        assert_eq!(tuner.weights().len(), 6 + 6 + 48 + 1);
+
+        let weights = tuner.weights();
+        assert!(!weights.contains(&Weight {
+            name: "midgame_piece_values",
+            index: 0
+        }));
+        assert!(!weights.contains(&Weight {
+            name: "passed_endgame",
+            index: 7
+        }));
+        assert!(!weights.contains(&Weight {
+            name: "midgame_pawn_table",
+            index: 3
+        }));
+        assert!(weights.contains(&Weight {
+            name: "midgame_pawn_table",
+            index: 8
+        }));
+    }
+
+    #[test]
+    fn local_search_reduces_error() {
+        // White is a knight up in all of the positions, but only draws.
+        let content = "\
+            4k3/pppp4/8/8/8/8/PPPP4/1N2K3 w - - [0.5]\n\
+            4k3/pppp4/8/8/8/8/PPPP4/1N2K3 b - - [0.5]\n\
+            4k3/1ppp4/8/8/8/2N5/1PPP4/4K3 w - - [0.5]\n";
+        let entries = parse_epd(content).unwrap();
+
+        let mut tuner = Tuner::new(
+            entries,
+            Parameters::default(),
+            &["endgame_piece_values"],
+            true,
+            2,
+        );
+        assert_eq!(tuner.weights().len(), 6);
+
+        let error = tuner.error();
+        let new_error = tuner.local_search_pass(error).unwrap();
+        assert!(new_error < error);
+
+        let knight = tuner.parameters().get("endgame_piece_values").unwrap()[2];
+        assert!(knight < Parameters::DEFAULT.get("endgame_piece_values").unwrap()[2]);
+    }
+}
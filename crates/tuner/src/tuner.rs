@@ -0,0 +1,240 @@
+use std::thread;
+
+use base::board::{color::Color, piece::Piece, Board};
+use engine::{
+    evaluation::{evaluate_with, parameters::Parameters},
+    generator::{CaptureMoves, MoveGenerator},
+};
+
+use crate::{dataset::Entry, error::TunerError};
+
+// The quiescence search of the tuner doesn't need to find the best move,
+// so it is cut off early to keep the error function fast.
+pub const MAX_QUIESCENCE_PLY: u8 = 8;
+
+// The parameters only change the search and not the static evaluation.
+pub const SEARCH_PARAMETERS: [&str; 2] = ["futility_margins", "delta_margin"];
+
+/// A single value of a parameter, like one square of a piece square table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weight {
+    pub name: &'static str,
+    pub index: usize,
+}
+
+impl Weight {
+    /// The weight is never part of the evaluation, like the value of an
+    /// empty square or a pawn on the first or last rank, so tuning it would
+    /// only waste time.
+    pub fn is_unused(&self) -> bool {
+        match self.name {
+            "midgame_piece_values" | "endgame_piece_values" => self.index == Piece::None.index(),
+            "connected_midgame" | "connected_endgame" | "passed_midgame" | "passed_endgame"
+            | "passed_king_weight" => self.index == 0 || self.index == 7,
+            "midgame_pawn_table" | "endgame_pawn_table" => self.index < 8 || self.index >= 56,
+            _ => false,
+        }
+    }
+}
+
+/// Optimizes the evaluation parameters with the Texel tuning method. The
+/// error is the mean squared difference between the game results and the
+/// win probability that follows from the evaluation.
+/// Source: https://www.chessprogramming.org/Texel%27s_Tuning_Method
+pub struct Tuner {
+    entries: Vec<Entry>,
+    parameters: Parameters,
+    weights: Vec<Weight>,
+    quiescence: bool,
+    threads: usize,
+    k: f64,
+}
+
+impl Tuner {
+    pub fn new(
+        entries: Vec<Entry>,
+        parameters: Parameters,
+        names: &[&'static str],
+        quiescence: bool,
+        threads: usize,
+    ) -> Self {
+        let mut weights = Vec::new();
+        for &name in names {
+            let count = parameters.get(name).map_or(0, |values| values.len());
+            weights.extend(
+                (0..count)
+                    .map(|index| Weight { name, index })
+                    .filter(|weight| !weight.is_unused()),
+            );
+        }
+
+        Self {
+            entries,
+            parameters,
+            weights,
+            quiescence,
+            threads: threads.max(1),
+            k: 1.0,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
+    #[inline(always)]
+    pub fn weights(&self) -> &[Weight] {
+        &self.weights
+    }
+
+    /// Finds the scaling constant of the sigmoid that fits the current
+    /// evaluation best, by narrowing down the step size one digit at a time.
+    pub fn optimize_k(&mut self) -> f64 {
+        let scores = self.scores(&self.parameters.clone());
+
+        let mut best_k = self.k;
+        let mut best_error = error_of(&self.entries, &scores, best_k);
+        let mut step = 1.0;
+
+        for _ in 0..4 {
+            let start = (best_k - step * 10.0).max(step);
+            for index in 0..=20 {
+                let k = start + step * index as f64;
+                let error = error_of(&self.entries, &scores, k);
+                if error < best_error {
+                    best_error = error;
+                    best_k = k;
+                }
+            }
+
+            step /= 10.0;
+        }
+
+        self.k = best_k;
+        best_k
+    }
+
+    pub fn error(&mut self) -> f64 {
+        let scores = self.scores(&self.parameters.clone());
+        error_of(&self.entries, &scores, self.k)
+    }
+
+    /// Runs a single pass of the local search over all the weights. Every
+    /// weight is moved by one in both directions and the change is kept if
+    /// it reduces the error. Returns the error after the pass.
+    pub fn local_search_pass(&mut self, best_error: f64) -> Result<f64, TunerError> {
+        let mut best_error = best_error;
+
+        for weight in self.weights.clone() {
+            for delta in [1, -1] {
+                let mut parameters = self.parameters.clone();
+                adjust(&mut parameters, weight, delta)?;
+
+                let scores = self.scores(&parameters);
+                let error = error_of(&self.entries, &scores, self.k);
+                if error < best_error {
+                    best_error = error;
+                    self.parameters = parameters;
+                    break;
+                }
+            }
+        }
+
+        Ok(best_error)
+    }
+
+    /// Evaluates all the positions from the view of white, split over the
+    /// threads.
+    fn scores(&mut self, parameters: &Parameters) -> Vec<i32> {
+        let chunk_size = self.entries.len().div_ceil(self.threads).max(1);
+        let quiescence = self.quiescence;
+
+        thread::scope(|scope| {
+            let handles = self
+                .entries
+                .chunks_mut(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter_mut()
+                            .map(|entry| score(&mut entry.board, parameters, quiescence))
+                            .collect::<Vec<i32>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+fn adjust(parameters: &mut Parameters, weight: Weight, delta: i32) -> Result<(), TunerError> {
+    let mut values = parameters.get(weight.name).unwrap_or_default();
+    values[weight.index] += delta;
+    parameters.set(weight.name, &values)?;
+    Ok(())
+}
+
+/// The evaluation of the board from the view of white.
+pub fn score(board: &mut Board, parameters: &Parameters, quiescence: bool) -> i32 {
+    let eval = match quiescence {
+        true => quiescence_search(board, parameters, -i32::MAX, i32::MAX, 0),
+        false => evaluate_with(board, board.active(), parameters),
+    };
+
+    match board.active() {
+        Color::White => eval,
+        Color::Black => -eval,
+    }
+}
+
+/// A plain quiescence search over all the captures, without any of the
+/// pruning of the engine, as only the score of a quiet position is needed.
+pub fn quiescence_search(
+    board: &mut Board,
+    parameters: &Parameters,
+    mut alpha: i32,
+    beta: i32,
+    ply: u8,
+) -> i32 {
+    let stand_pat = evaluate_with(board, board.active(), parameters);
+    if ply >= MAX_QUIESCENCE_PLY || stand_pat >= beta {
+        return stand_pat;
+    }
+
+    alpha = alpha.max(stand_pat);
+
+    for mov in MoveGenerator::<CaptureMoves>::new(board) {
+        board.make(mov);
+        let score = -quiescence_search(board, parameters, -beta, -alpha, ply + 1);
+        board.unmake(mov);
+
+        if score >= beta {
+            return score;
+        }
+
+        alpha = alpha.max(score);
+    }
+
+    alpha
+}
+
+/// The expected result of the game from the view of white.
+#[inline(always)]
+pub fn sigmoid(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+pub fn error_of(entries: &[Entry], scores: &[i32], k: f64) -> f64 {
+    let sum = entries
+        .iter()
+        .zip(scores)
+        .map(|(entry, &score)| (entry.result - sigmoid(score, k)).powi(2))
+        .sum::<f64>();
+
+    sum / entries.len() as f64
+}
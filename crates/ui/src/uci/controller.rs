@@ -1,39 +1,56 @@
-use std::{
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread::{self, JoinHandle},
-};
+use std::{env, sync::Arc};
 
-use crossbeam_channel::{select, Receiver, Sender};
+use crossbeam_channel::{never, select, Receiver};
+use rand::Rng;
 
 use base::{
     board::{color::Color, Board},
+    nnue::network::Network,
     polyglot::parser::PolyglotBook,
     r#move::Move,
     zobrist::ZobristHasher,
 };
 use engine::{
-    evaluation::evaluate,
+    evaluation::{evaluate_with, nnue_evaluation, parameters::Parameters, trace::EvaluationTrace},
     generator::{AllMoves, MoveGenerator},
-    hashtable::TranspositionTable,
     search::{
-        communication::{BestMove, Info, Score, SearchCommand},
-        error::SearchError,
-        search, TimeFrame,
+        communication::{BestMove, Info, Score, ScoreBound, SearchCommand},
+        engine::{Engine, SearchHandle},
+        limits::SearchLimits,
+        skill::{Skill, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, MIN_SKILL_LEVEL},
+        CURRMOVE_DELAY, MAX_MATE_MOVES,
     },
+    tablebase::Tablebase,
 };
 
 use super::{
-    error::{OptionValueMissing, UCIError},
+    error::{OptionValueMissing, UCIError, UnknownOption},
     parser::{DebugCommand, GoCommand, PositionCommand, SetOptionCommand, UCICommand},
 };
 
+#[cfg(feature = "trace")]
+use super::parser::{TraceCommand, TraceFormat};
+#[cfg(feature = "trace")]
+use engine::search::trace::trace_search;
+
 pub const DEFAULLT_BOOK: &[u8; 50032] = include_bytes!("../../../../books/perfect/Perfect2023.bin");
 pub const DEFAULT_CACHE_SIZE: usize = 16;
 pub const DEFAULT_OWN_BOOK: bool = true;
 pub const DEFAULT_THREADS: usize = 1;
+pub const DEFAULT_LIMIT_STRENGTH: bool = false;
+pub const DEFAULT_ELO: u16 = MAX_ELO;
+pub const DEFAULT_SKILL_LEVEL: u8 = MAX_SKILL_LEVEL;
+pub const DEFAULT_CONTEMPT: i32 = 0;
+pub const MIN_CONTEMPT: i32 = -100;
+pub const MAX_CONTEMPT: i32 = 100;
+pub const DEFAULT_ANALYSE_MODE: bool = false;
+pub const DEFAULT_EVAL_FILE: &str = "<empty>";
+pub const DEFAULT_PARAM_FILE: &str = "<empty>";
+pub const DEFAULT_USE_NNUE: bool = false;
+pub const DEFAULT_SYZYGY_PATH: &str = "<empty>";
+pub const MAX_CURRMOVE_DELAY: u128 = 60_000;
+pub const MIN_PARAMETER: i32 = -10_000;
+pub const MAX_PARAMETER: i32 = 10_000;
 
 pub const LICHESS_ANALYSIS_BASE: &str = "https://lichess.org/analysis";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -42,26 +59,27 @@ pub const NAME: &str = env!("CARGO_PKG_NAME");
 
 pub struct UCIController {
     uci_receiver: Receiver<UCICommand>,
-    cache: Arc<TranspositionTable>,
+    engine: Engine,
     hasher: ZobristHasher,
-    search_receiver: Receiver<SearchCommand>,
-    search_sender: Sender<SearchCommand>,
-    search_handle: Option<JoinHandle<Result<(), SearchError>>>,
-    search_running: Arc<AtomicBool>,
-    book: Arc<PolyglotBook>,
-    max_threads: usize,
+    search_handle: Option<SearchHandle>,
     own_book: bool,
+    limit_strength: bool,
+    elo: u16,
+    skill_level: u8,
+    contempt: i32,
+    analyse_mode: bool,
+    parameters: Parameters,
+    network: Option<Arc<Network>>,
+    use_nnue: bool,
     board: Board,
     debug: bool,
 }
 
 impl UCIController {
     pub fn new(uci_receiver: Receiver<UCICommand>) -> Result<Self, UCIError> {
-        let (search_sender, search_receiver) = crossbeam_channel::unbounded();
-
-        let cache = TranspositionTable::size(DEFAULT_CACHE_SIZE);
-        let book = PolyglotBook::parse(DEFAULLT_BOOK)?;
-        let search_running = AtomicBool::new(false);
+        let mut engine = Engine::new(DEFAULT_CACHE_SIZE);
+        engine.set_book(Some(PolyglotBook::parse(DEFAULLT_BOOK)?));
+        engine.set_threads(DEFAULT_THREADS);
 
         let mut rand = rand::thread_rng();
         let hasher = ZobristHasher::random(&mut rand);
@@ -70,15 +88,18 @@ impl UCIController {
 
         Ok(Self {
             uci_receiver,
-            cache: Arc::new(cache),
+            engine,
             hasher,
             board,
-            search_receiver,
-            search_sender,
-            search_running: Arc::new(search_running),
-            book: Arc::new(book),
-            max_threads: DEFAULT_THREADS,
             own_book: DEFAULT_OWN_BOOK,
+            limit_strength: DEFAULT_LIMIT_STRENGTH,
+            elo: DEFAULT_ELO,
+            skill_level: DEFAULT_SKILL_LEVEL,
+            contempt: DEFAULT_CONTEMPT,
+            analyse_mode: DEFAULT_ANALYSE_MODE,
+            parameters: Parameters::default(),
+            network: None,
+            use_nnue: DEFAULT_USE_NNUE,
             search_handle: None,
             debug: false,
         })
@@ -86,6 +107,12 @@ impl UCIController {
 
     pub fn start(&mut self) -> Result<(), UCIError> {
         loop {
+            // Without a running search, only the UCI commands are received.
+            let search_receiver = match &self.search_handle {
+                Some(handle) => handle.receiver().clone(),
+                None => never(),
+            };
+
             select! {
                 recv(self.uci_receiver) -> command => {
                     match command {
@@ -97,10 +124,10 @@ impl UCIController {
                         Err(error) => panic!("Error in the UCI receiver {}", error)
                     }
                 }
-                recv(self.search_receiver) -> command => {
+                recv(search_receiver) -> command => {
                     match command {
                         Ok(command) => self.handle_search(command)?,
-                        Err(error) => panic!("Error in the search receiver {}", error)
+                        Err(_) => self.finish_search()?,
                     }
                 }
             }
@@ -122,7 +149,10 @@ impl UCIController {
             UCICommand::Stop => self.received_stop(),
             UCICommand::Quit => self.received_quit(),
             UCICommand::Show => self.received_show(),
+            UCICommand::Eval => self.received_eval(),
             UCICommand::UCI => self.received_uci(),
+            #[cfg(feature = "trace")]
+            UCICommand::Trace(command) => self.received_trace(command),
         }
     }
 
@@ -133,44 +163,40 @@ impl UCIController {
         }
     }
 
+    /// Waits for the running search and sends the commands it has left. A
+    /// failed search is only reported, so the engine keeps running.
+    fn finish_search(&mut self) -> Result<(), UCIError> {
+        let handle = match self.search_handle.take() {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+
+        let receiver = handle.receiver().clone();
+        let result = handle.wait();
+        for command in receiver.try_iter() {
+            self.handle_search(command)?;
+        }
+
+        if let Err(error) = result {
+            println!("info string the search failed: {}", error);
+        }
+
+        Ok(())
+    }
+
     fn uci_position(&mut self, command: PositionCommand) -> Result<(), UCIError> {
         self.board = Board::from_str(&command.fen, self.hasher.clone())?;
+        self.board.set_network(self.active_network());
         self.board.make_moves(&command.moves)?;
         Ok(())
     }
 
     fn received_go(&mut self, command: GoCommand) -> Result<(), UCIError> {
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
-        }
+        self.finish_search()?;
 
-        let mut infinite = command.infinite;
-        let time_frame = match command.move_time {
-            Some(time) => TimeFrame::new(time),
-            None => {
-                let time_left = match self.board.active() {
-                    Color::White => match command.white_time {
-                        Some(time) => time,
-                        None => {
-                            infinite = true;
-                            u128::MAX
-                        }
-                    },
-                    Color::Black => match command.black_time {
-                        Some(time) => time,
-                        None => {
-                            infinite = true;
-                            u128::MAX
-                        }
-                    },
-                };
-                let increment = match self.board.active() {
-                    Color::White => command.white_increment.unwrap_or(0),
-                    Color::Black => command.black_increment.unwrap_or(0),
-                };
-
-                TimeFrame::estimate(time_left, increment)
-            }
+        let (time_left, increment) = match self.board.active() {
+            Color::White => (command.white_time, command.white_increment),
+            Color::Black => (command.black_time, command.black_increment),
         };
 
         let mut moves = Vec::with_capacity(command.search_moves.len());
@@ -180,31 +206,31 @@ impl UCIController {
             moves.push(mov);
         }
 
-        let running = self.search_running.clone();
-        let sender = self.search_sender.clone();
-        let max_threads = self.max_threads;
-        let board = self.board.clone();
-        let own_book = self.own_book;
-        let book = self.book.clone();
-
-        let cache = self.cache.clone();
-        let handle = thread::spawn(move || {
-            let book = if own_book { Some(book.as_ref()) } else { None };
-            search(
-                board,
-                book,
-                cache,
-                sender,
-                running,
-                time_frame,
-                command.nodes,
-                command.depth,
-                moves,
-                infinite,
-                max_threads,
-            )
+        // The plies of the mate have to fit into the maximum search depth.
+        let mate = command.mate.map(|mate| {
+            if mate > MAX_MATE_MOVES {
+                println!("info string mate is limited to {} moves", MAX_MATE_MOVES);
+            }
+
+            mate.min(MAX_MATE_MOVES)
         });
-        self.search_handle = Some(handle);
+
+        let limits = SearchLimits {
+            depth: command.depth,
+            nodes: command.nodes,
+            move_time: command.move_time,
+            time_left,
+            increment: increment.unwrap_or(0),
+            mate,
+            infinite: command.infinite,
+            moves,
+        };
+
+        self.engine.set_contempt(self.contempt());
+        self.engine.set_skill(self.skill());
+        self.engine.set_parameters(self.parameters.clone());
+
+        self.search_handle = Some(self.engine.start(&self.board, limits));
 
         Ok(())
     }
@@ -231,6 +257,56 @@ impl UCIController {
             "option name OwnBook type check default {}",
             DEFAULT_OWN_BOOK
         );
+        println!(
+            "option name UCI_LimitStrength type check default {}",
+            DEFAULT_LIMIT_STRENGTH
+        );
+        // The elo is not calibrated, it is just mapped onto the skill levels.
+        println!(
+            "option name UCI_Elo type spin default {} min {} max {}",
+            DEFAULT_ELO, MIN_ELO, MAX_ELO
+        );
+        println!(
+            "option name Skill Level type spin default {} min {} max {}",
+            DEFAULT_SKILL_LEVEL, MIN_SKILL_LEVEL, MAX_SKILL_LEVEL
+        );
+        println!(
+            "option name Contempt type spin default {} min {} max {}",
+            DEFAULT_CONTEMPT, MIN_CONTEMPT, MAX_CONTEMPT
+        );
+        println!(
+            "option name UCI_AnalyseMode type check default {}",
+            DEFAULT_ANALYSE_MODE
+        );
+        println!(
+            "option name UseNNUE type check default {}",
+            DEFAULT_USE_NNUE
+        );
+        println!(
+            "option name EvalFile type string default {}",
+            DEFAULT_EVAL_FILE
+        );
+        println!(
+            "option name ParamFile type string default {}",
+            DEFAULT_PARAM_FILE
+        );
+        println!(
+            "option name SyzygyPath type string default {}",
+            DEFAULT_SYZYGY_PATH
+        );
+        println!(
+            "option name CurrMoveDelay type spin default {} min 0 max {}",
+            CURRMOVE_DELAY, MAX_CURRMOVE_DELAY
+        );
+
+        // The weights of the evaluation that consist of a single value can
+        // be changed directly, which makes it easy to test them.
+        for (name, value) in Parameters::DEFAULT.scalars() {
+            println!(
+                "option name {} type spin default {} min {} max {}",
+                name, value, MIN_PARAMETER, MAX_PARAMETER
+            );
+        }
 
         println!("uciok");
         Ok(())
@@ -241,15 +317,26 @@ impl UCIController {
             "Hash" => self.set_hash_size(command.value),
             "Threads" => self.set_threads(command.value),
             "OwnBook" => self.set_own_book(command.value),
+            "UCI_LimitStrength" => self.set_limit_strength(command.value),
+            "UCI_Elo" => self.set_elo(command.value),
+            "Skill Level" => self.set_skill_level(command.value),
+            "Contempt" => self.set_contempt(command.value),
+            "UCI_AnalyseMode" => self.set_analyse_mode(command.value),
+            "CurrMoveDelay" => self.set_currmove_delay(command.value),
             "Clear Hash" => self.clear_hash(),
-            _ => todo!(),
+            "UseNNUE" => report_option("UseNNUE", self.set_use_nnue(command.value)),
+            "EvalFile" => report_option("EvalFile", self.set_eval_file(command.value)),
+            "ParamFile" => report_option("ParamFile", self.set_param_file(command.value)),
+            "SyzygyPath" => report_option("SyzygyPath", self.set_syzygy_path(command.value)),
+            name if is_scalar_parameter(name) => {
+                report_option(name, self.set_parameter(name, command.value))
+            }
+            name => report_option(name, Err(UnknownOption::new(name).into())),
         }
     }
 
     fn set_hash_size(&mut self, value: Option<String>) -> Result<(), UCIError> {
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
-        }
+        self.finish_search()?;
 
         let value = match value {
             Some(value) => value,
@@ -257,15 +344,13 @@ impl UCIController {
         };
         let size = value.parse::<usize>()?;
 
-        self.cache = Arc::new(TranspositionTable::size(size));
+        self.engine.set_cache_size(size);
 
         Ok(())
     }
 
     fn set_threads(&mut self, value: Option<String>) -> Result<(), UCIError> {
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
-        }
+        self.finish_search()?;
 
         let value = match value {
             Some(value) => value,
@@ -273,7 +358,7 @@ impl UCIController {
         };
 
         let threads = value.parse::<usize>()?;
-        self.max_threads = threads;
+        self.engine.set_threads(threads);
 
         Ok(())
     }
@@ -285,17 +370,196 @@ impl UCIController {
         };
 
         let own_book = value.parse::<bool>()?;
+        let book = match own_book {
+            true => Some(PolyglotBook::parse(DEFAULLT_BOOK)?),
+            false => None,
+        };
+
+        self.engine.set_book(book);
         self.own_book = own_book;
 
         Ok(())
     }
 
-    fn clear_hash(&mut self) -> Result<(), UCIError> {
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
+    fn set_limit_strength(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let limit_strength = value.parse::<bool>()?;
+        self.limit_strength = limit_strength;
+
+        Ok(())
+    }
+
+    fn set_elo(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let elo = value.parse::<u16>()?;
+        self.elo = elo.clamp(MIN_ELO, MAX_ELO);
+
+        Ok(())
+    }
+
+    fn set_skill_level(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let skill_level = value.parse::<u8>()?;
+        self.skill_level = skill_level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL);
+
+        Ok(())
+    }
+
+    fn set_contempt(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let contempt = value.parse::<i32>()?;
+        self.contempt = contempt.clamp(MIN_CONTEMPT, MAX_CONTEMPT);
+
+        Ok(())
+    }
+
+    fn set_currmove_delay(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let currmove_delay = value.parse::<u128>()?;
+        self.engine
+            .set_currmove_delay(currmove_delay.min(MAX_CURRMOVE_DELAY));
+
+        Ok(())
+    }
+
+    fn set_analyse_mode(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let analyse_mode = value.parse::<bool>()?;
+        self.analyse_mode = analyse_mode;
+
+        Ok(())
+    }
+
+    fn set_use_nnue(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let use_nnue = value.parse::<bool>()?;
+        self.use_nnue = use_nnue;
+        self.board.set_network(self.active_network());
+
+        Ok(())
+    }
+
+    /// Loads the network from the file. An empty value removes it, so the
+    /// handcrafted evaluation is used again.
+    fn set_eval_file(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        self.network = match value {
+            Some(path) if path != DEFAULT_EVAL_FILE => Some(Arc::new(Network::from_file(path)?)),
+            _ => None,
+        };
+        self.board.set_network(self.active_network());
+
+        Ok(())
+    }
+
+    /// Loads the evaluation parameters from the file. An empty value resets
+    /// them to the compiled-in defaults.
+    fn set_param_file(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        self.parameters = match value {
+            Some(path) if path != DEFAULT_PARAM_FILE => Parameters::load(path)?,
+            _ => Parameters::default(),
+        };
+
+        Ok(())
+    }
+
+    /// Loads the tablebases of all directories in the path, which are split
+    /// like the `PATH` variable. An empty value removes them.
+    fn set_syzygy_path(&mut self, value: Option<String>) -> Result<(), UCIError> {
+        let tablebase = match value {
+            Some(path) if path != DEFAULT_SYZYGY_PATH => {
+                let mut tablebase = Tablebase::new();
+                for directory in env::split_paths(&path) {
+                    tablebase.add_directory(directory)?;
+                }
+
+                Some(tablebase)
+            }
+            _ => None,
+        };
+        self.engine.set_tablebase(tablebase);
+
+        Ok(())
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Option<String>) -> Result<(), UCIError> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(OptionValueMissing.into()),
+        };
+
+        let value = value.parse::<i32>()?;
+        self.parameters
+            .set(name, &[value.clamp(MIN_PARAMETER, MAX_PARAMETER)])?;
+
+        Ok(())
+    }
+
+    /// The network is only used if it is enabled and loaded, otherwise the
+    /// handcrafted evaluation is the fallback.
+    fn active_network(&self) -> Option<Arc<Network>> {
+        match self.use_nnue {
+            true => self.network.clone(),
+            false => None,
+        }
+    }
+
+    /// While analysing, both sides should be evaluated the same way. So
+    /// a draw is scored as equal, independent of the side to move.
+    fn contempt(&self) -> i32 {
+        if self.analyse_mode {
+            0
+        } else {
+            self.contempt
+        }
+    }
+
+    /// The UCI_Elo takes precedence over the skill level, if the strength
+    /// is limited. As it is uncalibrated, it only roughly matches the
+    /// strength of a rated player.
+    fn skill(&self) -> Option<Skill> {
+        let seed = rand::thread_rng().gen();
+
+        if self.limit_strength {
+            Some(Skill::from_elo(self.elo, seed))
+        } else if self.skill_level < MAX_SKILL_LEVEL {
+            Some(Skill::new(self.skill_level, seed))
+        } else {
+            None
         }
+    }
+
+    fn clear_hash(&mut self) -> Result<(), UCIError> {
+        self.finish_search()?;
 
-        self.cache.clear();
+        self.engine.clear_cache();
 
         Ok(())
     }
@@ -311,23 +575,15 @@ impl UCIController {
     }
 
     fn received_stop(&mut self) -> Result<(), UCIError> {
-        self.search_running.store(false, Ordering::Relaxed);
-
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
+        if let Some(handle) = &self.search_handle {
+            handle.stop();
         }
 
-        Ok(())
+        self.finish_search()
     }
 
     fn received_quit(&mut self) -> Result<(), UCIError> {
-        self.search_running.store(false, Ordering::Relaxed);
-
-        if let Some(handle) = self.search_handle.take() {
-            handle.join().unwrap()?;
-        }
-
-        Ok(())
+        self.received_stop()
     }
 
     pub fn received_show(&mut self) -> Result<(), UCIError> {
@@ -337,10 +593,7 @@ impl UCIController {
         println!("FEN: {}", self.board.to_fen());
         println!("Hash: 0x{:X}", self.board.hash());
 
-        let is_checkmate = move_generator.is_checkmate(&self.board);
-        println!("Checkmate: {}", is_checkmate);
-        let is_stalemate = move_generator.is_stalemate(&self.board);
-        println!("Stalemate: {}", is_stalemate);
+        println!("Outcome: {:?}", self.board.outcome());
 
         println!("Moves {}:", move_generator.len());
         let moves = move_generator
@@ -350,7 +603,7 @@ impl UCIController {
         println!(" - {}", moves);
         println!();
 
-        let evaluation = evaluate(&self.board, self.board.active());
+        let evaluation = evaluate_with(&self.board, self.board.active(), &self.parameters);
         println!("Evaluation for side to move: {}", evaluation);
 
         if let Some(en_passant) = self.board.en_passant() {
@@ -363,20 +616,63 @@ impl UCIController {
         Ok(())
     }
 
+    pub fn received_eval(&mut self) -> Result<(), UCIError> {
+        println!(
+            "{}",
+            EvaluationTrace::with_parameters(&self.board, &self.parameters)
+        );
+
+        if let Some(eval) = nnue_evaluation(&self.board, Color::White) {
+            println!("NNUE evaluation: {}", eval);
+        }
+
+        Ok(())
+    }
+
+    /// Searches the position on this thread and prints its search tree.
+    #[cfg(feature = "trace")]
+    fn received_trace(&mut self, command: TraceCommand) -> Result<(), UCIError> {
+        self.finish_search()?;
+
+        let trace = trace_search(self.board.clone(), command.depth)?;
+
+        let mut stdout = std::io::stdout().lock();
+        match command.format {
+            TraceFormat::Json => trace.write_json(&mut stdout)?,
+            TraceFormat::Graphviz => trace.write_graphviz(&mut stdout)?,
+        }
+
+        Ok(())
+    }
+
     fn received_stats(&mut self) -> Result<(), UCIError> {
-        println!("Max Threads: {}", self.max_threads);
+        let options = self.engine.options();
+        println!("Max Threads: {}", options.threads);
         println!("Own Book: {}", self.own_book);
+        println!("Limit Strength: {}", self.limit_strength);
+        println!("Elo: {}", self.elo);
+        println!("Skill Level: {}", self.skill_level);
+        println!("Contempt: {}", self.contempt);
+        println!("CurrMove Delay: {}", options.currmove_delay);
+        println!("Analyse Mode: {}", self.analyse_mode);
+        println!("Use NNUE: {}", self.use_nnue);
+        println!("Network Loaded: {}", self.network.is_some());
+        if let Some(tablebase) = &options.tablebase {
+            println!("Tablebases: {}", tablebase.table_count());
+            println!("Tablebase Pieces: {}", tablebase.max_pieces());
+        }
         println!("Debug: {}", self.debug);
 
+        let cache = self.engine.cache();
         println!("Cache:");
-        println!(" - Capacity: {}", self.cache.capacity());
-        let overwrite_rate = self.cache.overwrites() as f64 / self.cache.stores() as f64;
+        println!(" - Capacity: {}", cache.capacity());
+        let overwrite_rate = cache.overwrites() as f64 / cache.stores() as f64;
         println!(" - Overwrite Rate: {}", overwrite_rate);
-        let insertion_rate = self.cache.inserted() as f64 / self.cache.stores() as f64;
+        let insertion_rate = cache.inserted() as f64 / cache.stores() as f64;
         println!(" - Insertion Rate: {}", insertion_rate);
-        let hit_rate = self.cache.hits() as f64 / self.cache.probes() as f64;
+        let hit_rate = cache.hits() as f64 / cache.probes() as f64;
         println!(" - Hit Rate: {}", hit_rate);
-        let miss_rate = self.cache.misses() as f64 / self.cache.probes() as f64;
+        let miss_rate = cache.misses() as f64 / cache.probes() as f64;
         println!(" - Miss Rate: {}", miss_rate);
 
         Ok(())
@@ -406,7 +702,11 @@ impl UCIController {
     }
 
     fn received_bestmove(&mut self, bestmove: BestMove) -> Result<(), UCIError> {
-        println!("bestmove {}", bestmove.mov);
+        match bestmove.ponder {
+            Some(ponder) => println!("bestmove {} ponder {}", bestmove.mov, ponder),
+            None => println!("bestmove {}", bestmove.mov),
+        }
+
         Ok(())
     }
 
@@ -421,6 +721,10 @@ impl UCIController {
             }
         }
 
+        if let Some(multipv) = info.multipv {
+            print!("multipv {} ", multipv);
+        }
+
         if let Some(time) = info.time {
             print!("time {} ", time);
         }
@@ -431,8 +735,14 @@ impl UCIController {
 
         if let Some(score) = info.score {
             match score {
-                Score::Centipawns(cp) => print!("score cp {} ", cp),
-                Score::Mate(mate) => print!("score mate {} ", mate),
+                Score::Centipawns(cp, _) => print!("score cp {} ", cp),
+                Score::Mate(mate, _) => print!("score mate {} ", mate),
+            }
+
+            match score.bound() {
+                ScoreBound::Lower => print!("lowerbound "),
+                ScoreBound::Upper => print!("upperbound "),
+                ScoreBound::Exact => {}
             }
         }
 
@@ -448,6 +758,10 @@ impl UCIController {
             print!("hashfull {} ", hashfull);
         }
 
+        if let Some(tbhits) = info.tbhits {
+            print!("tbhits {} ", tbhits);
+        }
+
         if let Some(nps) = info.nps {
             print!("nps {} ", nps);
         }
@@ -470,3 +784,21 @@ impl UCIController {
         Ok(())
     }
 }
+
+/// Reports an option that could not be set, like a file that does not
+/// exist. The previous value is kept and the engine goes on reading
+/// commands, instead of stopping over a typo in the GUI.
+fn report_option(name: &str, result: Result<(), UCIError>) -> Result<(), UCIError> {
+    if let Err(error) = result {
+        println!("info string could not set {}: {}", name, error);
+    }
+
+    Ok(())
+}
+
+fn is_scalar_parameter(name: &str) -> bool {
+    Parameters::DEFAULT
+        .scalars()
+        .iter()
+        .any(|(scalar, _)| *scalar == name)
+}
@@ -1,8 +1,13 @@
 use crossbeam_channel::{RecvError, SendError};
 use thiserror::Error;
 
-use base::{board::error::BoardError, polyglot::error::PolyglotError, r#move::error::MoveError};
-use engine::search::error::SearchError;
+use base::{
+    board::error::BoardError, nnue::error::NetworkError, polyglot::error::PolyglotError,
+    r#move::error::MoveError,
+};
+use engine::{
+    evaluation::error::ParameterError, search::error::SearchError, tablebase::error::TablebaseError,
+};
 
 use super::parser::UCICommand;
 
@@ -13,16 +18,28 @@ pub enum UCIError {
     IOError(#[from] std::io::Error),
     NotEnoughArguments(#[from] NotEnoughArguments),
     OptionValueMissing(#[from] OptionValueMissing),
+    UnknownOption(#[from] UnknownOption),
     InvalidArgument(#[from] InvalidArgument),
     ParseIntError(#[from] std::num::ParseIntError),
     ParseBoolError(#[from] std::str::ParseBoolError),
     BoardError(#[from] BoardError),
     PolyglotError(#[from] PolyglotError),
-    SendCommandError(#[from] SendError<UCICommand>),
+    SendCommandError(Box<SendError<UCICommand>>),
     RecvError(#[from] RecvError),
     SearchError(#[from] SearchError),
     FmtError(#[from] std::fmt::Error),
     MoveError(#[from] MoveError),
+    ParameterError(#[from] ParameterError),
+    NetworkError(#[from] NetworkError),
+    TablebaseError(#[from] TablebaseError),
+}
+
+// The command is returned with the error, which would make every result of
+// the controller as large as the biggest command.
+impl From<SendError<UCICommand>> for UCIError {
+    fn from(error: SendError<UCICommand>) -> Self {
+        Self::SendCommandError(Box::new(error))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -66,3 +83,15 @@ impl InvalidArgument {
 #[derive(Debug, Error)]
 #[error("the option value is missing")]
 pub struct OptionValueMissing;
+
+#[derive(Debug, Error)]
+#[error("the option '{name}' is unknown")]
+pub struct UnknownOption {
+    name: String,
+}
+
+impl UnknownOption {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
@@ -63,7 +63,13 @@ impl UCIParser {
                 UCICommand::Go(result)
             }
             "show" => UCICommand::Show,
+            "eval" => UCICommand::Eval,
             "stats" => UCICommand::Stats,
+            #[cfg(feature = "trace")]
+            "trace" => {
+                let result = TraceCommand::parse(&input, &mut tokens)?;
+                UCICommand::Trace(result)
+            }
             "setoption" => {
                 let result = SetOptionCommand::parse(&input, &mut tokens)?;
                 UCICommand::SetOption(result)
@@ -86,7 +92,10 @@ pub enum UCICommand {
     Stop,
     Quit,
     Show,
+    Eval,
     Analyse,
+    #[cfg(feature = "trace")]
+    Trace(TraceCommand),
 }
 
 #[derive(Default, Debug)]
@@ -202,6 +211,7 @@ pub struct GoCommand {
     pub depth: Option<u8>,
     pub nodes: Option<usize>,
     pub move_time: Option<u128>,
+    pub mate: Option<u8>,
     pub infinite: bool,
 }
 
@@ -222,6 +232,7 @@ impl GoCommand {
                 GoToken::Depth(depth) => result.depth = Some(depth),
                 GoToken::Nodes(nodes) => result.nodes = Some(nodes),
                 GoToken::MoveTime(time) => result.move_time = Some(time),
+                GoToken::Mate(moves) => result.mate = Some(moves),
                 GoToken::Infinite => result.infinite = true,
             }
         }
@@ -242,6 +253,7 @@ pub enum GoToken {
     Depth(u8),
     Nodes(usize),
     MoveTime(u128),
+    Mate(u8),
     Infinite,
 }
 
@@ -258,6 +270,7 @@ impl GoToken {
             "depth" => true,
             "nodes" => true,
             "movetime" => true,
+            "mate" => true,
             "infinite" => true,
             _ => false,
         }
@@ -330,6 +343,13 @@ impl GoToken {
                 let movetime = movetime.parse::<u128>()?;
                 return Ok(GoToken::MoveTime(movetime));
             }
+            "mate" => {
+                let mate = tokens
+                    .next()
+                    .ok_or(NotEnoughArguments::new(command.clone()))?;
+                let mate = mate.parse::<u8>()?;
+                return Ok(GoToken::Mate(mate));
+            }
             "infinite" => return Ok(GoToken::Infinite),
             _ => {
                 return Err(
@@ -404,6 +424,43 @@ impl SetOptionToken {
     }
 }
 
+/// Prints the search tree of the position with `trace <depth> [json|dot]`.
+#[cfg(feature = "trace")]
+#[derive(Debug)]
+pub struct TraceCommand {
+    pub depth: u8,
+    pub format: TraceFormat,
+}
+
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Json,
+    Graphviz,
+}
+
+#[cfg(feature = "trace")]
+impl TraceCommand {
+    pub fn parse(command: &String, tokens: &mut TokenStream) -> Result<Self, UCIError> {
+        let depth = tokens
+            .next()
+            .ok_or(NotEnoughArguments::new(command.clone()))?;
+        let depth = depth.parse::<u8>()?;
+
+        let format = match tokens.next() {
+            None | Some(&"json") => TraceFormat::Json,
+            Some(&"dot") => TraceFormat::Graphviz,
+            Some(token) => {
+                return Err(
+                    InvalidArgument::new(format!("'{}' is not a valid argument", token)).into(),
+                )
+            }
+        };
+
+        Ok(Self { depth, format })
+    }
+}
+
 pub fn collect_until<F>(tokens: &mut TokenStream, condition: F) -> Vec<String>
 where
     F: Fn(&str) -> bool,